@@ -30,3 +30,6 @@ player = Player
 snapshot = Snapshot
 take-snapshot = Take Snapshot
 restore-snapshot = Restore Snapshot
+
+state-checksum = Frame { $frame } checksum: { $checksum }
+state-checksum-mismatch = Checksum mismatch on frame { $frame }
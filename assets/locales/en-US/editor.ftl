@@ -47,3 +47,27 @@ view-offset = Camera Offset [ { $x }, { $y } ]
 cursor-position = Cursor Position [ { $x }, { $y } ]
 view-reset = Reset View
 show-grid = Show Grid
+
+undo = Undo
+redo = Redo
+rename = Rename
+
+paint-tiles = Paint Tiles
+erase-tiles = Erase Tiles
+paint-collision = Paint Collision
+place-element = Place Element
+move-elements = Move Elements
+
+left-edge = Left Edge
+right-edge = Right Edge
+bottom-edge = Bottom Edge
+top-edge = Top Edge
+shift-map = Shift Contents
+
+fill-rect = Fill Rectangle
+flood-fill = Flood Fill
+copy-region = Copy
+paste-region = Paste
+fill-by-tile = Same Tile
+fill-by-collision = Same Collision
+randomize-every-round = Randomize Every Round
@@ -4,4 +4,11 @@ waiting-for-map = Waiting for other player to select map...
 default-maps = Default Maps
 experimental-maps = Experimental Maps
 user-maps = User Maps
-builtin-maps = Builtin Maps
\ No newline at end of file
+builtin-maps = Builtin Maps
+playlists = Playlists
+random-maps = Random Maps
+include-experimental-maps = Include Experimental Maps
+custom-playlist = Custom Playlist ({ $count })
+clear-playlist = Clear
+shuffle-playlist = Shuffle
+add-to-playlist = Add to the custom playlist
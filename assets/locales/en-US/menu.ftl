@@ -22,3 +22,31 @@ quit = Quit
 export = Export
 reload = Reload
 restart = Restart
+save-replay = Save Replay
+export-map = Export Map
+
+# Match results
+round-winner = Player { $player } wins the round!
+round-draw = Nobody survived the round!
+match-winner = Player { $player } wins the match!
+player-score = Player { $player }: { $score }
+results = Results
+rounds-won = Rounds Won
+kills = Kills
+deaths = Deaths
+suicides = Suicides
+items-picked-up = Items Picked Up
+
+# Match events
+round-start = Round { $round }
+kill-feed = P{ $killer } → { $cause } → P{ $victim }
+kill-feed-suicide = { $cause } → P{ $victim }
+item-pickup = P{ $player } picked up { $item }
+death-cause-unknown = ???
+death-cause-bullet = musket
+death-cause-sword = sword
+death-cause-explosion = explosion
+death-cause-stomp = stomp boots
+death-cause-spikes = spikes
+death-cause-crate = crate
+death-cause-out-of-bounds = out of bounds
@@ -20,3 +20,7 @@ connected-and-querying = Connected
 waiting-for-players = Waiting for Players: { $current } / { $total }
 match-ready = Match Ready!
 error = Error
+lobby-code = Lobby Code
+start-match = Start Match
+leave-lobby = Leave Lobby
+player-number = Player { $number }
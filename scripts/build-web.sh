@@ -22,7 +22,7 @@ export CARGO_TARGET_DIR=$target_dir
 
 set -ex
 
-cargo build --target $target $release_arg
+cargo build --bin jumpy --target $target $release_arg
 rm -rf $dist_dir
 mkdir -p $dist_dir
 wasm-bindgen --out-dir $dist_dir --target web --no-typescript $target_dir/$target/$build_kind/jumpy.wasm
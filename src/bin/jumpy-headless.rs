@@ -0,0 +1,155 @@
+//! Runs Jumpy matches without a window or GPU and prints the results.
+//!
+//! ```text
+//! jumpy-headless [--map <name>]... [--frames <count>] [--matches <count>]
+//!                [--difficulty <easy|normal|hard>]...
+//! ```
+//!
+//! Every player in the simulated matches is controlled by the AI. If no `--map` is given, a match
+//! is played on every stable map. The `--difficulty` options are given to the players in order,
+//! repeating the list if there are fewer difficulties than players.
+
+use std::path::Path;
+
+use jumpy::{
+    headless::{load_game, run_match, HeadlessMatch},
+    prelude::*,
+    GameMeta,
+};
+
+/// Command line options for the headless runner.
+struct Args {
+    maps: Vec<String>,
+    frames: u64,
+    matches: u32,
+    difficulties: Vec<AiDifficulty>,
+}
+
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let mut args = Args {
+            maps: Vec::new(),
+            // Two minutes of gameplay.
+            frames: 2 * 60 * FPS as u64,
+            matches: 1,
+            difficulties: Vec::new(),
+        };
+
+        let mut iter = std::env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            let mut value = || {
+                iter.next()
+                    .ok_or_else(|| format!("Missing value for `{arg}`"))
+            };
+            match arg.as_str() {
+                "--map" => args.maps.push(value()?),
+                "--frames" => {
+                    args.frames = value()?
+                        .parse()
+                        .map_err(|e| format!("Invalid frame count: {e}"))?
+                }
+                "--matches" => {
+                    args.matches = value()?
+                        .parse()
+                        .map_err(|e| format!("Invalid match count: {e}"))?
+                }
+                "--difficulty" => args.difficulties.push(value()?.parse()?),
+                other => return Err(format!("Unknown argument `{other}`")),
+            }
+        }
+
+        if args.difficulties.is_empty() {
+            args.difficulties.push(default());
+        }
+
+        Ok(args)
+    }
+}
+
+fn main() {
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}");
+            eprintln!(
+                "Usage: jumpy-headless [--map <name>]... [--frames <count>] [--matches <count>] \
+                [--difficulty <easy|normal|hard>]..."
+            );
+            std::process::exit(2);
+        }
+    };
+
+    let mut game = match load_game(Path::new("assets"), Path::new("packs")) {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+
+    let (maps, players) = {
+        let asset_server = game.shared_resource::<AssetServer>().unwrap();
+        let meta = asset_server.root::<GameMeta>();
+
+        let maps = meta
+            .core
+            .stable_maps
+            .iter()
+            .map(|handle| asset_server.get(*handle).clone())
+            .filter(|map| args.maps.is_empty() || args.maps.iter().any(|x| x == map.name.as_str()))
+            .collect::<Vec<_>>();
+        let players = meta.core.players.clone();
+
+        (maps, players)
+    };
+
+    if maps.is_empty() {
+        eprintln!("No maps matched {:?}", args.maps);
+        std::process::exit(1);
+    }
+
+    let mut match_number = 0;
+    for map in &maps {
+        for _ in 0..args.matches {
+            match_number += 1;
+
+            let report = run_match(
+                &mut game,
+                HeadlessMatch {
+                    map: map.clone(),
+                    player_info: std::array::from_fn(|i| PlayerInput {
+                        active: true,
+                        selected_player: players[i % players.len()],
+                        control_source: None,
+                        ai_difficulty: args.difficulties[i % args.difficulties.len()],
+                        ..default()
+                    }),
+                    max_frames: args.frames,
+                },
+            );
+
+            let winner = report
+                .winner
+                .map(|x| format!("P{}", x + 1))
+                .unwrap_or_else(|| "draw".into());
+            println!(
+                "match {match_number}: map \"{}\", winner: {winner}, frames: {}, kills: {}",
+                report.map_name,
+                report.frames_played,
+                report.kills.len()
+            );
+            for kill in &report.kills {
+                let killer = kill
+                    .killer
+                    .map(|x| format!(" by P{}", x + 1))
+                    .unwrap_or_default();
+                println!(
+                    "  frame {:>6}: P{} killed{killer} ({:?})",
+                    kill.frame,
+                    kill.player + 1,
+                    kill.cause
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Checks Jumpy maps for problems and prints them.
+//!
+//! ```text
+//! jumpy-map-lint [--map <name>]... [--experimental]
+//! ```
+//!
+//! If no `--map` is given, every stable map is checked, and every experimental map too when
+//! `--experimental` is passed. Maps given with `--map` may be stable or experimental. Exits with
+//! a non-zero status if any problems were found.
+
+use std::path::Path;
+
+use jumpy::{headless::load_game, prelude::*, GameMeta};
+
+/// Command line options for the map linter.
+struct Args {
+    maps: Vec<String>,
+    experimental: bool,
+}
+
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let mut args = Args {
+            maps: Vec::new(),
+            experimental: false,
+        };
+
+        let mut iter = std::env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--map" => args.maps.push(
+                    iter.next()
+                        .ok_or_else(|| format!("Missing value for `{arg}`"))?,
+                ),
+                "--experimental" => args.experimental = true,
+                other => return Err(format!("Unknown argument `{other}`")),
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+fn main() {
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}");
+            eprintln!("Usage: jumpy-map-lint [--map <name>]... [--experimental]");
+            std::process::exit(2);
+        }
+    };
+
+    let game = match load_game(Path::new("assets"), Path::new("packs")) {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+
+    let asset_server = game.shared_resource::<AssetServer>().unwrap();
+    let meta = asset_server.root::<GameMeta>();
+    let experimental_maps = if args.experimental || !args.maps.is_empty() {
+        &meta.core.experimental_maps[..]
+    } else {
+        &[]
+    };
+    let maps = meta
+        .core
+        .stable_maps
+        .iter()
+        .chain(experimental_maps)
+        .map(|handle| asset_server.get(*handle).clone())
+        .filter(|map| args.maps.is_empty() || args.maps.iter().any(|x| x == map.name.as_str()))
+        .collect::<Vec<_>>();
+
+    if maps.is_empty() {
+        eprintln!("No maps matched {:?}", args.maps);
+        std::process::exit(1);
+    }
+
+    let mut problem_count = 0;
+    for map in &maps {
+        let problems = validate_map(map, &asset_server);
+        if problems.is_empty() {
+            println!("map \"{}\": ok", map.name);
+            continue;
+        }
+        println!("map \"{}\": {} problems", map.name, problems.len());
+        for problem in &problems {
+            println!("  {problem}");
+        }
+        problem_count += problems.len();
+    }
+
+    if problem_count > 0 {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,39 @@
+//! Runs a minimal matchmaking server for online games.
+//!
+//! ```text
+//! jumpy-matchmaker [--listen <address>]
+//! ```
+//!
+//! To play against it, set the matchmaking server in the networking settings to the address it is
+//! listening on, for example `127.0.0.1:65534`.
+
+use std::net::TcpListener;
+
+/// The address to listen on if none is given.
+const DEFAULT_LISTEN_ADDRESS: &str = "0.0.0.0:65534";
+
+fn main() {
+    let mut address = DEFAULT_LISTEN_ADDRESS.to_string();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--listen", Some(value)) => address = value,
+            _ => {
+                eprintln!("Usage: jumpy-matchmaker [--listen <address>]");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Could not listen on `{address}`: {e}");
+            std::process::exit(1);
+        }
+    };
+    println!("Matchmaker listening on {address}");
+
+    jumpy::networking::matchmaker::serve(listener);
+}
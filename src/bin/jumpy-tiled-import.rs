@@ -0,0 +1,91 @@
+//! Converts a map made with Tiled into a Jumpy map file.
+//!
+//! ```text
+//! jumpy-tiled-import <map.tmj|map.tmx> [--output <path>]
+//! ```
+//!
+//! The tilesets, elements and images used by the Tiled map must be in the asset pack, see
+//! [`jumpy::core::map_import`]. If no `--output` is given, the map is written next to the Tiled
+//! map, with the `.map.yaml` extension.
+
+use std::path::{Path, PathBuf};
+
+use jumpy::{headless::load_game, prelude::*};
+
+/// Command line options for the Tiled importer.
+struct Args {
+    input: PathBuf,
+    output: Option<PathBuf>,
+}
+
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let mut input = None;
+        let mut output = None;
+
+        let mut iter = std::env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--output" => {
+                    output = Some(
+                        iter.next()
+                            .ok_or_else(|| format!("Missing value for `{arg}`"))?
+                            .into(),
+                    )
+                }
+                other if other.starts_with("--") => {
+                    return Err(format!("Unknown argument `{other}`"))
+                }
+                _ if input.is_some() => return Err("Only one map can be imported at a time".into()),
+                _ => input = Some(arg.into()),
+            }
+        }
+
+        Ok(Args {
+            input: input.ok_or("Missing the map to import")?,
+            output,
+        })
+    }
+}
+
+fn main() {
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}");
+            eprintln!("Usage: jumpy-tiled-import <map.tmj|map.tmx> [--output <path>]");
+            std::process::exit(2);
+        }
+    };
+
+    let game = match load_game(Path::new("assets"), Path::new("packs")) {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    let assets = game.shared_resource::<AssetServer>().unwrap();
+
+    let map = match import_tiled_map(&args.input, &assets) {
+        Ok(map) => map,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    for problem in validate_map(&map, &assets) {
+        eprintln!("warning: {problem}");
+    }
+
+    let output = args.output.unwrap_or_else(|| {
+        let stem = args.input.file_stem().unwrap_or_default().to_string_lossy();
+        args.input
+            .with_file_name(format!("{stem}.{MAP_FILE_EXTENSION}"))
+    });
+    if let Err(e) = MapFile::from_meta(&map, &assets).and_then(|file| file.save(&output)) {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+    println!("Imported map \"{}\" to {}", map.name, output.display());
+}
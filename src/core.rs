@@ -1,7 +1,9 @@
+pub mod ai;
 pub mod attachment;
 pub mod audio;
 pub mod bullet;
 pub mod camera;
+pub mod checksum;
 pub mod damage;
 pub mod debug;
 pub mod editor;
@@ -12,10 +14,17 @@ pub mod item;
 pub mod lifetime;
 pub mod map;
 pub mod map_constructor;
+pub mod map_export;
+pub mod map_import;
+pub mod map_validation;
+pub mod match_rules;
 pub mod metadata;
 pub mod physics;
 pub mod player;
 pub mod random;
+pub mod replay;
+pub mod snapshot;
+pub mod stats;
 pub mod utils;
 
 /// The target fixed frames-per-second that the game sumulation runs at.
@@ -30,10 +39,11 @@ use crate::{prelude::*, settings::PlayerControlMapping};
 
 pub mod prelude {
     pub use super::{
-        attachment::*, audio::*, bullet::*, camera::*, damage::*, debug::*, editor::*, editor::*,
-        elements::prelude::*, elements::prelude::*, globals::*, input::*, item::*, lifetime::*,
-        map::*, map_constructor::*, metadata::*, physics::*, player::*, random::*, utils::*, FPS,
-        MAX_PLAYERS,
+        ai::*, attachment::*, audio::*, bullet::*, camera::*, checksum::*, damage::*, debug::*,
+        editor::*, editor::*, elements::prelude::*, elements::prelude::*, globals::*, input::*,
+        item::*, lifetime::*, map::*, map_constructor::*, map_export::*, map_import::*,
+        map_validation::*, match_rules::*, metadata::*, physics::*, player::*, random::*,
+        replay::*, snapshot::*, stats::*, utils::*, FPS, MAX_PLAYERS,
     };
 }
 
@@ -68,7 +78,10 @@ impl SessionPlugin for MatchPlugin {
         physics::install(session);
         input::install(session);
         map::install(session);
+        // Runs after the map is spawned, but before the player spawners are hydrated.
+        map_constructor::install(session);
         player::plugin(session);
+        ai::install(session);
         elements::session_plugin(session);
         damage::install(session);
         camera::install(session);
@@ -79,6 +92,10 @@ impl SessionPlugin for MatchPlugin {
         attachment::install(session);
         bullet::session_plugin(session);
         editor::install(session);
+        match_rules::install(session);
+        stats::install(session);
+        checksum::install(session);
+        replay::install(session);
 
         session.world.insert_resource(LoadedMap(Arc::new(self.map)));
         session.world.insert_resource(MatchInputs {
@@ -95,6 +112,8 @@ pub struct JumpyDefaultMatchRunner {
     pub input_collector: PlayerInputCollector,
     pub accumulator: f64,
     pub last_run: Option<Instant>,
+    /// If this is set, the player inputs are read from the replay instead of the input collector.
+    pub replay: Option<ReplayPlayback>,
 }
 
 impl SessionRunner for JumpyDefaultMatchRunner {
@@ -119,16 +138,39 @@ impl SessionRunner for JumpyDefaultMatchRunner {
                 .resource_mut::<Time>()
                 .advance_exact(Duration::from_secs_f64(STEP));
 
-            let input = self.input_collector.get();
             {
+                let round = world.resource::<MatchScore>().round;
                 let mut player_inputs = world.resource_mut::<MatchInputs>();
-                (0..MAX_PLAYERS).for_each(|i| {
-                    let player_input = &mut player_inputs.players[i];
-                    let Some(source) = &player_input.control_source else {
-                        return;
-                    };
-                    player_input.control = *input.get(source).unwrap();
-                });
+                if let Some(replay) = &mut self.replay {
+                    // The replay already contains the edits made to the map.
+                    world.resource_mut::<LocalEditorInputs>().inputs.clear();
+                    replay.apply_next_frame(round, &mut player_inputs);
+                } else {
+                    let input = self.input_collector.get();
+                    let mut editor_input =
+                        world.resource_mut::<LocalEditorInputs>().inputs.pop_front();
+                    (0..MAX_PLAYERS).for_each(|i| {
+                        let player_input = &mut player_inputs.players[i];
+                        player_input.editor_input = None;
+                        let Some(source) = &player_input.control_source else {
+                            return;
+                        };
+                        // Remote players don't have any local input.
+                        if let Some(control) = input.get(source) {
+                            player_input.control = *control;
+                            // The editor is used by the first local player.
+                            player_input.editor_input = editor_input.take();
+                        }
+                    });
+                    // If there is no local player, e.g. when only AI players are playing, the
+                    // edits are made by the first player instead.
+                    if editor_input.is_some() {
+                        player_inputs.players[0].editor_input = editor_input;
+                    }
+                }
+                world
+                    .resource_mut::<ReplayRecorder>()
+                    .record(&player_inputs);
             }
 
             // Advance the simulation
@@ -0,0 +1,1020 @@
+//! AI player behaviour.
+//!
+//! Every AI player has an [`AiPlayer`] component, which holds the state of the AI, and an
+//! [`AiDifficulty`], which is used to look up the [`AiBrain`] that controls it in the [`AiBrains`]
+//! resource. Brains decide what controls to press each frame, and can be replaced to change how the
+//! AI plays.
+//!
+//! The dangerous parts of the map are collected in the [`ThreatMap`] every frame, so that the AI
+//! can stay out of them.
+
+use std::{collections::VecDeque, time::Duration};
+
+use crate::prelude::*;
+
+mod threats;
+pub use threats::*;
+
+/// The colors of the pathfinding debug lines of each player.
+const PLAYER_COLORS: [Color; 4] = [
+    Color::RED,
+    Color::GREEN,
+    Color::BLUE,
+    Color::rgb(1.0, 0.0, 1.0),
+];
+
+pub fn install(session: &mut Session) {
+    session.world.init_resource::<AiBrains>();
+    session.world.init_resource::<ThreatMap>();
+    session.world.init_resource::<AiNavGraphs>();
+    session
+        .stages
+        .add_system_to_stage(CoreStage::First, threats::update_threat_map)
+        .add_system_to_stage(CoreStage::First, update_ai_nav_graphs)
+        .add_system_to_stage(CoreStage::First, player_ai_system);
+}
+
+/// The difficulty of an AI player, which selects the [`AiBrain`] that controls it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AiDifficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl AiDifficulty {
+    /// All of the difficulties, from easiest to hardest.
+    pub const ALL: [AiDifficulty; 3] =
+        [AiDifficulty::Easy, AiDifficulty::Normal, AiDifficulty::Hard];
+
+    /// Get the next harder difficulty, wrapping around to the easiest one.
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+
+    /// Get the previous, easier difficulty, wrapping around to the hardest one.
+    pub fn prev(self) -> Self {
+        Self::ALL[(self as usize + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    /// The localization key of the difficulty name.
+    pub fn localization_key(self) -> &'static str {
+        match self {
+            AiDifficulty::Easy => "ai-difficulty-easy",
+            AiDifficulty::Normal => "ai-difficulty-normal",
+            AiDifficulty::Hard => "ai-difficulty-hard",
+        }
+    }
+
+    /// Get the tuning of the default AI brain for this difficulty.
+    pub fn settings(self) -> AiSettings {
+        match self {
+            AiDifficulty::Easy => AiSettings {
+                reaction_time: 0.6,
+                speed_multiplier: 0.5,
+                pause_chance: 0.5,
+                attack_range: vec2(10.0, 8.0),
+                aim_chance: 0.3,
+                item_preference: 0.0,
+                dodge_chance: 0.0,
+                danger_avoidance: 0.25,
+                flee_chance: 0.02,
+            },
+            AiDifficulty::Normal => AiSettings {
+                reaction_time: 0.25,
+                speed_multiplier: 0.65,
+                pause_chance: 0.4,
+                attack_range: vec2(16.0, 12.0),
+                aim_chance: 0.7,
+                item_preference: 0.3,
+                dodge_chance: 0.2,
+                danger_avoidance: 1.0,
+                flee_chance: 0.1,
+            },
+            AiDifficulty::Hard => AiSettings {
+                reaction_time: 0.1,
+                speed_multiplier: 0.9,
+                pause_chance: 0.1,
+                attack_range: vec2(24.0, 16.0),
+                aim_chance: 1.0,
+                item_preference: 0.7,
+                dodge_chance: 0.6,
+                danger_avoidance: 2.0,
+                flee_chance: 0.5,
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for AiDifficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "easy" => Ok(AiDifficulty::Easy),
+            "normal" => Ok(AiDifficulty::Normal),
+            "hard" => Ok(AiDifficulty::Hard),
+            other => Err(format!("Unknown AI difficulty `{other}`")),
+        }
+    }
+}
+
+/// Tuning values for the [`PathfindingAiBrain`].
+#[derive(Clone, Copy, Debug)]
+pub struct AiSettings {
+    /// How long, in seconds, a target has to be in reach before the AI attacks it.
+    pub reaction_time: f32,
+    /// The fraction of the full walking speed that the AI moves with.
+    pub speed_multiplier: f32,
+    /// The chance that the AI takes a short pause, checked every [`AiPlayer::TICK_TIME`].
+    pub pause_chance: f32,
+    /// The horizontal and vertical distance at which the AI attacks a target.
+    pub attack_range: Vec2,
+    /// The chance that the AI turns towards its target before attacking, instead of attacking in
+    /// whatever direction it is facing.
+    pub aim_chance: f32,
+    /// The chance that an AI holding a melee weapon goes for a different item instead of another
+    /// player. Unarmed AI players always go for items first.
+    pub item_preference: f32,
+    /// The chance that the AI jumps away from an armed player that is about to reach it, checked
+    /// every [`AiPlayer::TICK_TIME`].
+    pub dodge_chance: f32,
+    /// How strongly the AI avoids paths through the [`ThreatMap`], scaling [`THREAT_COST`] and
+    /// [`PIT_COST`].
+    pub danger_avoidance: f32,
+    /// The chance that the AI notices it is in danger and flees, checked every frame.
+    pub flee_chance: f32,
+}
+
+/// Trait implemented by AI behaviours.
+pub trait AiBrain: Sync + Send + 'static {
+    /// Decide which controls the AI player should press this frame.
+    ///
+    /// The brain may keep state between frames in the [`AiPlayer`] component.
+    fn think(&self, ai: &mut AiPlayer, ctx: &AiContext) -> PlayerControl;
+}
+
+/// The parts of the match that an [`AiBrain`] can see.
+pub struct AiContext<'a> {
+    /// The AI player entity.
+    pub entity: Entity,
+    /// The position of the AI player.
+    pub position: Vec2,
+    /// The velocity of the AI player.
+    pub velocity: Vec2,
+    /// Whether the AI player is facing left.
+    pub facing_left: bool,
+    /// The item the AI player is holding, if any.
+    pub held_item: Option<AiHeldItem<'a>>,
+    /// The other players in the match.
+    pub opponents: &'a [AiOpponent],
+    /// The items that nobody is holding.
+    pub items: &'a [AiItem],
+    /// The dangerous parts of the map.
+    pub threats: &'a ThreatMap,
+    /// The navigation graph of the map.
+    pub nav_graph: &'a NavGraphInner,
+    /// The tile size of the map.
+    pub tile_size: Vec2,
+    /// The random number generator of the match.
+    pub rng: &'a GlobalRng,
+    /// The time since the last frame.
+    pub delta: Duration,
+}
+
+impl AiContext<'_> {
+    /// Get the navigation node that contains the given position.
+    pub fn node_at(&self, pos: Vec2) -> NavNode {
+        NavNode((pos / self.tile_size).floor().as_ivec2())
+    }
+
+    /// Get the position of the given player or item, if it is still around.
+    pub fn position_of(&self, entity: Entity) -> Option<Vec2> {
+        self.opponents
+            .iter()
+            .find(|x| x.entity == entity)
+            .map(|x| x.position)
+            .or_else(|| self.item(entity).map(|x| x.position))
+    }
+
+    /// Get the cost of moving into the given nav graph node because of the threats around it,
+    /// scaled by `danger_avoidance`.
+    pub fn danger_cost(&self, node: NavNode, danger_avoidance: f32) -> f32 {
+        if danger_avoidance == 0.0 {
+            return 0.0;
+        }
+        self.threats.node_cost(node, self.tile_size, self.entity) * danger_avoidance
+    }
+
+    /// Get the free item with the given entity.
+    pub fn item(&self, entity: Entity) -> Option<&AiItem> {
+        self.items.iter().find(|x| x.entity == entity)
+    }
+}
+
+/// Another player, as seen by an [`AiBrain`].
+#[derive(Clone, Copy, Debug)]
+pub struct AiOpponent {
+    pub entity: Entity,
+    pub position: Vec2,
+    /// The item the player is holding, if any.
+    pub holding: Option<Entity>,
+}
+
+/// An item that nobody is holding, as seen by an [`AiBrain`].
+#[derive(Clone, Copy, Debug)]
+pub struct AiItem {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub kind: AiItemKind,
+}
+
+/// The item held by an AI player.
+pub struct AiHeldItem<'a> {
+    pub entity: Entity,
+    pub kind: AiItemKind,
+    /// How the item is thrown when it is dropped, if it can be thrown.
+    pub throw: Option<&'a ItemThrow>,
+    /// The gravity of the item once it has been thrown.
+    pub gravity: f32,
+}
+
+/// How an AI uses an item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AiItemKind {
+    /// Swung at targets up close, like the sword.
+    Melee,
+    /// Fired at targets in line with the player, like the musket.
+    Gun { ammo: u32 },
+    /// Thrown at targets when used, like mines and crates.
+    Throwable,
+    /// Lit when used and then thrown at targets, like grenades and kick bombs.
+    Explosive { lit: bool },
+    /// Used close to targets.
+    Other,
+}
+
+/// Resource containing the [`AiBrain`] used for each [`AiDifficulty`].
+///
+/// By default every difficulty uses the [`PathfindingAiBrain`] with the difficulty's
+/// [`AiSettings`].
+#[derive(HasSchema, Clone)]
+pub struct AiBrains {
+    brains: [Arc<dyn AiBrain>; AiDifficulty::ALL.len()],
+}
+
+impl Default for AiBrains {
+    fn default() -> Self {
+        Self {
+            brains: AiDifficulty::ALL
+                .map(|x| Arc::new(PathfindingAiBrain::new(x.settings())) as Arc<dyn AiBrain>),
+        }
+    }
+}
+
+impl AiBrains {
+    /// Get the brain used for AI players with the given difficulty.
+    pub fn get(&self, difficulty: AiDifficulty) -> &dyn AiBrain {
+        self.brains[difficulty as usize].as_ref()
+    }
+
+    /// Set the brain used for AI players with the given difficulty.
+    pub fn set(&mut self, difficulty: AiDifficulty, brain: impl AiBrain) {
+        self.brains[difficulty as usize] = Arc::new(brain);
+    }
+}
+
+/// Component containing the state of an AI player.
+#[derive(Clone, Debug, HasSchema)]
+pub struct AiPlayer {
+    /// The difficulty of the AI, which selects its [`AiBrain`].
+    pub difficulty: AiDifficulty,
+    /// Tick timer that is used for AI pausing and dodging logic.
+    pub tick: Timer,
+    /// Indicates the player is taking pause for the given number of ticks.
+    pub pausing: u32,
+    /// Buffers planned AI movements
+    pub movement_buffer: Option<VecDeque<PlayerControl>>,
+    /// The player or item that the AI is going for.
+    pub target: Option<Entity>,
+    /// How long, in seconds, the target has been within reach.
+    pub time_in_reach: f32,
+    /// The last path the AI planned to its target, used for the debug lines.
+    pub path: Vec<NavNode>,
+    /// Whether the AI has noticed that it is in danger, and is running to safety.
+    pub fleeing: bool,
+    /// The last item that the AI dropped, which it won't go back for.
+    pub dropped_item: Option<Entity>,
+}
+
+impl Default for AiPlayer {
+    fn default() -> Self {
+        Self::new(default())
+    }
+}
+
+impl AiPlayer {
+    /// The interval of the AI tick timer, in seconds.
+    pub const TICK_TIME: f32 = 0.5;
+
+    pub fn new(difficulty: AiDifficulty) -> Self {
+        Self {
+            difficulty,
+            tick: Timer::from_seconds(Self::TICK_TIME, TimerMode::Repeating),
+            pausing: 0,
+            movement_buffer: Default::default(),
+            target: Default::default(),
+            time_in_reach: 0.0,
+            path: Vec::new(),
+            fleeing: false,
+            dropped_item: None,
+        }
+    }
+}
+
+/// The default AI brain, which walks to its target over the [`NavGraph`] and attacks it once it
+/// is close enough.
+pub struct PathfindingAiBrain {
+    pub settings: AiSettings,
+}
+
+impl PathfindingAiBrain {
+    /// How close, in pixels, the AI has to be to an item to pick it up.
+    const GRAB_DISTANCE: f32 = 8.0;
+    /// How close, in pixels, an armed player has to be for the AI to consider dodging it.
+    const DODGE_DISTANCE: f32 = 40.0;
+    /// How many frames the AI holds the controls of a dodge.
+    const DODGE_FRAMES: usize = 20;
+    /// How far above or below the AI, in pixels, a target may be for the AI to fire a gun at it.
+    const GUN_ALIGNMENT: f32 = 6.0;
+    /// How far away, in pixels, a target may be for the AI to fire a gun at it.
+    const GUN_RANGE: f32 = 300.0;
+    /// How close, in pixels, a throw has to pass by the target for the AI to make it.
+    const THROW_ACCURACY: f32 = 12.0;
+    /// How many frames of a throw the AI simulates when aiming.
+    const THROW_FRAMES: usize = 90;
+    /// How close, in pixels, a target has to be for the AI to light an explosive without a good
+    /// throw lined up.
+    const EXPLOSIVE_RANGE: f32 = 120.0;
+    /// The move directions of the different throws, for a player facing right.
+    const THROW_DIRECTIONS: [Vec2; 6] = [
+        vec2(0.0, 0.0),
+        vec2(1.0, 0.0),
+        vec2(0.0, 1.0),
+        vec2(1.0, 1.0),
+        vec2(1.0, -1.0),
+        vec2(0.0, -1.0),
+    ];
+
+    pub fn new(settings: AiSettings) -> Self {
+        Self { settings }
+    }
+
+    /// Pick a new target for the AI: the nearest reachable item if it is unarmed, or may swap its
+    /// melee weapon for another item, otherwise a random other player.
+    ///
+    /// Empty guns and the item the AI dropped last are never picked.
+    fn pick_target(&self, ai: &AiPlayer, ctx: &AiContext) -> Option<Entity> {
+        let wants_item = match &ctx.held_item {
+            None => true,
+            Some(item) => {
+                item.kind == AiItemKind::Melee
+                    && ctx.rng.chance(self.settings.item_preference as f64)
+            }
+        };
+        if wants_item && !ctx.items.is_empty() {
+            // Go for the item that is the shortest walk away.
+            let distances = petgraph::algo::dijkstra(
+                ctx.nav_graph,
+                ctx.node_at(ctx.position),
+                None,
+                |(_, to, edge)| edge.distance + ctx.danger_cost(to, self.settings.danger_avoidance),
+            );
+            let nearest_item = ctx
+                .items
+                .iter()
+                .filter(|x| ctx.held_item.is_none() || x.kind != AiItemKind::Melee)
+                .filter(|x| x.kind != AiItemKind::Gun { ammo: 0 })
+                .filter(|x| Some(x.entity) != ai.dropped_item)
+                .filter_map(|x| Some((x.entity, *distances.get(&ctx.node_at(x.position))?)))
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((item, _)) = nearest_item {
+                return Some(item);
+            }
+        }
+
+        if ctx.opponents.is_empty() {
+            return None;
+        }
+        Some(ctx.opponents[ctx.rng.usize(..ctx.opponents.len())].entity)
+    }
+
+    /// Get the controls for jumping away from the nearest armed player, if one is about to reach
+    /// the AI.
+    fn dodge(&self, ctx: &AiContext) -> Option<VecDeque<PlayerControl>> {
+        let threat = ctx
+            .opponents
+            .iter()
+            .filter(|x| x.holding.is_some())
+            .map(|x| x.position - ctx.position)
+            .filter(|offset| offset.length() < Self::DODGE_DISTANCE)
+            .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))?;
+
+        let away = if threat.x > 0.0 { -1.0 } else { 1.0 };
+        let mut controls = VecDeque::with_capacity(Self::DODGE_FRAMES);
+        for i in 0..Self::DODGE_FRAMES {
+            controls.push_back(PlayerControl {
+                move_direction: vec2(away, 0.0),
+                jump_pressed: true,
+                jump_just_pressed: i == 0,
+                ..default()
+            });
+        }
+        Some(controls)
+    }
+
+    /// Get the controls for running out of danger over the nav graph, or [`None`] if the AI is
+    /// already safe, or has nowhere safe to go.
+    fn flee(&self, ai: &mut AiPlayer, ctx: &AiContext) -> Option<PlayerControl> {
+        if !ctx.threats.is_dangerous(ctx.position, ctx.entity) {
+            return None;
+        }
+
+        // Finish the move we are in the middle of
+        if let Some(movement_buffer) = &mut ai.movement_buffer {
+            if let Some(control) = movement_buffer.pop_front() {
+                if movement_buffer.is_empty() {
+                    ai.movement_buffer = None;
+                }
+                return Some(control);
+            }
+        }
+
+        // Find the nearest node that is out of danger, preferring the safest way there.
+        let current_node = ctx.node_at(ctx.position);
+        let (_cost, path) = petgraph::algo::astar(
+            ctx.nav_graph,
+            current_node,
+            |x| x != current_node && !ctx.threats.is_node_dangerous(x, ctx.tile_size, ctx.entity),
+            |(_, to, edge)| edge.distance + ctx.danger_cost(to, 1.0),
+            |_| 0.0,
+        )?;
+        let edge = ctx.nav_graph.edge_weight(current_node, path[1])?;
+        let mut movement_buffer = edge.inputs.clone();
+        let control = movement_buffer.pop_front()?;
+        if !movement_buffer.is_empty() {
+            ai.movement_buffer = Some(movement_buffer);
+        }
+        ai.path = path;
+
+        Some(control)
+    }
+
+    /// Find the move direction that throws the held item closest to the target, if it passes
+    /// close enough to hit it.
+    ///
+    /// Throws without any horizontal movement go in the direction the player is facing, the others
+    /// turn the player towards the target.
+    fn aim_throw(&self, ctx: &AiContext, item: &AiHeldItem, target_pos: Vec2) -> Option<Vec2> {
+        let throw = item.throw?;
+        let towards_target = if target_pos.x < ctx.position.x {
+            -1.0
+        } else {
+            1.0
+        };
+        let facing = if ctx.facing_left { -1.0 } else { 1.0 };
+
+        Self::THROW_DIRECTIONS
+            .iter()
+            .map(|&direction| {
+                let flip = if direction.x == 0.0 {
+                    facing
+                } else {
+                    towards_target
+                };
+                let move_direction = direction * vec2(flip, 1.0);
+                let mut velocity = throw.velocity_from_control(&PlayerControl {
+                    move_direction,
+                    ..default()
+                }) * vec2(flip, 1.0);
+
+                // Follow the flight of the item, ignoring any collisions.
+                let mut pos = ctx.position;
+                let mut closest = pos.distance(target_pos);
+                for _ in 0..Self::THROW_FRAMES {
+                    pos += velocity;
+                    velocity.y -= item.gravity;
+                    closest = closest.min(pos.distance(target_pos));
+                    if velocity.y < 0.0 && pos.y < target_pos.y - Self::THROW_ACCURACY {
+                        break;
+                    }
+                }
+                (move_direction, closest)
+            })
+            .filter(|(_, closest)| *closest < Self::THROW_ACCURACY)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(move_direction, _)| move_direction)
+    }
+
+    /// Get the controls for attacking the target with the held item, if the AI is in a position to
+    /// attack.
+    fn attack(&self, ctx: &AiContext, item: &AiHeldItem, offset: Vec2) -> Option<PlayerControl> {
+        let settings = &self.settings;
+        let target_pos = ctx.position + offset;
+        let use_item = |move_direction| PlayerControl {
+            move_direction,
+            shoot_pressed: true,
+            shoot_just_pressed: true,
+            ..default()
+        };
+        let drop_item = |move_direction| PlayerControl {
+            move_direction,
+            grab_pressed: true,
+            grab_just_pressed: true,
+            ..default()
+        };
+        // Turn towards the target before attacking, if the AI aims well enough.
+        let aim = || {
+            if ctx.rng.chance(settings.aim_chance as f64) {
+                vec2(offset.x.signum(), 0.0)
+            } else {
+                Vec2::ZERO
+            }
+        };
+
+        match item.kind {
+            AiItemKind::Melee | AiItemKind::Other => {
+                let in_reach = offset.x.abs() < settings.attack_range.x
+                    && offset.y.abs() < settings.attack_range.y;
+                in_reach.then(|| use_item(aim()))
+            }
+            // Empty guns are no use, so get rid of them.
+            AiItemKind::Gun { ammo: 0 } => Some(drop_item(Vec2::ZERO)),
+            AiItemKind::Gun { .. } => {
+                let in_line =
+                    offset.y.abs() < Self::GUN_ALIGNMENT && offset.x.abs() < Self::GUN_RANGE;
+                let facing_target = (offset.x < 0.0) == ctx.facing_left;
+                if !in_line {
+                    return None;
+                }
+                let direction = aim();
+                (facing_target || direction != Vec2::ZERO).then(|| use_item(direction))
+            }
+            AiItemKind::Throwable => self.aim_throw(ctx, item, target_pos).map(use_item),
+            AiItemKind::Explosive { lit: false } => {
+                let lined_up = self.aim_throw(ctx, item, target_pos).is_some();
+                (lined_up || offset.length() < Self::EXPLOSIVE_RANGE).then(|| use_item(Vec2::ZERO))
+            }
+            // Never hold on to a lit explosive: throw it at the target, or at least towards it.
+            AiItemKind::Explosive { lit: true } => Some(drop_item(
+                self.aim_throw(ctx, item, target_pos)
+                    .unwrap_or(vec2(offset.x.signum(), 0.0)),
+            )),
+        }
+    }
+}
+
+impl AiBrain for PathfindingAiBrain {
+    fn think(&self, ai: &mut AiPlayer, ctx: &AiContext) -> PlayerControl {
+        let settings = &self.settings;
+
+        // Tick the AI timer
+        ai.tick.tick(ctx.delta);
+
+        // If a tick has elapsed
+        if ai.tick.just_finished() {
+            // If the player isn't pausing, there's a chance
+            if ai.pausing == 0 && ctx.rng.chance(settings.pause_chance as f64) {
+                // That we will pause for a random number of ticks between 0 and 2
+                ai.pausing = (ctx.rng.f32_normalized() * 2.0).round() as u32
+            }
+
+            // If the player is pausing
+            if ai.pausing > 0 {
+                // Subtract a tick from how long they should pause.
+                ai.pausing -= 1;
+            }
+
+            // Maybe jump away from an armed player that is getting close
+            if ai.pausing == 0
+                && ai.movement_buffer.is_none()
+                && ctx.rng.chance(settings.dodge_chance as f64)
+            {
+                ai.movement_buffer = self.dodge(ctx);
+            }
+        }
+
+        // Once we notice that we are in danger, run to safety, even if we were pausing.
+        let notices_danger = || {
+            ctx.threats.is_dangerous(ctx.position, ctx.entity)
+                && ctx.rng.chance(settings.flee_chance as f64)
+        };
+        if ai.fleeing || notices_danger() {
+            match self.flee(ai, ctx) {
+                Some(control) => {
+                    ai.fleeing = true;
+                    ai.pausing = 0;
+                    return control;
+                }
+                None => ai.fleeing = false,
+            }
+        }
+
+        // If the player is pausing, don't have the AI move this frame.
+        if ai.pausing > 0 {
+            return default();
+        }
+
+        // Items that have been picked up, or that we don't need any more, are no longer targets.
+        if let Some(target) = ai.target {
+            let gone = ctx.position_of(target).is_none();
+            let item_not_needed = ctx.item(target).is_some()
+                && ctx
+                    .held_item
+                    .as_ref()
+                    .is_some_and(|x| x.kind != AiItemKind::Melee);
+            // Unarmed players look for a new item every tick.
+            let unarmed =
+                ctx.held_item.is_none() && ctx.item(target).is_none() && ai.tick.just_finished();
+            if gone || item_not_needed || unarmed {
+                ai.target = None;
+            }
+        }
+        if ai.target.is_none() {
+            ai.target = self.pick_target(ai, ctx);
+            ai.time_in_reach = 0.0;
+        }
+        let Some(target) = ai.target else {
+            return default();
+        };
+        let target_pos = ctx.position_of(target).unwrap();
+        let offset = target_pos - ctx.position;
+
+        // Work out whether we can attack the target, and keep track of how long we have been able
+        // to, so that we only attack after our reaction time.
+        let attack = match &ctx.held_item {
+            // Picking up an item drops the one we are holding, so we may grab items while armed.
+            _ if ctx.item(target).is_some() => {
+                (offset.length() < Self::GRAB_DISTANCE).then(|| PlayerControl {
+                    grab_pressed: true,
+                    grab_just_pressed: true,
+                    ..default()
+                })
+            }
+            Some(item) => self.attack(ctx, item, offset),
+            None => None,
+        };
+        if attack.is_some() {
+            ai.time_in_reach += ctx.delta.as_secs_f32();
+        } else {
+            ai.time_in_reach = 0.0;
+        }
+        if let Some(attack) = attack {
+            let is_grab = ctx.item(target).is_some();
+            if is_grab || ai.time_in_reach >= settings.reaction_time {
+                ai.movement_buffer = None;
+                // Grabbing drops the item we are holding, if any.
+                if attack.grab_just_pressed {
+                    if let Some(item) = &ctx.held_item {
+                        ai.dropped_item = Some(item.entity);
+                    }
+                }
+                return attack;
+            }
+        }
+
+        // Complete any previous movement instructions if we are in the middle of any
+        if let Some(movement_buffer) = &mut ai.movement_buffer {
+            if let Some(control) = movement_buffer.pop_front() {
+                if movement_buffer.is_empty() {
+                    ai.movement_buffer = None;
+                }
+                return control;
+            }
+        }
+
+        let current_node = ctx.node_at(ctx.position);
+        let target_node = ctx.node_at(target_pos);
+        let path = petgraph::algo::astar(
+            ctx.nav_graph,
+            current_node,
+            |x| x == target_node,
+            |(_, to, edge)| edge.distance + ctx.danger_cost(to, settings.danger_avoidance),
+            |_| 0.0,
+        );
+
+        let mut control = PlayerControl::default();
+        if let Some((_cost, path)) = path {
+            if let Some(&next_node) = path.get(1) {
+                let edge = ctx.nav_graph.edge_weight(current_node, next_node).unwrap();
+                let mut movement_buffer = edge.inputs.clone();
+                let mut first_movement = movement_buffer.pop_front().unwrap();
+
+                // Slow down the AI movement according to the difficulty
+                first_movement.move_direction *= vec2(settings.speed_multiplier, 1.0);
+
+                // This is a hack to prevent us from getting stuck when we think we should be falling
+                // straight down and we actually need to move off of the block we're half-standing on.
+                //
+                // If we aren't moving at all, just move in the direction of the path
+                if ctx.velocity == Vec2::ZERO && first_movement.move_direction == Vec2::ZERO {
+                    let sign = (path.get(2).unwrap_or(&next_node).x as f32 * ctx.tile_size.x
+                        - ctx.position.x)
+                        .signum();
+                    first_movement.move_direction.x = sign;
+                }
+
+                control = first_movement;
+                if !movement_buffer.is_empty() {
+                    ai.movement_buffer = Some(movement_buffer)
+                }
+            }
+            ai.path = path;
+        } else {
+            ai.path.clear();
+        }
+
+        control
+    }
+}
+
+#[derive(Debug, HasSchema, Clone)]
+#[schema(no_default)]
+pub struct PathfindingDebugLines {
+    pub entities: Vec<Entity>,
+}
+
+impl FromWorld for PathfindingDebugLines {
+    fn from_world(world: &mut World) -> Self {
+        let entities = world.run_initialized_system(
+            |mut entities: ResMutInit<Entities>, mut transforms: CompMut<Transform>| {
+                (0..MAX_PLAYERS)
+                    .map(|_| {
+                        let ent = entities.create();
+
+                        transforms
+                            .insert(ent, Transform::from_translation(Vec3::new(0.0, 0.0, -1.0)));
+
+                        ent
+                    })
+                    .collect::<Vec<_>>()
+            },
+            (),
+        );
+
+        Self { entities }
+    }
+}
+
+/// Work out how an AI would use the given item, or [`None`] if the item has already been thrown.
+fn item_kind(world: &World, item: Entity) -> Option<AiItemKind> {
+    if world.component::<ThrownMine>().contains(item)
+        || world.component::<ThrownCrate>().contains(item)
+    {
+        return None;
+    }
+
+    let kind = if world.component::<Sword>().contains(item) {
+        AiItemKind::Melee
+    } else if let Some(musket) = world.component::<Musket>().get(item) {
+        AiItemKind::Gun { ammo: musket.ammo }
+    } else if world.component::<IdleMine>().contains(item)
+        || world.component::<IdleCrate>().contains(item)
+    {
+        AiItemKind::Throwable
+    } else if world.component::<IdleGrenade>().contains(item)
+        || world.component::<IdleKickBomb>().contains(item)
+    {
+        AiItemKind::Explosive { lit: false }
+    } else if world.component::<LitGrenade>().contains(item)
+        || world.component::<LitKickBomb>().contains(item)
+    {
+        AiItemKind::Explosive { lit: true }
+    } else {
+        AiItemKind::Other
+    };
+    Some(kind)
+}
+
+/// Resource containing the navigation graphs of the AI players that don't move like the default
+/// player that the [`NavGraph`] is made for, because their character or the map changes how far
+/// they jump.
+#[derive(HasSchema, Clone, Default)]
+pub struct AiNavGraphs {
+    /// The graph of each player, or `None` if it uses the [`NavGraph`].
+    pub graphs: [Option<Arc<NavGraphInner>>; MAX_PLAYERS],
+    /// What the graph of each player was made from, to update it with when the map changes.
+    sources: [Option<NavGraphSource>; MAX_PLAYERS],
+    /// The [`NavGraph`] that the graphs were made alongside, so that they are updated when it
+    /// changes.
+    source: Option<Arc<NavGraphInner>>,
+}
+
+/// Create the navigation graphs of the AI players that move differently from the default player,
+/// and update them when the map changes.
+fn update_ai_nav_graphs(world: &World) {
+    let (mut ai_nav_graphs, player_settings, default_settings, nav_graph, tiles) = world
+        .run_system(
+            |entities: Res<Entities>,
+             meta: Root<GameMeta>,
+             assets: Res<AssetServer>,
+             map: Res<SpawnedMapMeta>,
+             player_inputs: Res<MatchInputs>,
+             ai_players: Comp<AiPlayer>,
+             player_indexes: Comp<PlayerIdx>,
+             nav_graph: ResInit<NavGraph>,
+             nav_graph_source: ResInit<NavGraphSource>,
+             mut ai_nav_graphs: ResMutInit<AiNavGraphs>| {
+                let mut player_settings = [None; MAX_PLAYERS];
+                for (_, (_, player_idx)) in entities.iter_with((&ai_players, &player_indexes)) {
+                    let player_handle =
+                        player_inputs.players[player_idx.0 as usize].selected_player;
+                    let player = assets.get(player_handle);
+                    player_settings[player_idx.0 as usize] = Some(NavGraphSettings::new(
+                        &player,
+                        &meta.core.physics,
+                        &map.rules,
+                    ));
+                }
+                (
+                    // Taken out of the world, so that the graphs can be updated in place.
+                    std::mem::take(&mut *ai_nav_graphs),
+                    player_settings,
+                    NavGraphSettings::for_default_player(&assets, &map.rules),
+                    nav_graph.0.clone(),
+                    nav_graph_source.tiles.clone(),
+                )
+            },
+            (),
+        );
+    let player_settings = player_settings.map(|x| x.filter(|x| *x != default_settings));
+
+    let nav_graph_changed = !ai_nav_graphs
+        .source
+        .as_ref()
+        .is_some_and(|x| Arc::ptr_eq(x, &nav_graph));
+    let settings_changed = (0..MAX_PLAYERS)
+        .any(|idx| player_settings[idx] != ai_nav_graphs.sources[idx].as_ref().map(|x| x.settings));
+    if nav_graph_changed || settings_changed {
+        // The graphs are made from the same tiles as the `NavGraph`, and are updated for the
+        // changed tiles instead of being re-created.
+        let AiNavGraphs {
+            graphs, sources, ..
+        } = &mut ai_nav_graphs;
+        for idx in 0..MAX_PLAYERS {
+            let settings = player_settings[idx];
+            if sources[idx].as_ref().map(|x| x.settings) != settings {
+                graphs[idx] = None;
+                sources[idx] = None;
+            }
+            let Some(settings) = settings else {
+                continue;
+            };
+
+            // Players that move the same way share a graph.
+            if let Some(other) = (0..idx).find(|&x| player_settings[x] == Some(settings)) {
+                graphs[idx] = graphs[other].clone();
+                sources[idx] = sources[other].clone();
+                continue;
+            }
+            match (&mut graphs[idx], &mut sources[idx]) {
+                (Some(graph), Some(source)) => {
+                    source.update(graph, (*tiles).clone());
+                }
+                _ => {
+                    let (graph, source) = (*tiles).clone().into_nav_graph(settings);
+                    graphs[idx] = Some(Arc::new(graph));
+                    sources[idx] = Some(source);
+                }
+            }
+        }
+        ai_nav_graphs.source = Some(nav_graph);
+    }
+
+    world.run_system(
+        move |mut graphs: ResMutInit<AiNavGraphs>| {
+            *graphs = std::mem::take(&mut ai_nav_graphs);
+        },
+        (),
+    );
+}
+
+/// Runs the [`AiBrain`] of every AI player and sets their controls.
+fn player_ai_system(
+    world: &World,
+    entities: Res<Entities>,
+    nav_graph: ResInit<NavGraph>,
+    ai_nav_graphs: Res<AiNavGraphs>,
+    brains: Res<AiBrains>,
+    mut player_inputs: ResMutInit<MatchInputs>,
+    mut ai_players: CompMut<AiPlayer>,
+    player_indexes: Comp<PlayerIdx>,
+    map: Res<LoadedMap>,
+    spawned_map: Res<SpawnedMapMeta>,
+    transforms: Comp<Transform>,
+    pathfinding_debug_line: ResMutInit<PathfindingDebugLines>,
+    mut paths: CompMut<Path2d>,
+    bodies: Comp<KinematicBody>,
+    sprites: Comp<AtlasSprite>,
+    items: Comp<Item>,
+    item_throws: Comp<ItemThrow>,
+    inventories: Comp<Inventory>,
+    debug_settings: ResInit<DebugSettings>,
+    threat_map: Res<ThreatMap>,
+    rng: Res<GlobalRng>,
+    time: Res<Time>,
+) {
+    let players = entities
+        .iter_with((&player_indexes, &transforms))
+        .map(|(entity, (_, transform))| AiOpponent {
+            entity,
+            position: transform.translation.truncate(),
+            holding: inventories.get(entity).and_then(|x| x.0),
+        })
+        .collect::<Vec<_>>();
+    let free_items = entities
+        .iter_with((&items, &transforms))
+        .filter(|(item, _)| !players.iter().any(|x| x.holding == Some(*item)))
+        .filter_map(|(item, (_, transform))| {
+            // Lit explosives are best left alone.
+            let kind = item_kind(world, item)
+                .filter(|kind| *kind != AiItemKind::Explosive { lit: true })?;
+            Some(AiItem {
+                entity: item,
+                position: transform.translation.truncate(),
+                kind,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for (ai_ent, (player_idx, transform, ai_player)) in
+        entities.iter_with((&player_indexes, &transforms, &mut ai_players))
+    {
+        let opponents = players
+            .iter()
+            .filter(|x| x.entity != ai_ent)
+            .copied()
+            .collect::<Vec<_>>();
+        let held_item = inventories.get(ai_ent).and_then(|x| x.0).and_then(|item| {
+            Some(AiHeldItem {
+                entity: item,
+                kind: item_kind(world, item)?,
+                throw: item_throws.get(item),
+                gravity: bodies.get(item).map(|x| x.gravity).unwrap_or_default()
+                    * spawned_map.rules.gravity_scale(),
+            })
+        });
+        let ctx = AiContext {
+            entity: ai_ent,
+            position: transform.translation.truncate(),
+            velocity: bodies.get(ai_ent).map(|x| x.velocity).unwrap_or_default(),
+            facing_left: sprites.get(ai_ent).is_some_and(|x| x.flip_x),
+            held_item,
+            opponents: &opponents,
+            items: &free_items,
+            threats: &threat_map,
+            nav_graph: ai_nav_graphs.graphs[player_idx.0 as usize]
+                .as_deref()
+                .unwrap_or(&nav_graph.0),
+            tile_size: map.tile_size,
+            rng: &rng,
+            delta: time.delta(),
+        };
+
+        let brain = brains.get(ai_player.difficulty);
+        player_inputs.players[player_idx.0 as usize].control = brain.think(ai_player, &ctx);
+
+        let debug_line = pathfinding_debug_line.entities[player_idx.0 as usize];
+        if !debug_settings.show_pathfinding_lines {
+            paths.remove(debug_line);
+        } else if ai_player.path.is_empty() {
+            let pos = ctx.node_at(ctx.position).0.as_vec2() * map.tile_size + map.tile_size / 2.0
+                - vec2(0.0, 4.0);
+            paths.insert(
+                debug_line,
+                Path2d {
+                    points: vec![pos, pos + vec2(0.0, 4.0)],
+                    thickness: 8.0,
+                    color: Color::RED,
+                    ..default()
+                },
+            );
+        } else {
+            paths.insert(
+                debug_line,
+                Path2d {
+                    points: ai_player
+                        .path
+                        .iter()
+                        .map(|x| x.0.as_vec2() * map.tile_size + map.tile_size / 2.0)
+                        .collect(),
+                    thickness: 2.0,
+                    color: PLAYER_COLORS[player_idx.0 as usize],
+                    ..default()
+                },
+            );
+        }
+    }
+}
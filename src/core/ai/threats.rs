@@ -0,0 +1,223 @@
+//! The threat map, which tells the AI where the dangerous parts of the map are.
+
+use crate::prelude::*;
+
+/// The cost added to nav graph edges that lead into a threat, before scaling it by
+/// [`AiSettings::danger_avoidance`].
+pub const THREAT_COST: f32 = 20.0;
+
+/// The cost added to nav graph edges that lead above a bottomless drop, before scaling it by
+/// [`AiSettings::danger_avoidance`].
+pub const PIT_COST: f32 = 10.0;
+
+/// How many frames ahead of a bullet are considered dangerous.
+const BULLET_LOOKAHEAD_FRAMES: f32 = 30.0;
+
+/// Resource containing the dangerous areas of the map, rebuilt every frame for the AI.
+#[derive(HasSchema, Clone, Default)]
+pub struct ThreatMap {
+    /// The threats in the map.
+    pub threats: Vec<Threat>,
+    /// The nav graph nodes that have nothing solid below them, so that anything that ends up there
+    /// falls out of the map.
+    pub pits: HashSet<NavNode>,
+    /// The nav graph that the pits were found in, so that they are only updated when it changes.
+    pits_graph: Option<Arc<NavGraphInner>>,
+}
+
+/// A dangerous area of the map.
+#[derive(Clone, Copy, Debug)]
+pub struct Threat {
+    /// The entity that causes the threat.
+    pub entity: Entity,
+    pub kind: ThreatKind,
+    /// The area of the map that is dangerous.
+    pub area: Rect,
+    /// A player that isn't harmed by the threat, like the owner of a damage region.
+    pub harmless_to: Option<Entity>,
+}
+
+/// The kinds of threats in the [`ThreatMap`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThreatKind {
+    /// A [`DamageRegion`], like an explosion.
+    DamageRegion,
+    /// An explosive that is about to go off, like a lit grenade or an armed mine, using the size of
+    /// its alarm [`EmoteRegion`].
+    Explosive,
+    /// A hazard that is always dangerous to touch, like spikes and urchins.
+    Hazard,
+    /// The path of a bullet.
+    Bullet,
+}
+
+impl Threat {
+    /// Whether the threat can harm the given player.
+    pub fn harms(&self, player: Entity) -> bool {
+        self.harmless_to != Some(player)
+    }
+}
+
+impl ThreatMap {
+    /// Get the threats that harm the given player at the given position.
+    pub fn threats_at(&self, pos: Vec2, player: Entity) -> impl Iterator<Item = &Threat> {
+        self.threats
+            .iter()
+            .filter(move |x| x.harms(player) && x.area.contains(pos))
+    }
+
+    /// Whether the given position is dangerous for the given player.
+    pub fn is_dangerous(&self, pos: Vec2, player: Entity) -> bool {
+        self.threats_at(pos, player).next().is_some()
+    }
+
+    /// Whether any part of the given nav graph node is dangerous for the given player.
+    pub fn is_node_dangerous(&self, node: NavNode, tile_size: Vec2, player: Entity) -> bool {
+        let area = node_area(node, tile_size);
+        self.threats
+            .iter()
+            .any(|x| x.harms(player) && x.area.overlaps(&area))
+    }
+
+    /// Get the danger cost of moving into the given nav graph node for the given player.
+    pub fn node_cost(&self, node: NavNode, tile_size: Vec2, player: Entity) -> f32 {
+        let area = node_area(node, tile_size);
+        let threats = self
+            .threats
+            .iter()
+            .filter(|x| x.harms(player) && x.area.overlaps(&area))
+            .count();
+        let pit = if self.pits.contains(&node) {
+            PIT_COST
+        } else {
+            0.0
+        };
+        threats as f32 * THREAT_COST + pit
+    }
+}
+
+/// Get the area of the map covered by a nav graph node.
+fn node_area(node: NavNode, tile_size: Vec2) -> Rect {
+    let center = node.0.as_vec2() * tile_size + tile_size / 2.0;
+    Rect::new(center.x, center.y, tile_size.x, tile_size.y)
+}
+
+/// Find the nav graph nodes with nothing solid below them.
+fn find_pits(nav_graph: &NavGraphInner, grid_size: UVec2) -> HashSet<NavNode> {
+    let mut pits = HashSet::default();
+    for x in 0..grid_size.x as i32 {
+        for y in 0..grid_size.y as i32 {
+            let node = NavNode(ivec2(x, y));
+            // Solid tiles aren't in the nav graph.
+            if !nav_graph.contains_node(node) {
+                break;
+            }
+            pits.insert(node);
+        }
+    }
+    pits
+}
+
+/// Rebuild the [`ThreatMap`] from the dangerous things in the map.
+pub(super) fn update_threat_map(
+    entities: Res<Entities>,
+    mut threat_map: ResMutInit<ThreatMap>,
+    nav_graph: ResInit<NavGraph>,
+    spawned_map: Res<SpawnedMapMeta>,
+    assets: Res<AssetServer>,
+    transforms: Comp<Transform>,
+    bodies: Comp<KinematicBody>,
+    damage_regions: Comp<DamageRegion>,
+    damage_region_owners: Comp<DamageRegionOwner>,
+    emote_regions: Comp<EmoteRegion>,
+    lit_grenades: Comp<LitGrenade>,
+    lit_kick_bombs: Comp<LitKickBomb>,
+    thrown_mines: Comp<ThrownMine>,
+    spikes: Comp<Spike>,
+    urchins: Comp<Urchin>,
+    bullets: Comp<Bullet>,
+    bullet_handles: Comp<BulletHandle>,
+) {
+    let threat_map = &mut *threat_map;
+    threat_map.threats.clear();
+
+    for (entity, (damage_region, transform)) in entities.iter_with((&damage_regions, &transforms)) {
+        let pos = transform.translation.truncate();
+        threat_map.threats.push(Threat {
+            entity,
+            kind: ThreatKind::DamageRegion,
+            area: Rect::new(pos.x, pos.y, damage_region.size.x, damage_region.size.y),
+            harmless_to: damage_region_owners.get(entity).map(|x| x.0),
+        });
+    }
+
+    // Explosives alarm the players around them while they are about to go off, which is exactly
+    // the area the AI should stay out of.
+    for (entity, (emote_region, transform)) in entities.iter_with((&emote_regions, &transforms)) {
+        let is_explosive = lit_grenades.contains(entity)
+            || lit_kick_bombs.contains(entity)
+            || thrown_mines.contains(entity);
+        if !is_explosive || !emote_region.active || emote_region.emote != Emote::Alarm {
+            continue;
+        }
+        let pos = transform.translation.truncate();
+        threat_map.threats.push(Threat {
+            entity,
+            kind: ThreatKind::Explosive,
+            area: Rect::new(pos.x, pos.y, emote_region.size.x, emote_region.size.y),
+            harmless_to: None,
+        });
+    }
+
+    for (entity, (transform, body)) in entities.iter_with((&transforms, &bodies)) {
+        if !spikes.contains(entity) && !urchins.contains(entity) {
+            continue;
+        }
+        let aabb = body.shape.compute_aabb(*transform);
+        threat_map.threats.push(Threat {
+            entity,
+            kind: ThreatKind::Hazard,
+            area: Rect {
+                min: vec2(aabb.mins.x, aabb.mins.y),
+                max: vec2(aabb.maxs.x, aabb.maxs.y),
+            },
+            harmless_to: None,
+        });
+    }
+
+    for (entity, (bullet, bullet_handle, transform)) in
+        entities.iter_with((&bullets, &bullet_handles, &transforms))
+    {
+        let meta = assets.get(bullet_handle.0);
+        let start = transform.translation.truncate();
+        let end = start + bullet.direction * meta.velocity * BULLET_LOOKAHEAD_FRAMES;
+        let half_height = emote_regions
+            .get(entity)
+            .map(|x| x.size.y)
+            .unwrap_or(meta.body_diameter)
+            / 2.0;
+        threat_map.threats.push(Threat {
+            entity,
+            kind: ThreatKind::Bullet,
+            area: Rect {
+                min: start.min(end) - vec2(0.0, half_height),
+                max: start.max(end) + vec2(0.0, half_height),
+            },
+            harmless_to: Some(bullet.owner),
+        });
+    }
+
+    // Falling below the map is only dangerous if the map doesn't wrap around vertically.
+    let graph_changed = !threat_map
+        .pits_graph
+        .as_ref()
+        .is_some_and(|x| Arc::ptr_eq(x, &nav_graph.0));
+    if graph_changed {
+        threat_map.pits = if spawned_map.rules.wrap_y {
+            default()
+        } else {
+            find_pits(&nav_graph.0, spawned_map.grid_size)
+        };
+        threat_map.pits_graph = Some(nav_graph.0.clone());
+    }
+}
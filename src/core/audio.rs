@@ -48,7 +48,14 @@ fn play_sounds(
     mut audio: ResMut<AudioManager>,
     mut audio_events: ResMut<AudioEvents>,
     assets: Res<AssetServer>,
+    resimulating: Option<Res<Resimulating>>,
 ) {
+    // The sounds have already been played the first time these frames were simulated.
+    if resimulating.map(|x| **x).unwrap_or(false) {
+        audio_events.queue.clear();
+        return;
+    }
+
     // Play all the sounds in the queue
     for event in audio_events.queue.drain(..) {
         match event {
@@ -145,7 +145,12 @@ fn update(
             .filter(|player| *player != bullet.owner)
             .for_each(|player| {
                 hit_player = true;
-                commands.add(PlayerCommand::kill(player, Some(position.translation.xy())));
+                commands.add(PlayerCommand::kill(
+                    player,
+                    Some(position.translation.xy()),
+                    Some(bullet.owner),
+                    DeathCause::Bullet,
+                ));
             });
 
         // check solid tile collisions
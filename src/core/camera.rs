@@ -42,7 +42,7 @@ pub struct CameraState {
 fn camera_controller(
     meta: Root<GameMeta>,
     entities: Res<Entities>,
-    map: Res<LoadedMap>,
+    map: Res<SpawnedMapMeta>,
     mut cameras: CompMut<Camera>,
     mut camera_shakes: CompMut<CameraShake>,
     mut camera_states: CompMut<CameraState>,
@@ -178,7 +178,7 @@ fn camera_parallax(
     mut transforms: CompMut<Transform>,
     parallax_bg_sprites: Comp<ParallaxBackgroundSprite>,
     cameras: Comp<Camera>,
-    map: Res<LoadedMap>,
+    map: Res<SpawnedMapMeta>,
 ) {
     // TODO: This constant represents that maximum camera-visible distance, and should be moved
     // somewhere more appropriate.
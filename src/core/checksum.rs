@@ -0,0 +1,182 @@
+//! Deterministic checksums of the match state.
+//!
+//! Two simulations of a match that are given the same inputs must stay identical, so comparing
+//! the [`StateChecksum`] of every frame finds the first frame where they diverged, whether that is
+//! between the players of an online match or between a replay and its playback.
+
+use std::collections::HashMap;
+
+use crate::prelude::*;
+
+/// Install this module.
+pub fn install(session: &mut Session) {
+    session.world.init_resource::<StateChecksum>();
+    session.world.init_resource::<ReferenceChecksums>();
+    session
+        .stages
+        .add_system_to_stage(CoreStage::Last, update_state_checksum);
+}
+
+/// Resource containing the checksum of the match state at the end of the latest frame.
+///
+/// The checksum covers the gameplay relevant state: the [`Transform`], [`KinematicBody`],
+/// [`PlayerState`], and [`Inventory`] components, the item components including the ammo of
+/// muskets and the timers of explosives, and the [`GlobalRng`].
+#[derive(HasSchema, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StateChecksum {
+    /// The frame that the checksum was taken on, counting from zero, or [`None`] before the
+    /// first frame.
+    pub frame: Option<u32>,
+    /// The checksum.
+    pub value: u64,
+}
+
+/// Resource containing checksums that the [`StateChecksum`] is expected to match, such as the
+/// ones recorded in a replay.
+///
+/// A warning is logged for the first frame that doesn't match.
+#[derive(HasSchema, Clone, Debug, Default)]
+pub struct ReferenceChecksums {
+    /// The expected checksums by frame.
+    pub checksums: HashMap<u32, u64>,
+    /// The first frame where the checksum didn't match the reference.
+    pub mismatch: Option<u32>,
+}
+
+/// A hasher that gives the same results on every platform and build.
+///
+/// This is FNV-1a, because the hasher from the standard library isn't guaranteed to be stable.
+struct StableHasher(u64);
+
+impl StableHasher {
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_f32s(&mut self, values: &[f32]) {
+        for value in values {
+            self.write(&value.to_bits().to_le_bytes());
+        }
+    }
+
+    fn write_entity(&mut self, entity: Entity) {
+        self.write_u64(entity.index() as u64);
+    }
+
+    fn write_str(&mut self, value: &str) {
+        self.write_u64(value.len() as u64);
+        self.write(value.as_bytes());
+    }
+
+    fn write_timer(&mut self, timer: &Timer) {
+        self.write_u64(timer.elapsed().as_nanos() as u64);
+    }
+}
+
+fn update_state_checksum(
+    entities: Res<Entities>,
+    transforms: Comp<Transform>,
+    bodies: Comp<KinematicBody>,
+    player_states: Comp<PlayerState>,
+    inventories: Comp<Inventory>,
+    items: Comp<Item>,
+    items_grabbed: Comp<ItemGrabbed>,
+    items_dropped: Comp<ItemDropped>,
+    items_used: Comp<ItemUsed>,
+    muskets: Comp<Musket>,
+    lit_grenades: Comp<LitGrenade>,
+    lit_kick_bombs: Comp<LitKickBomb>,
+    thrown_mines: Comp<ThrownMine>,
+    rng: Res<GlobalRng>,
+    mut checksum: ResMut<StateChecksum>,
+    mut reference: ResMut<ReferenceChecksums>,
+) {
+    let mut hasher = StableHasher::new();
+
+    for (ent, transform) in entities.iter_with(&transforms) {
+        hasher.write_entity(ent);
+        hasher.write_f32s(&transform.translation.to_array());
+        hasher.write_f32s(&transform.rotation.to_array());
+        hasher.write_f32s(&transform.scale.to_array());
+    }
+    for (ent, body) in entities.iter_with(&bodies) {
+        hasher.write_entity(ent);
+        hasher.write_f32s(&body.velocity.to_array());
+        hasher.write_f32s(&[body.angular_velocity]);
+        hasher.write(&[
+            body.is_on_ground as u8,
+            body.is_on_platform as u8,
+            body.is_deactivated as u8,
+            body.fall_through as u8,
+        ]);
+    }
+    for (ent, state) in entities.iter_with(&player_states) {
+        hasher.write_entity(ent);
+        hasher.write_str(state.current.as_str());
+        hasher.write_u64(state.age);
+    }
+    for (ent, inventory) in entities.iter_with(&inventories) {
+        hasher.write_entity(ent);
+        match inventory.0 {
+            Some(item) => hasher.write_entity(item),
+            None => hasher.write_u64(u64::MAX),
+        }
+    }
+    for (ent, _) in entities.iter_with(&items) {
+        hasher.write_entity(ent);
+        hasher.write(&[
+            items_grabbed.contains(ent) as u8,
+            items_dropped.contains(ent) as u8,
+            items_used.contains(ent) as u8,
+        ]);
+    }
+    for (ent, musket) in entities.iter_with(&muskets) {
+        hasher.write_entity(ent);
+        hasher.write_u64(musket.ammo as u64);
+        hasher.write_timer(&musket.cooldown);
+    }
+    for (ent, grenade) in entities.iter_with(&lit_grenades) {
+        hasher.write_entity(ent);
+        hasher.write_timer(&grenade.fuse_time);
+    }
+    for (ent, kick_bomb) in entities.iter_with(&lit_kick_bombs) {
+        hasher.write_entity(ent);
+        hasher.write_timer(&kick_bomb.arm_delay);
+        hasher.write_timer(&kick_bomb.fuse_time);
+    }
+    for (ent, mine) in entities.iter_with(&thrown_mines) {
+        hasher.write_entity(ent);
+        hasher.write_timer(&mine.arm_delay);
+    }
+    // Clone the RNG so that we don't advance it.
+    hasher.write_u64((*rng).clone().u64(..));
+
+    let frame = checksum.frame.map_or(0, |x| x + 1);
+    *checksum = StateChecksum {
+        frame: Some(frame),
+        value: hasher.0,
+    };
+
+    if reference.mismatch.is_none() {
+        if let Some(expected) = reference.checksums.get(&frame).copied() {
+            if expected != checksum.value {
+                warn!(
+                    "State checksum mismatch on frame {frame}: expected {expected:016x}, got {:016x}",
+                    checksum.value
+                );
+                reference.mismatch = Some(frame);
+            }
+        }
+    }
+}
@@ -21,6 +21,14 @@ pub fn install(session: &mut Session) {
 pub struct DamageRegion {
     /// The size of the damage region in pixels
     pub size: Vec2,
+    /// What players killed by the damage region die of.
+    pub cause: DeathCause,
+    /// The player responsible for kills by the damage region, if it isn't the
+    /// [`DamageRegionOwner`].
+    ///
+    /// Unlike the owner, this player may still be harmed by the damage region, like when they are
+    /// caught in the explosion of their own grenade.
+    pub killer: Option<Entity>,
 }
 
 impl DamageRegion {
@@ -59,12 +67,10 @@ fn kill_players_in_damage_region(
         let player_rect = body.bounding_box(*transform);
         for (ent, (damage_region, transform)) in entities.iter_with((&damage_regions, &transforms))
         {
-            let owner = damage_region_owners.get(ent);
+            let owner = damage_region_owners.get(ent).map(|x| x.0);
             // Don't damage the player that owns this damage region
-            if let Some(owner) = owner {
-                if owner.0 == player_ent {
-                    continue;
-                }
+            if owner == Some(player_ent) {
+                continue;
             }
 
             let damage_rect = damage_region.collider_rect(transform.translation);
@@ -72,6 +78,8 @@ fn kill_players_in_damage_region(
                 commands.add(PlayerCommand::kill(
                     player_ent,
                     Some(transform.translation.xy()),
+                    damage_region.killer.or(owner),
+                    damage_region.cause,
                 ));
             }
         }
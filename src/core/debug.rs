@@ -21,6 +21,8 @@ pub struct DebugSettings {
     pub show_damage_regions: bool,
     /// Whether or not to show the pathfinding lines.
     pub show_pathfinding_lines: bool,
+    /// Whether or not to show the [`StateChecksum`] overlay.
+    pub show_state_checksum: bool,
 }
 
 /// Resource containing the physics debug line entity.
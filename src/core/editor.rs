@@ -1,12 +1,23 @@
 //! Map editing implementation.
 //!
 //! Allows you to edit the game map while the game is running.
+//!
+//! Every change made through the [`MapManager`] records its inverse in the [`MapEditHistory`], so
+//! that it can be reverted with [`EditorInput::Undo`] and re-applied with [`EditorInput::Redo`].
+
+use std::collections::VecDeque;
 
-use crate::core::map_constructor::{shiftnanigans::ShiftnanigansMapConstructor, MapConstructor};
+use crate::core::map_constructor::{
+    shiftnanigans::ShiftnanigansMapConstructor, MapConstructor, MapConstructors,
+    RoundMapConstructor,
+};
+use crate::core::physics::collisions::Solid;
 use crate::prelude::*;
 
 /// Install this module.
 pub fn install(session: &mut Session) {
+    session.world.init_resource::<MapEditHistory>();
+    session.world.init_resource::<MapClipboard>();
     session
         .stages
         .add_system_to_stage(CoreStage::PreUpdate, handle_editor_input);
@@ -28,15 +39,179 @@ impl_system_param! {
         element_handles: CompMut<'a, ElementHandle>,
         transforms: CompMut<'a, Transform>,
         spawned_map_layer_metas: CompMut<'a, SpawnedMapLayerMeta>,
+        element_spawn_positions: CompMut<'a, ElementSpawnPos>,
         tile_layers: CompMut<'a, TileLayer>,
         tiles: CompMut<'a, Tile>,
         tile_collisions: CompMut<'a, TileCollisionKind>,
+        bodies: Comp<'a, KinematicBody>,
+        solids: Comp<'a, Solid>,
+        colliders: Comp<'a, Collider>,
         map: Res<'a, LoadedMap>,
         element_kill_callbacks: Comp<'a, ElementKillCallback>,
         spawner_manager: SpawnerManager<'a>,
+        history: ResMutInit<'a, MapEditHistory>,
+        nav_graph_dirty_tiles: ResMutInit<'a, NavGraphDirtyTiles>,
+        assets: Res<'a, AssetServer>,
     }
 }
 
+/// The maximum number of edits that are kept in the [`MapEditHistory`].
+pub const MAP_EDIT_HISTORY_LIMIT: usize = 100;
+
+/// A change to the map, as recorded in the [`MapEditHistory`].
+///
+/// The history stores the inverse of every change that is made through the [`MapManager`], so
+/// that applying the recorded edit reverts the change.
+#[derive(Clone, Debug)]
+pub enum MapEdit {
+    /// Create a map element.
+    CreateElement {
+        handle: Handle<ElementMeta>,
+        translation: Vec2,
+        layer: u32,
+        /// The entity of the deleted element that is being re-created, if any.
+        ///
+        /// Any references to it in the history are replaced with the new entity.
+        replaces: Option<Entity>,
+    },
+    /// Delete a map element.
+    DeleteElement { entity: Entity },
+    /// Move a map element.
+    MoveElement { entity: Entity, pos: Vec2 },
+    /// Set or, if `idx` is [`None`], delete a tile.
+    SetTile {
+        layer: u32,
+        pos: UVec2,
+        idx: Option<u32>,
+        collision: TileCollisionKind,
+    },
+    /// Delete a layer.
+    DeleteLayer { layer: u32 },
+    /// Re-create a deleted layer, with its tiles and elements.
+    RestoreLayer {
+        layer: u32,
+        name: Ustr,
+        tilemap: Option<Handle<Atlas>>,
+        tiles: Vec<MapTileMeta>,
+        /// The elements of the layer, along with the entity they had before the layer was deleted.
+        elements: Vec<(Entity, ElementSpawn)>,
+    },
+    /// Rename a layer.
+    RenameLayer { layer: u32, name: Ustr },
+    /// Swap a layer with the one below or above it.
+    SwapLayer { layer: u32, down: bool },
+    /// Set the tilemap of a layer.
+    SetLayerTilemap {
+        layer: u32,
+        tilemap: Option<Handle<Atlas>>,
+    },
+    /// Rename the map.
+    RenameMap { name: Ustr },
+    /// Resize the map, moving its contents.
+    Resize { size: UVec2, offset: IVec2 },
+}
+
+impl MapEdit {
+    /// Replace references to an entity that has been re-created.
+    fn replace_entity(&mut self, old: Entity, new: Entity) {
+        let replace = |entity: &mut Entity| {
+            if *entity == old {
+                *entity = new;
+            }
+        };
+        match self {
+            MapEdit::CreateElement {
+                replaces: Some(entity),
+                ..
+            }
+            | MapEdit::DeleteElement { entity }
+            | MapEdit::MoveElement { entity, .. } => replace(entity),
+            MapEdit::RestoreLayer { elements, .. } => {
+                elements.iter_mut().for_each(|(entity, _)| replace(entity))
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Resource containing the undo and redo history of the map editor.
+///
+/// Each entry is the list of [`MapEdit`]s that revert a single [`EditorInput`], in the order that
+/// they were recorded.
+#[derive(HasSchema, Clone, Debug, Default)]
+pub struct MapEditHistory {
+    undo: Vec<Vec<MapEdit>>,
+    redo: Vec<Vec<MapEdit>>,
+    /// The edits recorded since the last editor input was committed.
+    pending: Vec<MapEdit>,
+}
+
+impl MapEditHistory {
+    /// Whether or not there is an edit that can be undone.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Whether or not there is an edit that can be redone.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Record the inverse of a change to the map.
+    fn record(&mut self, edit: MapEdit) {
+        self.pending.push(edit);
+    }
+
+    /// Push the recorded edits to the undo history as a single entry.
+    ///
+    /// This clears the redo history if anything was recorded.
+    fn commit(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        self.undo.push(std::mem::take(&mut self.pending));
+        self.redo.clear();
+        if self.undo.len() > MAP_EDIT_HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Replace references to an entity that has been re-created.
+    fn replace_entity(&mut self, old: Entity, new: Entity) {
+        self.undo
+            .iter_mut()
+            .chain(self.redo.iter_mut())
+            .flatten()
+            .chain(self.pending.iter_mut())
+            .for_each(|edit| edit.replace_entity(old, new));
+    }
+}
+
+/// How the connected tiles are selected by [`MapManager::flood_fill()`].
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FloodFillMode {
+    /// Fill the tiles with the same tile index as the starting tile, or the empty tiles if it is
+    /// empty.
+    #[default]
+    TileIndex,
+    /// Fill the tiles with the same collision kind as the starting tile.
+    Collision,
+}
+
+/// Resource containing a rectangular region of the map, copied with
+/// [`MapManager::copy_region()`].
+#[derive(HasSchema, Clone, Debug, Default)]
+pub struct MapClipboard {
+    /// The size of the region in tiles.
+    pub size: UVec2,
+    /// The layer index, position relative to the bottom-left corner of the region, tile index and
+    /// collision of every tile in the region. Empty tiles don't have a tile index.
+    pub tiles: Vec<(u32, UVec2, Option<u32>, TileCollisionKind)>,
+    /// The layer index, position relative to the bottom-left corner of the region, and handle of
+    /// every element in the region.
+    pub elements: Vec<(u32, Vec2, Handle<ElementMeta>)>,
+}
+
 impl<'a> MapManager<'a> {
     /// Create a new map element at the given location on the given layer.
     pub fn create_element(
@@ -44,7 +219,7 @@ impl<'a> MapManager<'a> {
         element_meta_handle: &Handle<ElementMeta>,
         translation: &Vec2,
         layer_index: u32,
-    ) {
+    ) -> Entity {
         let entity = self.entities.create();
         // TODO remove element handles as the underlying elements are removed
         self.element_handles
@@ -54,12 +229,16 @@ impl<'a> MapManager<'a> {
             entity,
             Transform::from_translation(translation.extend(z_depth)),
         );
+        self.element_spawn_positions
+            .insert(entity, ElementSpawnPos(*translation));
         self.spawned_map_layer_metas.insert(
             entity,
             SpawnedMapLayerMeta {
                 layer_idx: layer_index,
             },
         );
+        self.history.record(MapEdit::DeleteElement { entity });
+        entity
     }
     /// Create a new layer with the given name.
     pub fn create_layer(&mut self, name: Ustr) {
@@ -91,9 +270,13 @@ impl<'a> MapManager<'a> {
             entity,
             Transform::from_translation(Vec3::new(0.0, 0.0, z_depth_for_map_layer(layer_index))),
         );
+        self.history
+            .record(MapEdit::DeleteLayer { layer: layer_index });
     }
     /// Delete the layer with the given index.
     pub fn delete_layer(&mut self, layer_index: u32) {
+        self.record_deleted_layer(layer_index);
+
         let layer_count = self.spawned_map_meta.layer_names.len() as u32;
         let layers_to_decrement = layer_count - layer_index;
         self.spawned_map_meta.layer_names = self
@@ -112,6 +295,8 @@ impl<'a> MapManager<'a> {
             })
             .collect();
         let mut to_kill = Vec::new();
+        let mut changed_tiles = Vec::new();
+        let grid_size = self.spawned_map_meta.grid_size;
         self.entities
             .iter_with(&mut self.spawned_map_layer_metas)
             .for_each(|(entity, layer)| {
@@ -122,6 +307,13 @@ impl<'a> MapManager<'a> {
                         for tile in tile_layer.tiles.iter().flatten() {
                             to_kill.push(*tile);
                         }
+                        for y in 0..grid_size.y {
+                            for x in 0..grid_size.x {
+                                if tile_layer.get(uvec2(x, y)).is_some() {
+                                    changed_tiles.push(NavNode(ivec2(x as i32, y as i32)));
+                                }
+                            }
+                        }
                     }
                 };
 
@@ -129,12 +321,124 @@ impl<'a> MapManager<'a> {
                     layer.layer_idx -= 1;
                 }
             });
+        for &entity in &to_kill {
+            changed_tiles.extend(self.element_nav_nodes(entity));
+        }
         to_kill.into_iter().for_each(|ent| {
             self.entities.kill(ent);
         });
+        self.update_nav_graph(changed_tiles);
+    }
+    /// Record the contents of a layer that is about to be deleted, so that it can be restored.
+    fn record_deleted_layer(&mut self, layer_index: u32) {
+        let Some(name) = self
+            .spawned_map_meta
+            .layer_names
+            .get(layer_index as usize)
+            .copied()
+        else {
+            return;
+        };
+
+        let mut tilemap = None;
+        let mut tiles = Vec::new();
+        if let Some((_, (tile_layer, _))) = self
+            .entities
+            .iter_with((&self.tile_layers, &self.spawned_map_layer_metas))
+            .find(|x| x.1 .1.layer_idx == layer_index)
+        {
+            tilemap = (tile_layer.atlas != default()).then_some(tile_layer.atlas);
+            for y in 0..self.spawned_map_meta.grid_size.y {
+                for x in 0..self.spawned_map_meta.grid_size.x {
+                    let pos = UVec2 { x, y };
+                    if let Some(tile) = tile_layer.get(pos).and_then(|ent| {
+                        self.tiles.get(ent).map(|tile| MapTileMeta {
+                            pos,
+                            idx: tile.idx,
+                            collision: self.tile_collisions.get(ent).copied().unwrap_or_default(),
+                        })
+                    }) {
+                        tiles.push(tile);
+                    }
+                }
+            }
+        }
+
+        let elements = self
+            .entities
+            .iter_with((
+                &self.element_handles,
+                &self.transforms,
+                &self.spawned_map_layer_metas,
+            ))
+            .filter(|x| x.1 .2.layer_idx == layer_index)
+            .map(|(entity, (element_handle, transform, _))| {
+                (
+                    entity,
+                    ElementSpawn {
+                        pos: self
+                            .element_spawn_positions
+                            .get(entity)
+                            .map_or(transform.translation.truncate(), |x| x.0),
+                        element: element_handle.0,
+                    },
+                )
+            })
+            .collect();
+
+        self.history.record(MapEdit::RestoreLayer {
+            layer: layer_index,
+            name,
+            tilemap,
+            tiles,
+            elements,
+        });
+    }
+    /// Re-create a deleted layer at the given index, along with its tiles and elements.
+    fn restore_layer(
+        &mut self,
+        layer_index: u32,
+        name: Ustr,
+        tilemap: Option<Handle<Atlas>>,
+        tiles: Vec<MapTileMeta>,
+        elements: Vec<(Entity, ElementSpawn)>,
+    ) {
+        // Restoring the layer is recorded as a single edit, instead of every step it takes.
+        let pending = std::mem::take(&mut self.history.pending);
+
+        self.create_layer(name);
+        let mut current_index = self.get_layers_total() as u32 - 1;
+        while current_index > layer_index {
+            self.swap_layer(current_index, false);
+            current_index -= 1;
+        }
+        self.set_layer_tilemap(layer_index, &tilemap);
+        for tile in tiles {
+            self.set_tile(layer_index, tile.pos, &Some(tile.idx), tile.collision);
+        }
+        let replaced_entities = elements
+            .into_iter()
+            .map(|(old, element)| {
+                let new = self.create_element(&element.element, &element.pos, layer_index);
+                (old, new)
+            })
+            .collect::<Vec<_>>();
+
+        self.history.pending = pending;
+        for (old, new) in replaced_entities {
+            self.history.replace_entity(old, new);
+        }
+        self.history
+            .record(MapEdit::DeleteLayer { layer: layer_index });
     }
     /// Rename the layer with the given index.
     pub fn rename_layer(&mut self, layer_index: u32, name: &str) {
+        if let Some(&old_name) = self.spawned_map_meta.layer_names.get(layer_index as usize) {
+            self.history.record(MapEdit::RenameLayer {
+                layer: layer_index,
+                name: old_name,
+            });
+        }
         self.spawned_map_meta.layer_names = self
             .spawned_map_meta
             .layer_names
@@ -153,12 +457,40 @@ impl<'a> MapManager<'a> {
     }
     /// Move an element to a new position on the map.
     pub fn move_element(&mut self, entity: Entity, position: &Vec2) {
+        let mut changed_tiles = self.element_nav_nodes(entity);
         let transform = self.transforms.get_mut(entity).unwrap();
+        self.history.record(MapEdit::MoveElement {
+            entity,
+            pos: self
+                .element_spawn_positions
+                .get(entity)
+                .map_or(transform.translation.truncate(), |x| x.0),
+        });
         transform.translation.x = position.x;
         transform.translation.y = position.y;
+        self.element_spawn_positions
+            .insert(entity, ElementSpawnPos(*position));
+        changed_tiles.extend(self.element_nav_nodes(entity));
+        self.update_nav_graph(changed_tiles);
     }
     /// Delete an element off of the map.
     pub fn delete_element(&mut self, entity: Entity) {
+        let changed_tiles = self.element_nav_nodes(entity);
+        if let (Some(element_handle), Some(transform), Some(layer_meta)) = (
+            self.element_handles.get(entity),
+            self.transforms.get(entity),
+            self.spawned_map_layer_metas.get(entity),
+        ) {
+            self.history.record(MapEdit::CreateElement {
+                handle: element_handle.0,
+                translation: self
+                    .element_spawn_positions
+                    .get(entity)
+                    .map_or(transform.translation.truncate(), |x| x.0),
+                layer: layer_meta.layer_idx,
+                replaces: Some(entity),
+            });
+        }
         if let Some(element_kill_callback) = self.element_kill_callbacks.get(entity) {
             let system = element_kill_callback.system.clone();
             self.commands
@@ -174,6 +506,7 @@ impl<'a> MapManager<'a> {
         } else {
             self.entities.kill(entity);
         }
+        self.update_nav_graph(changed_tiles);
     }
     /// Set the tilemap for the given layer.
     pub fn set_layer_tilemap(&mut self, layer_index: u32, tilemap: &Option<Handle<Atlas>>) {
@@ -182,6 +515,10 @@ impl<'a> MapManager<'a> {
             .iter_with((&mut self.tile_layers, &self.spawned_map_layer_metas))
             .find(|x| x.1 .1.layer_idx == layer_index)
         {
+            self.history.record(MapEdit::SetLayerTilemap {
+                layer: layer_index,
+                tilemap: (tile_layer.atlas != default()).then_some(tile_layer.atlas),
+            });
             if let Some(handle) = tilemap {
                 tile_layer.atlas = *handle;
             } else {
@@ -197,11 +534,255 @@ impl<'a> MapManager<'a> {
         tilemap_tile_index: &Option<u32>,
         tile_collision_kind: TileCollisionKind,
     ) {
+        if self.write_tile(
+            layer_index,
+            position,
+            tilemap_tile_index,
+            tile_collision_kind,
+        ) {
+            self.commands
+                .add(move |mut collision_world: CollisionWorld| {
+                    collision_world.update_tile(layer_index, position);
+                });
+            self.update_nav_graph([NavNode(position.as_ivec2())]);
+        }
+    }
+    /// Get the tile index and collision of a tile on the given layer, if there is a tile.
+    pub fn get_tile(&self, layer_index: u32, position: UVec2) -> Option<(u32, TileCollisionKind)> {
+        let (_, (tile_layer, _)) = self
+            .entities
+            .iter_with((&self.tile_layers, &self.spawned_map_layer_metas))
+            .find(|x| x.1 .1.layer_idx == layer_index)?;
+        let entity = tile_layer.get(position)?;
+        let tile = self.tiles.get(entity)?;
+        let collision = self.tile_collisions.get(entity).copied();
+        Some((tile.idx, collision.unwrap_or_default()))
+    }
+    /// Set every tile in a rectangle on the given layer, from `start` to `start + size`.
+    ///
+    /// The collisions are updated once for the whole rectangle.
+    pub fn fill_rect(
+        &mut self,
+        layer_index: u32,
+        start: UVec2,
+        size: UVec2,
+        tilemap_tile_index: &Option<u32>,
+        tile_collision_kind: TileCollisionKind,
+    ) {
+        let end = start
+            .saturating_add(size)
+            .min(self.spawned_map_meta.grid_size);
+        let mut changed = HashSet::default();
+        for y in start.y..end.y {
+            for x in start.x..end.x {
+                let position = UVec2 { x, y };
+                if self.write_tile(
+                    layer_index,
+                    position,
+                    tilemap_tile_index,
+                    tile_collision_kind,
+                ) {
+                    changed.insert((layer_index, position));
+                }
+            }
+        }
+        self.update_tile_collisions(changed);
+    }
+    /// Set the tile at the given position, and every tile connected to it that matches it
+    /// according to the [`FloodFillMode`].
+    ///
+    /// The collisions are updated once for the whole filled region.
+    pub fn flood_fill(
+        &mut self,
+        layer_index: u32,
+        position: UVec2,
+        tilemap_tile_index: &Option<u32>,
+        tile_collision_kind: TileCollisionKind,
+        mode: FloodFillMode,
+    ) {
+        let grid_size = self.spawned_map_meta.grid_size;
+        if position.cmpge(grid_size).any() {
+            return;
+        }
+        let target = self.get_tile(layer_index, position);
+        let matches = |tile: Option<(u32, TileCollisionKind)>| match mode {
+            FloodFillMode::TileIndex => tile.map(|x| x.0) == target.map(|x| x.0),
+            FloodFillMode::Collision => {
+                tile.map(|x| x.1).unwrap_or_default() == target.map(|x| x.1).unwrap_or_default()
+            }
+        };
+
+        // Find the connected tiles before changing any of them
+        let mut visited = HashSet::default();
+        visited.insert(position);
+        let mut queue = VecDeque::from([position]);
+        let mut region = Vec::new();
+        while let Some(pos) = queue.pop_front() {
+            region.push(pos);
+            for offset in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+                let next = pos.as_ivec2() + offset;
+                if next.cmplt(IVec2::ZERO).any() || next.cmpge(grid_size.as_ivec2()).any() {
+                    continue;
+                }
+                let next = next.as_uvec2();
+                if visited.insert(next) && matches(self.get_tile(layer_index, next)) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let mut changed = HashSet::default();
+        for position in region {
+            if self.write_tile(
+                layer_index,
+                position,
+                tilemap_tile_index,
+                tile_collision_kind,
+            ) {
+                changed.insert((layer_index, position));
+            }
+        }
+        self.update_tile_collisions(changed);
+    }
+    /// Copy the tiles and elements of every layer in a rectangle, from `start` to `start + size`.
+    ///
+    /// Elements are copied from the position they were placed at, not where they are now.
+    pub fn copy_region(&self, start: UVec2, size: UVec2) -> MapClipboard {
+        let grid_size = self.spawned_map_meta.grid_size;
+        let tile_size = self.spawned_map_meta.tile_size;
+        let start = start.min(grid_size);
+        let end = start.saturating_add(size).min(grid_size);
+
+        let mut clipboard = MapClipboard {
+            size: end - start,
+            ..default()
+        };
+        for (_, (tile_layer, layer_meta)) in self
+            .entities
+            .iter_with((&self.tile_layers, &self.spawned_map_layer_metas))
+        {
+            for y in start.y..end.y {
+                for x in start.x..end.x {
+                    let position = UVec2 { x, y };
+                    let tile = tile_layer.get(position).and_then(|entity| {
+                        let collision = self.tile_collisions.get(entity).copied();
+                        self.tiles
+                            .get(entity)
+                            .map(|tile| (tile.idx, collision.unwrap_or_default()))
+                    });
+                    clipboard.tiles.push((
+                        layer_meta.layer_idx,
+                        position - start,
+                        tile.map(|x| x.0),
+                        tile.map(|x| x.1).unwrap_or_default(),
+                    ));
+                }
+            }
+        }
+        for (entity, (element_handle, transform, layer_meta)) in self.entities.iter_with((
+            &self.element_handles,
+            &self.transforms,
+            &self.spawned_map_layer_metas,
+        )) {
+            let pos = self
+                .element_spawn_positions
+                .get(entity)
+                .map_or(transform.translation.truncate(), |x| x.0);
+            let tile_pos = (pos / tile_size).floor();
+            if tile_pos.cmpge(start.as_vec2()).all() && tile_pos.cmplt(end.as_vec2()).all() {
+                clipboard.elements.push((
+                    layer_meta.layer_idx,
+                    pos - start.as_vec2() * tile_size,
+                    element_handle.0,
+                ));
+            }
+        }
+
+        clipboard
+    }
+    /// Paste a region copied with [`copy_region()`][Self::copy_region] with its bottom-left
+    /// corner at the given position.
+    ///
+    /// Empty tiles in the region clear the tiles they are pasted over. Tiles and elements that
+    /// would be outside of the map, or on a layer that doesn't exist, are skipped. The collisions
+    /// are updated once for the whole region.
+    pub fn paste_region(&mut self, clipboard: &MapClipboard, position: UVec2) {
+        let grid_size = self.spawned_map_meta.grid_size;
+        let tile_size = self.spawned_map_meta.tile_size;
+        let layers_total = self.get_layers_total() as u32;
+
+        let mut changed = HashSet::default();
+        for &(layer_index, offset, idx, collision) in &clipboard.tiles {
+            let tile_pos = position.saturating_add(offset);
+            if tile_pos.cmplt(grid_size).all()
+                && self.write_tile(layer_index, tile_pos, &idx, collision)
+            {
+                changed.insert((layer_index, tile_pos));
+            }
+        }
+        self.update_tile_collisions(changed);
+
+        for &(layer_index, offset, handle) in &clipboard.elements {
+            let translation = position.as_vec2() * tile_size + offset;
+            if layer_index < layers_total
+                && (translation / tile_size)
+                    .floor()
+                    .cmplt(grid_size.as_vec2())
+                    .all()
+            {
+                self.create_element(&handle, &translation, layer_index);
+            }
+        }
+    }
+    /// Update the collisions of the given tiles, with a single collision world update.
+    fn update_tile_collisions(&mut self, tiles: HashSet<(u32, UVec2)>) {
+        if tiles.is_empty() {
+            return;
+        }
+        self.update_nav_graph(tiles.iter().map(|(_, pos)| NavNode(pos.as_ivec2())));
+        self.commands
+            .add(move |mut collision_world: CollisionWorld| {
+                collision_world.update_tiles_with_filter(|layer_index, position| {
+                    tiles.contains(&(layer_index, position))
+                });
+            });
+    }
+    /// Set a tile without updating its collision, returning whether or not it was changed.
+    fn write_tile(
+        &mut self,
+        layer_index: u32,
+        position: UVec2,
+        tilemap_tile_index: &Option<u32>,
+        tile_collision_kind: TileCollisionKind,
+    ) -> bool {
         if let Some((_, (tile_layer, _))) = self
             .entities
             .iter_with((&mut self.tile_layers, &self.spawned_map_layer_metas))
             .find(|x| x.1 .1.layer_idx == layer_index)
         {
+            let previous = tile_layer.get(position).and_then(|entity| {
+                self.tiles.get(entity).map(|tile| {
+                    let collision = self.tile_collisions.get(entity).copied();
+                    (tile.idx, collision.unwrap_or_default())
+                })
+            });
+            let unchanged = match (previous, tilemap_tile_index) {
+                (None, None) => true,
+                (Some((idx, collision)), Some(new_idx)) => {
+                    idx == *new_idx && collision == tile_collision_kind
+                }
+                _ => false,
+            };
+            if unchanged {
+                return false;
+            }
+            self.history.record(MapEdit::SetTile {
+                layer: layer_index,
+                pos: position,
+                idx: previous.map(|x| x.0),
+                collision: previous.map(|x| x.1).unwrap_or_default(),
+            });
+
             if let Some(entity) = tile_layer.get(position) {
                 if let Some(idx) = tilemap_tile_index.as_ref() {
                     self.tiles.get_mut(entity).unwrap().idx = *idx;
@@ -239,11 +820,10 @@ impl<'a> MapManager<'a> {
                 }
             }
 
-            self.commands
-                .add(move |mut collision_world: CollisionWorld| {
-                    collision_world.update_tile(layer_index, position);
-                });
-        };
+            true
+        } else {
+            false
+        }
     }
     /// Swap the position of two layers.
     pub fn swap_layer(&mut self, layer_index: u32, is_downward: bool) {
@@ -253,6 +833,11 @@ impl<'a> MapManager<'a> {
         } else {
             origin_layer_index - 1
         };
+        self.history.record(MapEdit::SwapLayer {
+            layer: other_layer_index,
+            down: !is_downward,
+        });
+
         let mut layer_names = self.spawned_map_meta.layer_names.to_vec();
         layer_names.swap(origin_layer_index as usize, other_layer_index as usize);
         self.spawned_map_meta.layer_names = layer_names.into_iter().collect();
@@ -272,8 +857,112 @@ impl<'a> MapManager<'a> {
     }
     /// Rename the map.
     pub fn rename_map(&mut self, name: &str) {
+        self.history.record(MapEdit::RenameMap {
+            name: self.spawned_map_meta.name,
+        });
         self.spawned_map_meta.name = ustr(name);
     }
+    /// Resize the map to the given size in tiles, moving its contents by `offset` tiles.
+    ///
+    /// This can grow or crop the map on any edge. For example, adding a column on the left of the
+    /// map is done with a size that is one tile wider and an offset of one tile to the right. Tiles
+    /// and elements that end up outside of the map are deleted.
+    pub fn resize(&mut self, size: UVec2, offset: IVec2) {
+        let old_size = self.spawned_map_meta.grid_size;
+        let tile_size = self.spawned_map_meta.tile_size;
+        let pixel_offset = offset.as_vec2() * tile_size;
+        let in_bounds =
+            |pos: IVec2| pos.cmpge(IVec2::ZERO).all() && pos.cmplt(size.as_ivec2()).all();
+
+        // Delete the elements that end up outside of the map
+        let outside_elements = self
+            .entities
+            .iter_with((
+                &self.element_handles,
+                &self.transforms,
+                &self.spawned_map_layer_metas,
+            ))
+            .filter(|(entity, (_, transform, _))| {
+                let pos = self
+                    .element_spawn_positions
+                    .get(*entity)
+                    .map_or(transform.translation.truncate(), |x| x.0)
+                    + pixel_offset;
+                !in_bounds((pos / tile_size).floor().as_ivec2())
+            })
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        for entity in outside_elements {
+            self.delete_element(entity);
+        }
+
+        // Move the tiles to new tile layers of the new size
+        let mut to_kill = Vec::new();
+        for (_, (tile_layer, layer_meta)) in self
+            .entities
+            .iter_with((&mut self.tile_layers, &self.spawned_map_layer_metas))
+        {
+            let mut new_tile_layer = TileLayer::new(size, tile_size, tile_layer.atlas);
+            for y in 0..old_size.y {
+                for x in 0..old_size.x {
+                    let pos = UVec2 { x, y };
+                    let Some(tile_ent) = tile_layer.get(pos) else {
+                        continue;
+                    };
+                    let new_pos = pos.as_ivec2().saturating_add(offset);
+                    if in_bounds(new_pos) {
+                        new_tile_layer.set(new_pos.as_uvec2(), Some(tile_ent));
+                    } else {
+                        if let Some(tile) = self.tiles.get(tile_ent) {
+                            self.history.record(MapEdit::SetTile {
+                                layer: layer_meta.layer_idx,
+                                pos,
+                                idx: Some(tile.idx),
+                                collision: self
+                                    .tile_collisions
+                                    .get(tile_ent)
+                                    .copied()
+                                    .unwrap_or_default(),
+                            });
+                        }
+                        to_kill.push(tile_ent);
+                    }
+                }
+            }
+            *tile_layer = new_tile_layer;
+        }
+        for entity in to_kill {
+            self.entities.kill(entity);
+        }
+
+        // Move the elements, and everything in the map along with them
+        for (entity, transform) in self.entities.iter_with(&mut self.transforms) {
+            let is_element = self.element_handles.contains(entity)
+                && self.spawned_map_layer_metas.contains(entity);
+            if is_element || self.bodies.contains(entity) {
+                transform.translation += pixel_offset.extend(0.0);
+            }
+        }
+        for (_, spawn_pos) in self.entities.iter_with(&mut self.element_spawn_positions) {
+            spawn_pos.0 += pixel_offset;
+        }
+
+        self.spawned_map_meta.grid_size = size;
+        self.history.record(MapEdit::Resize {
+            size: old_size,
+            offset: -offset,
+        });
+
+        self.commands
+            .add(|mut collision_world: CollisionWorld| collision_world.update_tiles());
+        self.regenerate_nav_graph();
+    }
+    /// Move the contents of the map by `offset` tiles, without resizing it.
+    ///
+    /// Tiles and elements that end up outside of the map are deleted.
+    pub fn shift(&mut self, offset: IVec2) {
+        self.resize(self.spawned_map_meta.grid_size, offset);
+    }
     /// Get the size of the map.
     pub fn get_size(&self) -> UVec2 {
         self.spawned_map_meta.grid_size
@@ -282,17 +971,141 @@ impl<'a> MapManager<'a> {
     pub fn get_layers_total(&self) -> usize {
         self.spawned_map_meta.layer_names.len()
     }
+    /// Get the size of the map tiles.
+    pub fn get_tile_size(&self) -> Vec2 {
+        self.spawned_map_meta.tile_size
+    }
+    /// Get the settings that the [`NavGraph`] of the map is made with.
+    pub fn get_nav_graph_settings(&self) -> NavGraphSettings {
+        NavGraphSettings::for_default_player(&self.assets, &self.spawned_map_meta.rules)
+    }
+    /// Get the tiles of every layer.
+    pub fn get_tile_layers(&self) -> Vec<LocatedTileLayer> {
+        let mut layers = self
+            .entities
+            .iter_with((&self.tile_layers, &self.spawned_map_layer_metas))
+            .map(|(_, (tile_layer, layer_meta))| {
+                let mut located_tiles = Vec::new();
+                for y in 0..tile_layer.grid_size.y {
+                    for x in 0..tile_layer.grid_size.x {
+                        let position = UVec2 { x, y };
+                        let Some(entity) = tile_layer.get(position) else {
+                            continue;
+                        };
+                        if let Some(tile) = self.tiles.get(entity) {
+                            let collision = self.tile_collisions.get(entity).copied();
+                            located_tiles.push((position, tile.idx, collision.unwrap_or_default()));
+                        }
+                    }
+                }
+                LocatedTileLayer {
+                    layer_index: layer_meta.layer_idx,
+                    located_tiles,
+                }
+            })
+            .collect::<Vec<_>>();
+        layers.sort_by_key(|x| x.layer_index);
+        layers
+    }
+    /// Get the elements of every layer, with their positions in tiles.
+    pub fn get_element_layers(&self) -> Vec<ElementLayer> {
+        let tile_size = self.spawned_map_meta.tile_size;
+        let mut layers = (0..self.get_layers_total() as u32)
+            .map(|layer_index| ElementLayer {
+                layer_index,
+                ..default()
+            })
+            .collect::<Vec<_>>();
+        for (_, (element_handle, transform, layer_meta)) in self.entities.iter_with((
+            &self.element_handles,
+            &self.transforms,
+            &self.spawned_map_layer_metas,
+        )) {
+            if let Some(layer) = layers.get_mut(layer_meta.layer_idx as usize) {
+                layer.located_elements.push((
+                    transform.translation.truncate() / tile_size,
+                    element_handle.0,
+                ));
+            }
+        }
+        layers
+    }
+    /// Get the entity, position and layer index of every element on the map.
+    pub fn get_elements(&self) -> Vec<(Entity, Vec2, u32)> {
+        self.entities
+            .iter_with((
+                &self.element_handles,
+                &self.transforms,
+                &self.spawned_map_layer_metas,
+            ))
+            .map(|(entity, (_, transform, layer_meta))| {
+                (
+                    entity,
+                    transform.translation.truncate(),
+                    layer_meta.layer_idx,
+                )
+            })
+            .collect()
+    }
+    /// Whether or not the element is a player spawner.
+    ///
+    /// This works for elements that haven't been hydrated yet, too.
+    pub fn is_player_spawner(&self, entity: Entity) -> bool {
+        self.element_handles.get(entity).map_or(false, |handle| {
+            let element_meta = self.assets.get(handle.0);
+            self.assets
+                .get(element_meta.data)
+                .try_cast_ref::<PlayerSpawner>()
+                .is_ok()
+        })
+    }
+    /// Re-create the [`NavGraph`] from the current tiles of the map, once the edits have been
+    /// applied.
+    pub fn regenerate_nav_graph(&mut self) {
+        self.commands.add(rebuild_nav_graph);
+    }
+    /// Update the [`NavGraph`] around the given tiles, once the edits have been applied.
+    ///
+    /// This is done by every edit that changes tiles or moves elements. The tiles are collected in
+    /// the [`NavGraphDirtyTiles`], so that the graph is only updated once for all of the edits in a
+    /// frame.
+    pub fn update_nav_graph(&mut self, changed_tiles: impl IntoIterator<Item = NavNode>) {
+        if self.nav_graph_dirty_tiles.mark(changed_tiles) {
+            self.commands.add(update_nav_graph);
+        }
+    }
+    /// Get the navigation graph nodes that an element affects: the tile it is in, in case it is a
+    /// sproinger, and the tiles it covers if it is solid.
+    fn element_nav_nodes(&self, entity: Entity) -> Vec<NavNode> {
+        let Some(transform) = self.transforms.get(entity) else {
+            return Vec::new();
+        };
+        let tile_size = self.spawned_map_meta.tile_size;
+        let mut nodes = vec![sproinger_nav_node(
+            transform.translation.truncate(),
+            tile_size,
+        )];
+        if let (Some(_), Some(collider)) = (self.solids.get(entity), self.colliders.get(entity)) {
+            nodes.extend(solid_nav_nodes(collider, transform, tile_size));
+        }
+        nodes
+    }
     /// Clear all the tiles on the map.
     pub fn clear_tiles(&mut self) {
         let empty_tile: Option<u32> = Option::None;
+        let mut changed = HashSet::default();
         for y in 0..self.spawned_map_meta.grid_size.y {
             for x in 0..self.spawned_map_meta.grid_size.x {
                 let position = UVec2 { x, y };
                 for layer_index in 0..self.spawned_map_meta.layer_names.len() as u32 {
-                    self.set_tile(layer_index, position, &empty_tile, TileCollisionKind::Empty);
+                    if self.write_tile(layer_index, position, &empty_tile, TileCollisionKind::Empty)
+                    {
+                        changed.insert((layer_index, position));
+                    }
                 }
             }
         }
+        self.update_tile_collisions(changed);
     }
     /// Clear all of the elements on the map.
     pub fn clear_elements(&mut self) {
@@ -307,10 +1120,97 @@ impl<'a> MapManager<'a> {
             self.delete_element(entity);
         });
     }
+    /// Revert the last edit in the [`MapEditHistory`].
+    pub fn undo(&mut self) {
+        if let Some(edits) = self.history.undo.pop() {
+            let redo = self.apply_edits(edits);
+            self.history.redo.push(redo);
+        }
+    }
+    /// Re-apply the last edit that was reverted with [`undo()`][Self::undo].
+    pub fn redo(&mut self) {
+        if let Some(edits) = self.history.redo.pop() {
+            let undo = self.apply_edits(edits);
+            self.history.undo.push(undo);
+        }
+    }
+    /// Push the edits made since the last commit to the [`MapEditHistory`] as a single entry, so
+    /// that they are undone together.
+    pub fn commit_edits(&mut self) {
+        self.history.commit();
+    }
+    /// Apply a list of recorded edits in reverse order, returning the edits that revert them.
+    ///
+    /// The collisions of the tiles that are changed are updated together.
+    fn apply_edits(&mut self, edits: Vec<MapEdit>) -> Vec<MapEdit> {
+        let pending = std::mem::take(&mut self.history.pending);
+        let mut changed = HashSet::default();
+        for edit in edits.into_iter().rev() {
+            if let MapEdit::SetTile {
+                layer,
+                pos,
+                idx,
+                collision,
+            } = edit
+            {
+                if self.write_tile(layer, pos, &idx, collision) {
+                    changed.insert((layer, pos));
+                }
+            } else {
+                self.apply_edit(edit);
+            }
+        }
+        self.update_tile_collisions(changed);
+        std::mem::replace(&mut self.history.pending, pending)
+    }
+    /// Apply a single recorded edit.
+    fn apply_edit(&mut self, edit: MapEdit) {
+        match edit {
+            MapEdit::CreateElement {
+                handle,
+                translation,
+                layer,
+                replaces,
+            } => {
+                let entity = self.create_element(&handle, &translation, layer);
+                if let Some(old) = replaces {
+                    self.history.replace_entity(old, entity);
+                }
+            }
+            MapEdit::DeleteElement { entity } => self.delete_element(entity),
+            MapEdit::MoveElement { entity, pos } => self.move_element(entity, &pos),
+            MapEdit::SetTile {
+                layer,
+                pos,
+                idx,
+                collision,
+            } => self.set_tile(layer, pos, &idx, collision),
+            MapEdit::DeleteLayer { layer } => self.delete_layer(layer),
+            MapEdit::RestoreLayer {
+                layer,
+                name,
+                tilemap,
+                tiles,
+                elements,
+            } => self.restore_layer(layer, name, tilemap, tiles, elements),
+            MapEdit::RenameLayer { layer, name } => self.rename_layer(layer, &name),
+            MapEdit::SwapLayer { layer, down } => self.swap_layer(layer, down),
+            MapEdit::SetLayerTilemap { layer, tilemap } => self.set_layer_tilemap(layer, &tilemap),
+            MapEdit::RenameMap { name } => self.rename_map(&name),
+            MapEdit::Resize { size, offset } => self.resize(size, offset),
+        }
+    }
 }
 
 /// Handles user input comming from the editor and makes the required changes to the map.
-fn handle_editor_input(player_inputs: Res<MatchInputs>, mut map_manager: MapManager) {
+fn handle_editor_input(
+    player_inputs: Res<MatchInputs>,
+    rng: Res<GlobalRng>,
+    map_constructors: Res<MapConstructors>,
+    mut round_map_constructor: ResMut<RoundMapConstructor>,
+    mut clipboard: ResMut<MapClipboard>,
+    mut map_manager: MapManager,
+) {
     for player in &player_inputs.players {
         if let Some(editor_input) = &player.editor_input {
             match editor_input {
@@ -367,7 +1267,372 @@ fn handle_editor_input(player_inputs: Res<MatchInputs>, mut map_manager: MapMana
                     );
                     map_constructor.construct_map(&mut map_manager);
                 }
+                EditorInput::ConstructMap { name, every_round } => {
+                    let name = ustr(name);
+                    if map_constructors.construct(name, rng.u64(..), &mut map_manager) {
+                        round_map_constructor.constructor = every_round.then_some(name);
+                    } else {
+                        warn!("There is no map constructor named `{name}`");
+                    }
+                }
+                EditorInput::FillRect {
+                    layer,
+                    start,
+                    size,
+                    tilemap_tile_idx,
+                    collision,
+                } => map_manager.fill_rect(
+                    *layer as u32,
+                    *start,
+                    *size,
+                    tilemap_tile_idx,
+                    *collision,
+                ),
+                EditorInput::FloodFill {
+                    layer,
+                    pos,
+                    tilemap_tile_idx,
+                    collision,
+                    mode,
+                } => {
+                    map_manager.flood_fill(*layer as u32, *pos, tilemap_tile_idx, *collision, *mode)
+                }
+                EditorInput::CopyRegion { start, size } => {
+                    *clipboard = map_manager.copy_region(*start, *size);
+                }
+                EditorInput::PasteRegion { pos } => map_manager.paste_region(&clipboard, *pos),
+                EditorInput::ResizeMap { size, offset } => map_manager.resize(*size, *offset),
+                EditorInput::ShiftMap { offset } => map_manager.shift(*offset),
+                EditorInput::Undo => map_manager.undo(),
+                EditorInput::Redo => map_manager.redo(),
             }
+            // Each editor input is undone as a whole.
+            map_manager.commit_edits();
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::headless::start_test_match;
+
+    #[test]
+    fn undo_and_redo_edits() {
+        let game = start_test_match(1);
+        let world = &game.sessions.get(SessionNames::GAME).unwrap().world;
+
+        let snapshot = |world: &World| {
+            let map_meta = export_map_meta(world);
+            let tiles = map_meta
+                .layers
+                .iter()
+                .map(|x| x.tiles.iter().map(|x| (x.pos, x.idx)).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            (map_meta.name, map_meta.layers.len(), tiles)
+        };
+        let original = snapshot(world);
+
+        world.run_system(
+            |mut map_manager: MapManager| {
+                map_manager.set_tile(0, UVec2::ZERO, &Some(3), TileCollisionKind::Solid);
+                map_manager.commit_edits();
+                map_manager.rename_map("Edited");
+                map_manager.delete_layer(0);
+                map_manager.commit_edits();
+            },
+            (),
+        );
+        let edited = snapshot(world);
+        assert_ne!(edited, original);
+
+        world.run_system(
+            |mut map_manager: MapManager| {
+                map_manager.undo();
+                map_manager.undo();
+            },
+            (),
+        );
+        assert_eq!(snapshot(world), original);
+
+        world.run_system(
+            |mut map_manager: MapManager| {
+                map_manager.redo();
+                map_manager.redo();
+            },
+            (),
+        );
+        assert_eq!(snapshot(world), edited);
+    }
+
+    #[test]
+    fn undo_restores_elements_where_they_were_placed() {
+        let game = start_test_match(1);
+        let world = &game.sessions.get(SessionNames::GAME).unwrap().world;
+        let positions = |world: &World| {
+            export_map_meta(world)
+                .layers
+                .iter()
+                .map(|x| {
+                    let mut positions = x.elements.iter().map(|x| x.pos).collect::<Vec<_>>();
+                    positions.sort_by(|a, b| a.to_array().partial_cmp(&b.to_array()).unwrap());
+                    positions
+                })
+                .collect::<Vec<_>>()
+        };
+        let original = positions(world);
+
+        // Move every element away from where it was placed, like it would during the match.
+        world.run_system(
+            |entities: Res<Entities>,
+             element_handles: Comp<ElementHandle>,
+             mut transforms: CompMut<Transform>| {
+                for (_, (_, transform)) in entities.iter_with((&element_handles, &mut transforms)) {
+                    transform.translation += Vec3::new(100.0, 100.0, 0.0);
+                }
+            },
+            (),
+        );
+
+        world.run_system(
+            |mut map_manager: MapManager| {
+                for layer in (0..map_manager.get_layers_total() as u32).rev() {
+                    map_manager.delete_layer(layer);
+                }
+                map_manager.commit_edits();
+                map_manager.undo();
+            },
+            (),
+        );
+        assert_eq!(positions(world), original);
+    }
+
+    #[test]
+    fn fill_and_copy_regions() {
+        let game = start_test_match(1);
+        let world = &game.sessions.get(SessionNames::GAME).unwrap().world;
+
+        world.run_system(
+            |mut map_manager: MapManager| {
+                let original = map_manager.get_tile(0, uvec2(1, 1));
+
+                map_manager.fill_rect(
+                    0,
+                    UVec2::ZERO,
+                    uvec2(2, 2),
+                    &Some(5),
+                    TileCollisionKind::Solid,
+                );
+                map_manager.commit_edits();
+                for pos in [uvec2(0, 0), uvec2(1, 0), uvec2(0, 1), uvec2(1, 1)] {
+                    assert_eq!(
+                        map_manager.get_tile(0, pos),
+                        Some((5, TileCollisionKind::Solid))
+                    );
+                }
+
+                let clipboard = map_manager.copy_region(UVec2::ZERO, uvec2(2, 2));
+                assert_eq!(clipboard.size, uvec2(2, 2));
+                map_manager.paste_region(&clipboard, uvec2(3, 0));
+                map_manager.commit_edits();
+                assert_eq!(
+                    map_manager.get_tile(0, uvec2(4, 1)),
+                    Some((5, TileCollisionKind::Solid))
+                );
+
+                map_manager.flood_fill(
+                    0,
+                    UVec2::ZERO,
+                    &Some(6),
+                    TileCollisionKind::JumpThrough,
+                    FloodFillMode::TileIndex,
+                );
+                map_manager.commit_edits();
+                assert_eq!(
+                    map_manager.get_tile(0, uvec2(1, 1)),
+                    Some((6, TileCollisionKind::JumpThrough))
+                );
+
+                map_manager.undo();
+                map_manager.undo();
+                map_manager.undo();
+                assert_eq!(map_manager.get_tile(0, uvec2(1, 1)), original);
+
+                // Regions that go past the edge of the map are cut off at the edge.
+                let size = map_manager.get_size();
+                let clipboard = map_manager.copy_region(uvec2(1, 1), UVec2::MAX);
+                assert_eq!(clipboard.size, size - 1);
+                map_manager.paste_region(&clipboard, UVec2::MAX);
+                map_manager.fill_rect(0, uvec2(1, 1), UVec2::MAX, &None, TileCollisionKind::Empty);
+                assert_eq!(map_manager.get_tile(0, size - 1), None);
+            },
+            (),
+        );
+    }
+
+    #[test]
+    fn resize_and_shift_move_elements_with_tiles() {
+        let game = start_test_match(1);
+        let world = &game.sessions.get(SessionNames::GAME).unwrap().world;
+
+        // The tiles and the tile of every element on each layer, moved by the given offset.
+        let contents = |world: &World, offset: IVec2| {
+            let map_meta = export_map_meta(world);
+            map_meta
+                .layers
+                .iter()
+                .map(|layer| {
+                    let mut tiles = layer
+                        .tiles
+                        .iter()
+                        .map(|x| ((x.pos.as_ivec2() + offset).to_array(), x.idx))
+                        .collect::<Vec<_>>();
+                    tiles.sort();
+                    let mut elements = layer
+                        .elements
+                        .iter()
+                        .map(|x| {
+                            let tile = (x.pos / map_meta.tile_size).floor().as_ivec2();
+                            (tile + offset).to_array()
+                        })
+                        .collect::<Vec<_>>();
+                    elements.sort();
+                    (tiles, elements)
+                })
+                .collect::<Vec<_>>()
+        };
+        let original = contents(world, IVec2::ZERO);
+        let moved = contents(world, ivec2(1, 1));
+
+        // Move every element away from where it was placed, like it would during the match.
+        world.run_system(
+            |entities: Res<Entities>,
+             element_handles: Comp<ElementHandle>,
+             mut transforms: CompMut<Transform>| {
+                for (_, (_, transform)) in entities.iter_with((&element_handles, &mut transforms)) {
+                    transform.translation += Vec3::new(100.0, 100.0, 0.0);
+                }
+            },
+            (),
+        );
+
+        // Grow the map and move its contents by two tiles to the right and one up, and then shift
+        // them back to the left by one tile.
+        world.run_system(
+            |mut map_manager: MapManager| {
+                let size = map_manager.get_size();
+                map_manager.resize(size + uvec2(2, 1), ivec2(2, 1));
+                map_manager.commit_edits();
+                map_manager.shift(ivec2(-1, 0));
+                map_manager.commit_edits();
+            },
+            (),
+        );
+        assert_eq!(contents(world, IVec2::ZERO), moved);
+
+        world.run_system(
+            |mut map_manager: MapManager| {
+                map_manager.undo();
+                map_manager.undo();
+            },
+            (),
+        );
+        assert_eq!(contents(world, IVec2::ZERO), original);
+    }
+
+    #[test]
+    fn nav_graph_follows_edits() {
+        let mut game = start_test_match(1);
+        let world = &game.sessions.get(SessionNames::GAME).unwrap().world;
+
+        world.run_system(
+            |mut map_manager: MapManager| {
+                let size = map_manager.get_size();
+                map_manager.fill_rect(
+                    0,
+                    uvec2(0, size.y / 2),
+                    uvec2(size.x / 2, 2),
+                    &Some(3),
+                    TileCollisionKind::Solid,
+                );
+                map_manager.set_tile(
+                    0,
+                    uvec2(size.x - 2, size.y / 3),
+                    &Some(3),
+                    TileCollisionKind::JumpThrough,
+                );
+                map_manager.commit_edits();
+            },
+            (),
+        );
+        game.step(Instant::now());
+        let world = &game.sessions.get(SessionNames::GAME).unwrap().world;
+
+        let edges = |graph: &NavGraphInner| {
+            let mut edges = graph
+                .all_edges()
+                .map(|(from, to, edge)| (from, to, edge.inputs.len()))
+                .collect::<Vec<_>>();
+            edges.sort();
+            edges
+        };
+        let nav_graph = world.resource::<NavGraph>().0.clone();
+        let settings = world.resource::<NavGraphSource>().settings;
+        let rebuilt = create_world_nav_graph(world, &settings);
+        assert_eq!(nav_graph.node_count(), rebuilt.node_count());
+        assert_eq!(edges(&nav_graph), edges(&rebuilt));
+    }
+
+    #[test]
+    fn nav_graph_follows_solid_elements() {
+        let mut game = start_test_match(1);
+        let world = &game.sessions.get(SessionNames::GAME).unwrap().world;
+
+        // None of the elements in the asset pack are solid, so make one that covers a single tile,
+        // starting outside of the map.
+        let (entity, node) = world.run_system(
+            |mut entities: ResMutInit<Entities>,
+             mut solids: CompMut<Solid>,
+             mut colliders: CompMut<Collider>,
+             mut transforms: CompMut<Transform>,
+             nav_graph: Res<NavGraph>,
+             map: Res<SpawnedMapMeta>| {
+                let node = nav_graph.nodes().max().unwrap();
+                let entity = entities.create();
+                solids.insert(entity, Solid);
+                colliders.insert(
+                    entity,
+                    Collider {
+                        shape: ColliderShape::Rectangle {
+                            size: map.tile_size / 2.0,
+                        },
+                        ..default()
+                    },
+                );
+                transforms.insert(
+                    entity,
+                    Transform::from_translation(-map.tile_size.extend(0.0) * 10.0),
+                );
+                (entity, node)
+            },
+            (),
+        );
+
+        world.run_system(
+            move |mut map_manager: MapManager| {
+                let pos = (node.0.as_vec2() + 0.5) * map_manager.get_tile_size();
+                map_manager.move_element(entity, &pos);
+            },
+            (),
+        );
+        game.step(Instant::now());
+        let world = &game.sessions.get(SessionNames::GAME).unwrap().world;
+
+        let nav_graph = world.resource::<NavGraph>().0.clone();
+        assert!(!nav_graph.contains_node(node));
+        let settings = world.resource::<NavGraphSource>().settings;
+        let rebuilt = create_world_nav_graph(world, &settings);
+        assert_eq!(nav_graph.node_count(), rebuilt.node_count());
+        assert_eq!(nav_graph.edge_count(), rebuilt.edge_count());
+    }
+}
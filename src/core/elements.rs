@@ -68,7 +68,7 @@ impl Default for ElementEditorMeta {
 pub struct MapElementHydrated;
 
 /// Component that contains the [`Entity`] to de-hydrate when the entity with this component is out
-/// of the [`LoadedMap`] bounds.
+/// of the [`SpawnedMapMeta`] bounds.
 ///
 /// This is useful for map elements that spawn items: when the item falls off the map, it should
 /// de-hydrate it's spawner, so that the spawner will re-spawn the item in it's default state.
@@ -79,6 +79,14 @@ pub struct DehydrateOutOfBounds(pub Entity);
 #[derive(Clone, Copy, HasSchema, Default, Deref, DerefMut)]
 pub struct ElementHandle(pub Handle<ElementMeta>);
 
+/// Component containing the handle that a map element was spawned with, before the
+/// [`MapRulesMeta`] were applied to it.
+///
+/// The element may have been replaced with one of the boosted items of the map, but it is still
+/// exported as the original element.
+#[derive(Clone, Copy, HasSchema, Default, Deref, DerefMut)]
+pub struct OriginalElementHandle(pub Handle<ElementMeta>);
+
 #[derive(Clone, HasSchema)]
 #[schema(no_default)]
 pub struct ElementKillCallback {
@@ -262,7 +270,8 @@ macro_rules! install_plugins {
         pub fn session_plugin(session: &mut Session) {
             session
                 .stages
-                .add_system_to_stage(CoreStage::First, handle_out_of_bounds_items);
+                .add_system_to_stage(CoreStage::First, handle_out_of_bounds_items)
+                .add_system_to_stage(CoreStage::First, apply_map_item_rules);
 
             $(
                 session.install_plugin($module::session_plugin);
@@ -306,7 +315,7 @@ fn handle_out_of_bounds_items(
     entities: ResMutInit<Entities>,
     transforms: CompMut<Transform>,
     spawners: Comp<DehydrateOutOfBounds>,
-    map: Res<LoadedMap>,
+    map: Res<SpawnedMapMeta>,
 ) {
     for (item_ent, (transform, spawner)) in entities.iter_with((&transforms, &spawners)) {
         if map.is_out_of_bounds(&transform.translation) {
@@ -317,3 +326,58 @@ fn handle_out_of_bounds_items(
         }
     }
 }
+
+/// Apply the banned and boosted items of the [`MapRulesMeta`] to the map elements, before they are
+/// hydrated.
+fn apply_map_item_rules(
+    entities: Res<Entities>,
+    map: Res<SpawnedMapMeta>,
+    assets: Res<AssetServer>,
+    rng: Res<GlobalRng>,
+    spawned_map_layer_metas: Comp<SpawnedMapLayerMeta>,
+    mut element_handles: CompMut<ElementHandle>,
+    mut original_element_handles: CompMut<OriginalElementHandle>,
+    mut hydrated: CompMut<MapElementHydrated>,
+) {
+    let rules = &map.rules;
+    if rules.banned_items.is_empty() && rules.boosted_items.is_empty() {
+        return;
+    }
+
+    let mut not_hydrated_bitset = hydrated.bitset().clone();
+    not_hydrated_bitset.bit_not();
+    not_hydrated_bitset.bit_and(element_handles.bitset());
+    not_hydrated_bitset.bit_and(spawned_map_layer_metas.bitset());
+
+    for entity in entities.iter_with_bitset(&not_hydrated_bitset) {
+        let handle = element_handles.get(entity).unwrap().0;
+
+        // Elements are only replaced once, and not every time that they are re-hydrated.
+        if !original_element_handles.contains(entity) {
+            original_element_handles.insert(entity, OriginalElementHandle(handle));
+
+            let category = assets.get(handle).category;
+            let boosted_items = rules
+                .boosted_items
+                .iter()
+                .copied()
+                .filter(|&x| assets.get(x).category == category)
+                .collect::<Vec<_>>();
+            if !boosted_items.is_empty()
+                && !boosted_items.contains(&handle)
+                && rng.f32() < rules.boost_chance()
+            {
+                let boosted_item = boosted_items[rng.usize(..boosted_items.len())];
+                element_handles.insert(entity, ElementHandle(boosted_item));
+            }
+        }
+
+        if rules
+            .banned_items
+            .contains(&element_handles.get(entity).unwrap().0)
+        {
+            // Marking the element as hydrated keeps it from spawning anything.
+            hydrated.insert(entity, MapElementHydrated);
+        }
+    }
+}
@@ -42,10 +42,10 @@ pub fn session_plugin(session: &mut Session) {
 }
 
 #[derive(Clone, HasSchema, Default)]
-struct IdleCrate;
+pub struct IdleCrate;
 
 #[derive(Clone, HasSchema, Default)]
-struct ThrownCrate {
+pub struct ThrownCrate {
     owner: Entity,
     damage_delay: Timer,
     break_timeout: Timer,
@@ -278,6 +278,8 @@ fn update_thrown_crates(
             commands.add(PlayerCommand::kill(
                 *player_entity,
                 Some(transform.translation.xy()),
+                Some(thrown_crate.owner),
+                DeathCause::Crate,
             ));
         }
         let kill_nearby_colliding: bool = kill_all_colliding_if_freshly_thrown(
@@ -353,12 +355,16 @@ fn kill_all_colliding_if_freshly_thrown(
                 commands.add(PlayerCommand::kill(
                     *player_entity,
                     Some(transform.translation.xy()),
+                    Some(thrown_crate.owner),
+                    DeathCause::Crate,
                 ));
             }
         }
         commands.add(PlayerCommand::kill(
             thrown_crate.owner,
             Some(transform.translation.xy()),
+            Some(thrown_crate.owner),
+            DeathCause::Crate,
         ));
         true
     } else {
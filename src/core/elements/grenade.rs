@@ -282,6 +282,7 @@ fn update_lit_grenades(
             // Clone types for move into closure
             let damage_region_size = *damage_region_size;
             let damage_region_lifetime = *damage_region_lifetime;
+            let owner = grenade.owner;
             let explosion_lifetime = *explosion_lifetime;
             let explosion_atlas = *explosion_atlas;
             let explosion_fps = *explosion_fps;
@@ -303,6 +304,8 @@ fn update_lit_grenades(
                         ent,
                         DamageRegion {
                             size: damage_region_size,
+                            cause: DeathCause::Explosion,
+                            killer: Some(owner),
                         },
                     );
                     lifetimes.insert(ent, Lifetime::new(damage_region_lifetime));
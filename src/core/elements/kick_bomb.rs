@@ -47,8 +47,12 @@ pub struct IdleKickBomb;
 
 #[derive(Clone, HasSchema, Default, Debug)]
 pub struct LitKickBomb {
-    arm_delay: Timer,
-    fuse_time: Timer,
+    /// Once this timer finishes, the kick bomb explodes when it is kicked from the wrong side.
+    pub arm_delay: Timer,
+    /// The amount of time left until the kick bomb explodes.
+    pub fuse_time: Timer,
+    /// The player that lit the kick bomb.
+    pub owner: Entity,
 }
 
 fn hydrate(
@@ -165,7 +169,7 @@ fn update_idle_kick_bombs(
         let arm_delay = *arm_delay;
         let fuse_time = *fuse_time;
 
-        if items_used.get(entity).is_some() {
+        if let Some(ItemUsed { owner }) = items_used.get(entity).copied() {
             audio_events.play(*fuse_sound, *fuse_sound_volume);
             items_used.remove(entity);
             let animated_sprite = animated_sprites.get_mut(entity).unwrap();
@@ -180,6 +184,7 @@ fn update_idle_kick_bombs(
                         LitKickBomb {
                             arm_delay: Timer::new(arm_delay, TimerMode::Once),
                             fuse_time: Timer::new(fuse_time, TimerMode::Once),
+                            owner,
                         },
                     );
                 },
@@ -203,6 +208,7 @@ fn update_lit_kick_bombs(
     mut hydrated: CompMut<MapElementHydrated>,
     mut attachments: CompMut<PlayerBodyAttachment>,
     mut player_layers: CompMut<PlayerLayers>,
+    mut emote_regions: CompMut<EmoteRegion>,
     player_inventories: PlayerInventories,
     mut transforms: CompMut<Transform>,
     mut commands: Commands,
@@ -236,12 +242,30 @@ fn update_lit_kick_bombs(
         kick_bomb.fuse_time.tick(time.delta());
         kick_bomb.arm_delay.tick(time.delta());
 
+        if !emote_regions.contains(entity) {
+            emote_regions.insert(
+                entity,
+                EmoteRegion {
+                    active: true,
+                    emote: Emote::Alarm,
+                    owner: Some(kick_bomb.owner),
+                    direction_sensitive: true,
+                    size: *damage_region_size * 2.0,
+                    buffer: Some(Timer::new(Duration::from_millis(400), TimerMode::Once)),
+                },
+            );
+        }
+        let emote_region = emote_regions.get_mut(entity).unwrap();
+        // Only alarm players while the bomb isn't being held
+        emote_region.active = true;
+
         let mut should_explode = false;
         // If the item is being held
         if let Some(inventory) = player_inventories
             .iter()
             .find_map(|x| x.filter(|x| x.inventory == entity))
         {
+            emote_region.active = false;
             let player = inventory.player;
             let body = bodies.get_mut(entity).unwrap();
             player_layers.get_mut(player).unwrap().fin_anim = *fin_anim;
@@ -306,6 +330,7 @@ fn update_lit_kick_bombs(
             // Clone types for move into closure
             let damage_region_size = *damage_region_size;
             let damage_region_lifetime = *damage_region_lifetime;
+            let owner = kick_bomb.owner;
             let explosion_lifetime = *explosion_lifetime;
             let explosion_atlas = *explosion_atlas;
             let explosion_fps = *explosion_fps;
@@ -327,6 +352,8 @@ fn update_lit_kick_bombs(
                         ent,
                         DamageRegion {
                             size: damage_region_size,
+                            cause: DeathCause::Explosion,
+                            killer: Some(owner),
                         },
                     );
                     lifetimes.insert(ent, Lifetime::new(damage_region_lifetime));
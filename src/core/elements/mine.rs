@@ -48,8 +48,10 @@ pub struct IdleMine;
 
 #[derive(Clone, HasSchema, Default, Debug)]
 pub struct ThrownMine {
-    // The mine won't explode until this timer finishes.
-    arm_delay: Timer,
+    /// The mine won't explode until this timer finishes.
+    pub arm_delay: Timer,
+    /// The player that threw the mine.
+    pub owner: Entity,
 }
 
 fn hydrate(
@@ -164,6 +166,7 @@ fn update_idle_mines(
                                     Duration::from_secs_f32(arm_delay),
                                     TimerMode::Once,
                                 ),
+                                owner: player,
                             },
                         );
                     },
@@ -181,6 +184,7 @@ fn update_thrown_mines(
     mut trauma_events: ResMutInit<CameraTraumaEvents>,
     mut thrown_mines: CompMut<ThrownMine>,
     mut animated_sprites: CompMut<AnimatedSprite>,
+    mut emote_regions: CompMut<EmoteRegion>,
     mut hydrated: CompMut<MapElementHydrated>,
     player_indexes: Comp<PlayerIdx>,
     mut commands: Commands,
@@ -230,6 +234,17 @@ fn update_thrown_mines(
             sprite.frames = (0..*armed_frames).collect();
             sprite.fps = *armed_fps;
             sprite.repeat = true;
+
+            // Alarm players that get close to the armed mine
+            emote_regions.insert(
+                entity,
+                EmoteRegion {
+                    owner: Some(thrown_mine.owner),
+                    size: *damage_region_size * 2.0,
+                    direction_sensitive: false,
+                    ..default()
+                },
+            );
         }
 
         let colliding_with_players = collision_world
@@ -248,6 +263,8 @@ fn update_thrown_mines(
                 commands.add(PlayerCommand::kill(
                     *player,
                     Some(mine_transform.translation.xy()),
+                    Some(thrown_mine.owner),
+                    DeathCause::Explosion,
                 ));
             }
 
@@ -258,6 +275,7 @@ fn update_thrown_mines(
             // Clone types for move into closure
             let damage_region_size = *damage_region_size;
             let damage_region_lifetime = *damage_region_lifetime;
+            let owner = thrown_mine.owner;
             let explosion_lifetime = *explosion_lifetime;
             let explosion_atlas = *explosion_atlas;
             let explosion_fps = *explosion_fps;
@@ -283,6 +301,8 @@ fn update_thrown_mines(
                         damage_ent,
                         DamageRegion {
                             size: damage_region_size,
+                            cause: DeathCause::Explosion,
+                            killer: Some(owner),
                         },
                     );
                     lifetimes.insert(damage_ent, Lifetime::new(damage_region_lifetime));
@@ -60,8 +60,12 @@ fn update(
     mut player_indexes: CompMut<PlayerIdx>,
     mut transforms: CompMut<Transform>,
     player_inputs: Res<MatchInputs>,
+    players_have_spawned: ResInit<PlayersHaveSpawned>,
     mut spawner_manager: SpawnerManager,
 ) {
+    // In matches with rounds, players only spawn once per round.
+    let has_rounds = has_rounds(&player_inputs);
+
     let alive_players = entities
         .iter_with(&player_indexes)
         .map(|(_ent, pidx)| pidx.0)
@@ -76,7 +80,10 @@ fn update(
         let player = &player_inputs.players[i as usize];
 
         // If the player is active, but not alive
-        if player.active && !alive_players.contains(&i) {
+        if player.active
+            && !alive_players.contains(&i)
+            && !(has_rounds && players_have_spawned.players[i as usize])
+        {
             // Increment the spawner index
             current_spawner.0 += 1;
             current_spawner.0 %= spawn_points.len().max(1);
@@ -93,7 +93,12 @@ fn update(
             })
             .into_iter()
             .for_each(|player| {
-                commands.add(PlayerCommand::kill(player, Some(pos.translation.xy())));
+                commands.add(PlayerCommand::kill(
+                    player,
+                    Some(pos.translation.xy()),
+                    None,
+                    DeathCause::Spikes,
+                ));
             });
     }
 }
@@ -38,7 +38,8 @@ fn hydrate(
     mut sproingers: CompMut<Sproinger>,
     mut atlas_sprites: CompMut<AtlasSprite>,
     mut bodies: CompMut<KinematicBody>,
-    mut nav_graph: ResMutInit<NavGraph>,
+    mut nav_graph_dirty_tiles: ResMutInit<NavGraphDirtyTiles>,
+    mut commands: Commands,
     transforms: Comp<Transform>,
     map: Res<LoadedMap>,
 ) {
@@ -71,25 +72,32 @@ fn hydrate(
     }
 
     // Update the navigation graph with the new sproingers
-    if !new_sproingers.is_empty() {
-        let mut new_graph = nav_graph.as_ref().clone();
-
-        for ent in new_sproingers {
-            let pos = transforms.get(ent).unwrap().translation;
-            let node = NavNode((pos.truncate() / map.tile_size).as_ivec2());
-            let sproing_to = node.above().above().above().above().above().above();
+    let changed_tiles = new_sproingers.into_iter().map(|ent| {
+        let pos = transforms.get(ent).unwrap().translation;
+        sproinger_nav_node(pos.truncate(), map.tile_size)
+    });
+    if nav_graph_dirty_tiles.mark(changed_tiles) {
+        commands.add(update_nav_graph);
+    }
+}
 
-            new_graph.add_edge(
-                node,
-                sproing_to,
-                NavGraphEdge {
-                    inputs: [PlayerControl::default()].into(),
-                    distance: node.distance(&sproing_to),
-                },
-            );
-        }
-        **nav_graph = Arc::new(new_graph);
+/// Add the edge that a sproinger at the given position launches players along to the navigation
+/// graph.
+pub fn add_sproinger_nav_edge(graph: &mut NavGraphInner, pos: Vec2, tile_size: Vec2) {
+    let node = sproinger_nav_node(pos, tile_size);
+    let sproing_to = node.above().above().above().above().above().above();
+    if !graph.contains_node(sproing_to) {
+        return;
     }
+
+    graph.add_edge(
+        node,
+        sproing_to,
+        NavGraphEdge {
+            inputs: [PlayerControl::default()].into(),
+            distance: node.distance(&sproing_to),
+        },
+    );
 }
 
 fn update(
@@ -200,6 +200,8 @@ fn update_wearer(
                     commands.add(PlayerCommand::kill(
                         player,
                         Some(player_transform.translation.xy()),
+                        Some(entity),
+                        DeathCause::Stomp,
                     ))
                 }
             });
@@ -35,6 +35,8 @@ pub fn session_plugin(session: &mut Session) {
 pub struct Sword {
     pub state: SwordState,
     pub dropped_time: f32,
+    /// The player that last held the sword, who is responsible for kills by the thrown sword.
+    pub owner: Option<Entity>,
 }
 
 #[derive(Default, Clone, Copy, Debug)]
@@ -178,7 +180,14 @@ fn update(
                     );
 
                     lifetimes.insert(entity, Lifetime::new(2.0 / 60.0));
-                    damage_regions.insert(entity, DamageRegion { size });
+                    damage_regions.insert(
+                        entity,
+                        DamageRegion {
+                            size,
+                            cause: DeathCause::Sword,
+                            killer: None,
+                        },
+                    );
                     transforms.insert(entity, Transform::from_translation(pos));
                     damage_region_owners.insert(entity, DamageRegionOwner(owner));
                 },
@@ -203,6 +212,7 @@ fn update(
             .find_map(|x| x.filter(|x| x.inventory == entity))
         {
             let player = inventory.player;
+            sword.owner = Some(player);
             let sprite = sprites.get_mut(entity).unwrap();
             let player_translation = transforms.get(player).unwrap().translation;
             let flip = sprite.flip_x;
@@ -319,6 +329,8 @@ fn update(
                         commands.add(PlayerCommand::kill(
                             player,
                             Some(sword_transform.translation.xy()),
+                            sword.owner,
+                            DeathCause::Sword,
                         ))
                     });
             }
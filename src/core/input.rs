@@ -1,11 +1,12 @@
 //! Player and editor input types.
 
-use std::array;
+use std::{array, collections::VecDeque};
 
 use crate::{prelude::*, MAX_PLAYERS};
 
 pub fn install(session: &mut Session) {
     session.world.init_resource::<MatchInputs>();
+    session.world.init_resource::<LocalEditorInputs>();
 }
 
 /// The inputs for each player in this simulation frame.
@@ -22,6 +23,15 @@ impl Default for MatchInputs {
     }
 }
 
+/// Resource containing the editor inputs made by the local player, which haven't been added to
+/// the [`MatchInputs`] yet.
+///
+/// The match runner takes one input from the queue every simulation frame.
+#[derive(Clone, Debug, Default, HasSchema)]
+pub struct LocalEditorInputs {
+    pub inputs: VecDeque<EditorInput>,
+}
+
 /// Player input, not just controls, but also other status that comes from the player, such as the
 /// selected player and whether the player is actually active.
 #[derive(Default, Clone, Debug, HasSchema)]
@@ -38,6 +48,8 @@ pub struct PlayerInput {
     pub editor_input: Option<EditorInput>,
     /// If this is [`None`] it means the player is an AI.
     pub control_source: Option<ControlSource>,
+    /// The difficulty of the player if it is an AI.
+    pub ai_difficulty: AiDifficulty,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -121,4 +133,65 @@ pub enum EditorInput {
         element_layers: Vec<ElementLayer>,
         tile_size: Vec2,
     },
+    /// Run one of the [`MapConstructors`] on the map.
+    ConstructMap {
+        /// The name of the map constructor.
+        name: String,
+        /// Whether or not to run the map constructor again at the start of every round.
+        every_round: bool,
+    },
+    /// Set every tile in a rectangle on a layer.
+    FillRect {
+        /// The layer index of the layer to update.
+        layer: u8,
+        /// The position of the bottom-left tile of the rectangle.
+        start: UVec2,
+        /// The size of the rectangle in tiles.
+        size: UVec2,
+        /// The index in the tilemap to set the tiles to, or [`None`] to delete the tiles.
+        tilemap_tile_idx: Option<u32>,
+        /// The tile collision kind
+        collision: TileCollisionKind,
+    },
+    /// Set a tile and all of the matching tiles connected to it.
+    FloodFill {
+        /// The layer index of the layer to update.
+        layer: u8,
+        /// The position of the tile to start filling from.
+        pos: UVec2,
+        /// The index in the tilemap to set the tiles to, or [`None`] to delete the tiles.
+        tilemap_tile_idx: Option<u32>,
+        /// The tile collision kind
+        collision: TileCollisionKind,
+        /// How the matching tiles are found.
+        mode: FloodFillMode,
+    },
+    /// Copy a rectangle of every layer to the [`MapClipboard`].
+    CopyRegion {
+        /// The position of the bottom-left tile of the rectangle.
+        start: UVec2,
+        /// The size of the rectangle in tiles.
+        size: UVec2,
+    },
+    /// Paste the contents of the [`MapClipboard`].
+    PasteRegion {
+        /// The position to paste the bottom-left tile of the region at.
+        pos: UVec2,
+    },
+    /// Resize the map, growing or cropping it on any edge.
+    ResizeMap {
+        /// The new size of the map in tiles.
+        size: UVec2,
+        /// The number of tiles to move the contents of the map by.
+        offset: IVec2,
+    },
+    /// Move the contents of the map without resizing it.
+    ShiftMap {
+        /// The number of tiles to move the contents of the map by.
+        offset: IVec2,
+    },
+    /// Revert the last edit made to the map.
+    Undo,
+    /// Re-apply the last edit that was reverted with [`EditorInput::Undo`].
+    Redo,
 }
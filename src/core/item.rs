@@ -81,6 +81,23 @@ pub struct ItemGrabbed {
     pub player: Entity,
 }
 
+/// An item pickup, as recorded in the [`ItemPickupEvents`].
+#[derive(Clone, Copy, Debug)]
+pub struct ItemPickupEvent {
+    /// The time since the start of the round that the item was picked up at.
+    pub time: Duration,
+    /// The index of the player that picked up the item.
+    pub player: u32,
+    /// The element of the item, if it is one.
+    pub element: Option<Handle<ElementMeta>>,
+}
+
+/// Resource containing every item pickup of the current round, in the order that they happened.
+#[derive(HasSchema, Clone, Debug, Default)]
+pub struct ItemPickupEvents {
+    pub events: Vec<ItemPickupEvent>,
+}
+
 /// Marker component added to items when they are used.
 #[derive(Clone, Copy, HasSchema, Default)]
 pub struct ItemUsed {
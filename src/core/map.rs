@@ -2,17 +2,18 @@
 
 use std::{
     cmp::{max, min},
-    collections::VecDeque,
+    collections::{BTreeMap, VecDeque},
 };
 
-use super::physics::collisions::{CollisionWorld, TileCollisionKind};
+use super::physics::collisions::{Collider, CollisionWorld, Solid, TileCollisionKind};
 use crate::prelude::*;
 
 pub fn install(session: &mut Session) {
     session
         .stages
         .add_system_to_stage(CoreStage::First, spawn_map)
-        .add_system_to_stage(CoreStage::First, handle_out_of_bounds_players);
+        .add_system_to_stage(CoreStage::First, handle_out_of_bounds_players)
+        .add_system_to_stage(CoreStage::First, wrap_around_map);
 }
 
 /// Resource containing the map metadata for this game session.
@@ -45,6 +46,15 @@ pub struct SpawnedMapMeta {
     pub grid_size: UVec2,
     pub tile_size: Vec2,
     pub layer_names: Arc<[Ustr]>,
+    pub rules: Arc<MapRulesMeta>,
+}
+
+impl SpawnedMapMeta {
+    /// Checks if the given position is out of the bounds of the map, which may have been resized
+    /// since it was spawned.
+    pub fn is_out_of_bounds(&self, pos: &Vec3) -> bool {
+        is_out_of_map_bounds(self.grid_size, self.tile_size, &self.rules, pos)
+    }
 }
 
 impl Default for SpawnedMapMeta {
@@ -56,6 +66,7 @@ impl Default for SpawnedMapMeta {
             grid_size: default(),
             tile_size: default(),
             layer_names: Arc::new([]),
+            rules: default(),
         }
     }
 }
@@ -70,6 +81,13 @@ pub struct SpawnedMapLayerMeta {
     pub layer_idx: u32,
 }
 
+/// Component containing the position that a map element was placed at.
+///
+/// Elements may move during the match, so this, rather than the element's [`Transform`], is the
+/// position that is used when exporting the world to `MapMeta`.
+#[derive(HasSchema, Clone, Copy, Default, Deref, DerefMut)]
+pub struct ElementSpawnPos(pub Vec2);
+
 /// The map navigation graph resource.
 #[derive(Clone, Debug, Deref, DerefMut, HasSchema, Default)]
 pub struct NavGraph(pub Arc<NavGraphInner>);
@@ -143,11 +161,43 @@ pub struct NavGraphEdge {
     pub distance: f32,
 }
 
+/// How a player moves, which decides the jumps that [`create_nav_graph()`] finds by simulating
+/// the player.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NavGraphSettings {
+    pub body_size: Vec2,
+    pub stats: PlayerStatsMeta,
+    /// The gravity of the player, with the gravity scale of the map applied.
+    pub gravity: f32,
+    pub terminal_velocity: f32,
+}
+
+impl NavGraphSettings {
+    /// Get the settings for the given player on a map with the given rules.
+    pub fn new(player: &PlayerMeta, physics: &PhysicsMeta, rules: &MapRulesMeta) -> Self {
+        Self {
+            body_size: player.body_size,
+            stats: player.stats,
+            gravity: player.gravity * rules.gravity_scale(),
+            terminal_velocity: rules.physics(physics).terminal_velocity,
+        }
+    }
+
+    /// Get the settings for the first player in the [`CoreMeta`], which the [`NavGraph`] is made
+    /// for.
+    pub fn for_default_player(assets: &AssetServer, rules: &MapRulesMeta) -> Self {
+        let core = &assets.root::<GameMeta>().core;
+        let player = assets.get(core.players[0]);
+        Self::new(&player, &core.physics, rules)
+    }
+}
+
 fn spawn_map(
     mut commands: Commands,
     mut entities: ResMutInit<Entities>,
     mut clear_color: ResMutInit<ClearColor>,
     map: Res<LoadedMap>,
+    assets: Res<AssetServer>,
     mut map_spawned: ResMutInit<MapSpawned>,
     mut tiles: CompMut<Tile>,
     mut tile_layers: CompMut<TileLayer>,
@@ -157,16 +207,26 @@ fn spawn_map(
     mut parallax_bg_sprites: CompMut<ParallaxBackgroundSprite>,
     mut sprites: CompMut<Sprite>,
     mut nav_graph: ResMutInit<NavGraph>,
+    mut nav_graph_source: ResMutInit<NavGraphSource>,
     mut cameras: CompMut<Camera>,
     mut camera_shakes: CompMut<CameraShake>,
     mut camera_states: CompMut<CameraState>,
     mut spawned_map_layer_metas: CompMut<SpawnedMapLayerMeta>,
+    mut element_spawn_positions: CompMut<ElementSpawnPos>,
     mut spawned_map_meta: ResMutInit<SpawnedMapMeta>,
 ) {
     if map_spawned.0 {
         return;
     }
 
+    // Validating the map simulates the jumps across the whole map, which causes a hitch, so it is
+    // left to `jumpy-map-lint` in release builds.
+    if cfg!(debug_assertions) {
+        for problem in validate_map(&map, &assets) {
+            warn!("Problem with map `{}`: {problem}", map.name);
+        }
+    }
+
     // Fill in the spawned map metadata
     *spawned_map_meta = SpawnedMapMeta {
         name: map.name,
@@ -175,6 +235,7 @@ fn spawn_map(
         grid_size: map.grid_size,
         tile_size: map.tile_size,
         layer_names: map.layers.iter().map(|x| x.id).collect(),
+        rules: Arc::new(map.rules.clone()),
     };
 
     // Spawn the camera
@@ -196,7 +257,10 @@ fn spawn_map(
     **clear_color = map.background_color;
 
     // Load the navigation graph
-    nav_graph.0 = create_nav_graph(&map);
+    let nav_graph_settings = NavGraphSettings::for_default_player(&assets, &map.rules);
+    let (graph, source) = NavGraphTiles::from_map_meta(&map).into_nav_graph(nav_graph_settings);
+    nav_graph.0 = Arc::new(graph);
+    *nav_graph_source = source;
 
     // Spawn parallax backgrounds
     for layer in &map.background.layers {
@@ -263,6 +327,7 @@ fn spawn_map(
             let element_ent = entities.create();
 
             spawned_map_layer_metas.insert(element_ent, SpawnedMapLayerMeta { layer_idx });
+            element_spawn_positions.insert(element_ent, ElementSpawnPos(element_meta.pos));
             transforms.insert(
                 element_ent,
                 Transform::from_translation(element_meta.pos.extend(layer_z)),
@@ -282,53 +347,421 @@ fn handle_out_of_bounds_players(
     mut commands: Commands,
     transforms: CompMut<Transform>,
     player_indexes: Comp<PlayerIdx>,
-    map: Res<LoadedMap>,
+    map: Res<SpawnedMapMeta>,
 ) {
     for (player_ent, (_player_idx, transform)) in entities.iter_with((&player_indexes, &transforms))
     {
         if map.is_out_of_bounds(&transform.translation) {
-            commands.add(PlayerCommand::kill(player_ent, None));
+            // Falling out of the map counts as a suicide.
+            commands.add(PlayerCommand::kill(
+                player_ent,
+                None,
+                Some(player_ent),
+                DeathCause::OutOfBounds,
+            ));
         }
     }
 }
 
-/// Helper method to create a navigation graph from the map metadata.
-fn create_nav_graph(meta: &MapMeta) -> Arc<NavGraphInner> {
-    // Load the navigation graph
-    let mut graph = NavGraphInner::default();
+/// Move the physics bodies that leave an edge that the map wraps around to the other side.
+fn wrap_around_map(
+    entities: Res<Entities>,
+    bodies: Comp<KinematicBody>,
+    mut transforms: CompMut<Transform>,
+    map: Res<SpawnedMapMeta>,
+) {
+    let map_size = map.grid_size.as_vec2() * map.tile_size;
+    let wrap_x = map.rules.wrap_x && map_size.x > 0.0;
+    let wrap_y = map.rules.wrap_y && map_size.y > 0.0;
+    if !wrap_x && !wrap_y {
+        return;
+    }
+
+    for (_ent, (_body, transform)) in entities.iter_with((&bodies, &mut transforms)) {
+        let pos = &mut transform.translation;
+        if wrap_x {
+            pos.x = pos.x.rem_euclid(map_size.x);
+        }
+        if wrap_y {
+            pos.y = pos.y.rem_euclid(map_size.y);
+        }
+    }
+}
+/// Helper method to create a navigation graph from the map metadata, with the jumps of a player
+/// that moves according to the given settings.
+pub fn create_nav_graph(meta: &MapMeta, settings: &NavGraphSettings) -> Arc<NavGraphInner> {
+    let (graph, _) = NavGraphTiles::from_map_meta(meta).into_nav_graph(*settings);
+    Arc::new(graph)
+}
+
+/// Create the navigation graph of the map in the world, including the edges of its sproingers and
+/// the elements that act as platforms, with the jumps of a player that moves according to the
+/// given settings.
+pub fn create_world_nav_graph(world: &World, settings: &NavGraphSettings) -> NavGraphInner {
+    let (graph, _) = NavGraphTiles::from_world(world).into_nav_graph(*settings);
+    graph
+}
+
+/// Re-create the [`NavGraph`] from the current tiles and elements of the map.
+///
+/// This is meant to be run as a command once the whole map has changed, like when it is resized.
+/// [`update_nav_graph()`] is quicker after smaller edits.
+pub fn rebuild_nav_graph(world: &World) {
+    let settings = world.run_system(
+        |assets: Res<AssetServer>, map: Res<SpawnedMapMeta>| {
+            NavGraphSettings::for_default_player(&assets, &map.rules)
+        },
+        (),
+    );
+    let (mut graph, mut source) = NavGraphTiles::from_world(world).into_nav_graph(settings);
+    world.run_system(
+        move |mut nav_graph: ResMutInit<NavGraph>,
+              mut nav_graph_source: ResMutInit<NavGraphSource>,
+              mut dirty_tiles: ResMutInit<NavGraphDirtyTiles>| {
+            **nav_graph = Arc::new(std::mem::take(&mut graph));
+            *nav_graph_source = std::mem::take(&mut source);
+            dirty_tiles.clear();
+        },
+        (),
+    );
+}
+
+/// Update the [`NavGraph`] after the tiles in the [`NavGraphDirtyTiles`] have changed, by only
+/// re-reading those tiles from the world and re-creating the edges of the nodes that are affected
+/// by the changes.
+///
+/// This is meant to be run as a command by systems that edit the map, which the [`MapManager`]
+/// queues once per frame for all of its edits.
+pub fn update_nav_graph(world: &World) {
+    let (nodes, tiles) = world.run_system(
+        |mut dirty_tiles: ResMutInit<NavGraphDirtyTiles>,
+         nav_graph_source: ResInit<NavGraphSource>| {
+            (
+                std::mem::take(&mut dirty_tiles.0),
+                nav_graph_source.tiles.clone(),
+            )
+        },
+        (),
+    );
+    if nodes.is_empty() {
+        return;
+    }
+    let mut tiles = (*tiles).clone().update_from_world(world, nodes);
+    world.run_system(
+        move |mut nav_graph: ResMutInit<NavGraph>,
+              mut nav_graph_source: ResMutInit<NavGraphSource>| {
+            nav_graph_source.update(&mut nav_graph.0, std::mem::take(&mut tiles));
+        },
+        (),
+    );
+}
+
+/// Resource containing the tiles that have changed since the [`NavGraph`] was last updated.
+///
+/// Systems that change the map add the tiles that they changed to it, and queue
+/// [`update_nav_graph()`] as a command if it was empty, so that the graph is only updated once for
+/// all of the changes in a frame.
+#[derive(HasSchema, Clone, Default, Deref, DerefMut)]
+pub struct NavGraphDirtyTiles(pub HashSet<NavNode>);
+
+impl NavGraphDirtyTiles {
+    /// Add changed tiles, returning whether [`update_nav_graph()`] has to be queued for them.
+    pub fn mark(&mut self, nodes: impl IntoIterator<Item = NavNode>) -> bool {
+        let was_empty = self.0.is_empty();
+        self.0.extend(nodes);
+        was_empty && !self.0.is_empty()
+    }
+}
+
+/// Get the nodes of the tiles covered by a solid element, which it blocks like a solid tile.
+pub fn solid_nav_nodes(
+    collider: &Collider,
+    transform: &Transform,
+    tile_size: Vec2,
+) -> impl Iterator<Item = NavNode> {
+    let aabb = collider.shape.compute_aabb(*transform);
+    let min = (vec2(aabb.mins.x, aabb.mins.y) / tile_size)
+        .floor()
+        .as_ivec2();
+    let max = ((vec2(aabb.maxs.x, aabb.maxs.y) / tile_size).ceil() - 1.0).as_ivec2();
+    (min.x..=max.x).flat_map(move |x| (min.y..=max.y).map(move |y| NavNode(ivec2(x, y))))
+}
+
+/// Get the node of the tile that a sproinger at the given position is in.
+pub fn sproinger_nav_node(pos: Vec2, tile_size: Vec2) -> NavNode {
+    NavNode((pos / tile_size).floor().as_ivec2())
+}
+
+/// Resource containing what the [`NavGraph`] was made from, so that [`update_nav_graph()`] can
+/// update it when the map changes.
+#[derive(HasSchema, Clone, Default)]
+pub struct NavGraphSource {
+    /// The settings of the player that the graph was made for.
+    pub settings: NavGraphSettings,
+    /// The tiles that the graph was made from.
+    pub tiles: Arc<NavGraphTiles>,
+    /// The tiles that the simulated jumps from each node went through, which are the tiles that
+    /// can change the jumps from the node.
+    jump_bounds: Arc<HashMap<NavNode, NavBounds>>,
+}
+
+impl NavGraphSource {
+    /// Update the graph, which was made from this source, for the new tiles, returning whether
+    /// anything changed.
+    pub fn update(&mut self, graph: &mut Arc<NavGraphInner>, tiles: NavGraphTiles) -> bool {
+        if *self.tiles == tiles {
+            return false;
+        }
+        if tiles.grid_size != self.tiles.grid_size || tiles.tile_size != self.tiles.tile_size {
+            let (new_graph, source) = tiles.into_nav_graph(self.settings);
+            *graph = Arc::new(new_graph);
+            *self = source;
+            return true;
+        }
+
+        // The walking, falling and sproinger edges of a node only depend on the tiles right next
+        // to it, while its jumps depend on the tiles that they went through.
+        let changed = self.tiles.changed_nodes(&tiles);
+        let mut affected = changed
+            .iter()
+            .flat_map(|node| {
+                [-1, 0, 1].into_iter().flat_map(move |x| {
+                    [0, 1]
+                        .into_iter()
+                        .map(move |y| NavNode(node.0 + ivec2(x, y)))
+                })
+            })
+            .chain(
+                self.jump_bounds
+                    .iter()
+                    .filter(|(_, bounds)| changed.iter().any(|x| bounds.contains(*x)))
+                    .map(|(node, _)| *node),
+            )
+            .collect::<Vec<_>>();
+        affected.sort();
+        affected.dedup();
+
+        let graph = Arc::make_mut(graph);
+        let jump_bounds = Arc::make_mut(&mut self.jump_bounds);
+        for &node in &changed {
+            if tiles.is_blocked(node) {
+                graph.remove_node(node);
+            } else {
+                graph.add_node(node);
+            }
+        }
+        for node in affected {
+            if tiles.is_blocked(node) {
+                jump_bounds.remove(&node);
+                continue;
+            }
+            for to in graph.neighbors(node).collect::<Vec<_>>() {
+                graph.remove_edge(node, to);
+            }
+            let bounds = tiles.add_node_edges(graph, node, &self.settings);
+            jump_bounds.insert(node, bounds);
+        }
+
+        self.tiles = Arc::new(tiles);
+        true
+    }
+}
+
+/// The tiles of a map that a navigation graph is made from.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NavGraphTiles {
+    pub grid_size: UVec2,
+    pub tile_size: Vec2,
+    /// The tiles that can't be moved through, including the ones covered by elements that act as
+    /// platforms.
+    pub solids: HashSet<NavNode>,
+    /// The jump-through tiles, which can be stood on, but moved through from below.
+    pub semi_solids: HashSet<NavNode>,
+    /// The tiles with a sproinger in them.
+    pub sproingers: HashSet<NavNode>,
+}
 
-    // Initialize set of traversable tiles, assuming all tiles are traversable
-    for x in 0..meta.grid_size.x as i32 {
-        for y in 0..meta.grid_size.y as i32 {
-            graph.add_node(NavNode(ivec2(x, y)));
+impl NavGraphTiles {
+    /// Get the tiles of the map metadata.
+    pub fn from_map_meta(meta: &MapMeta) -> Self {
+        let mut tiles = Self {
+            grid_size: meta.grid_size,
+            tile_size: meta.tile_size,
+            ..default()
+        };
+        for tile in meta.layers.iter().flat_map(|x| &x.tiles) {
+            tiles.insert(NavNode(tile.pos.as_ivec2()), tile.collision);
         }
+        tiles
+    }
+
+    /// Get the tiles of the map in the world, along with its sproingers and the elements that act
+    /// as platforms.
+    pub fn from_world(world: &World) -> Self {
+        let map = world.resource::<SpawnedMapMeta>();
+        let tiles = Self {
+            grid_size: map.grid_size,
+            tile_size: map.tile_size,
+            ..default()
+        };
+        drop(map);
+        tiles.read_world(world, None)
     }
 
-    // Find all solid tiles and remove them from the traversable tiles list, while also recording
-    // the jump-through tiles.
-    let mut semi_solids = HashSet::default();
-    for layer in &meta.layers {
-        for tile in &layer.tiles {
-            if tile.collision == TileCollisionKind::JumpThrough {
-                semi_solids.insert(NavNode(tile.pos.as_ivec2()));
-            } else if tile.collision != TileCollisionKind::Empty {
-                graph.remove_node(NavNode(tile.pos.as_ivec2()));
+    /// Re-read the given tiles from the map in the world, after they have changed.
+    pub fn update_from_world(mut self, world: &World, nodes: HashSet<NavNode>) -> Self {
+        for node in &nodes {
+            self.solids.remove(node);
+            self.semi_solids.remove(node);
+            self.sproingers.remove(node);
+        }
+        self.read_world(world, Some(nodes))
+    }
+
+    /// Add the tiles of the map in the world, or only the given ones, to the tiles.
+    fn read_world(self, world: &World, nodes: Option<HashSet<NavNode>>) -> Self {
+        let mut tiles = self;
+        world.run_system(
+            move |entities: Res<Entities>,
+                  tile_layers: Comp<TileLayer>,
+                  tile_collisions: Comp<TileCollisionKind>,
+                  solids: Comp<Solid>,
+                  colliders: Comp<Collider>,
+                  sproingers: Comp<Sproinger>,
+                  transforms: Comp<Transform>| {
+                let mut tiles = std::mem::take(&mut tiles);
+                let includes = |node: &NavNode| nodes.as_ref().map_or(true, |x| x.contains(node));
+                let grid_size = tiles.grid_size;
+                let grid_nodes = match &nodes {
+                    Some(nodes) => nodes
+                        .iter()
+                        .copied()
+                        .filter(|x| {
+                            x.cmpge(IVec2::ZERO).all() && x.cmplt(grid_size.as_ivec2()).all()
+                        })
+                        .collect::<Vec<_>>(),
+                    None => (0..grid_size.y)
+                        .flat_map(|y| {
+                            (0..grid_size.x).map(move |x| NavNode(uvec2(x, y).as_ivec2()))
+                        })
+                        .collect(),
+                };
+
+                for (_, tile_layer) in entities.iter_with(&tile_layers) {
+                    for &node in &grid_nodes {
+                        if let Some(collision) = tile_layer
+                            .get(node.0.as_uvec2())
+                            .and_then(|x| tile_collisions.get(x))
+                        {
+                            tiles.insert(node, *collision);
+                        }
+                    }
+                }
+
+                // Solid elements block the tiles they cover just like solid tiles.
+                for (_, (_, collider, transform)) in
+                    entities.iter_with((&solids, &colliders, &transforms))
+                {
+                    tiles.solids.extend(
+                        solid_nav_nodes(collider, transform, tiles.tile_size).filter(includes),
+                    );
+                }
+
+                for (_, (_, transform)) in entities.iter_with((&sproingers, &transforms)) {
+                    let node =
+                        sproinger_nav_node(transform.translation.truncate(), tiles.tile_size);
+                    if includes(&node) {
+                        tiles.sproingers.insert(node);
+                    }
+                }
+
+                tiles
+            },
+            (),
+        )
+    }
+
+    /// Record the collision of a tile.
+    fn insert(&mut self, node: NavNode, collision: TileCollisionKind) {
+        match collision {
+            TileCollisionKind::Empty => (),
+            TileCollisionKind::Solid => {
+                self.solids.insert(node);
+            }
+            TileCollisionKind::JumpThrough => {
+                self.semi_solids.insert(node);
             }
         }
     }
 
-    // Calculate possible movements from every node
-    macro_rules! is_solid {
-        ($node:expr) => {
-            !graph.contains_node($node) || semi_solids.contains(&$node)
+    /// Get the nodes that are different in the other tiles, in order.
+    fn changed_nodes(&self, other: &Self) -> Vec<NavNode> {
+        let mut changed = self
+            .solids
+            .symmetric_difference(&other.solids)
+            .chain(self.semi_solids.symmetric_difference(&other.semi_solids))
+            .chain(self.sproingers.symmetric_difference(&other.sproingers))
+            .copied()
+            .collect::<Vec<_>>();
+        changed.sort();
+        changed.dedup();
+        changed
+    }
+
+    /// Whether the node can't be moved through, which includes everything outside of the map.
+    pub fn is_blocked(&self, node: NavNode) -> bool {
+        node.cmplt(IVec2::ZERO).any()
+            || node.cmpge(self.grid_size.as_ivec2()).any()
+            || self.solids.contains(&node)
+    }
+
+    /// Whether the node can be stood on.
+    pub fn is_solid(&self, node: NavNode) -> bool {
+        self.is_blocked(node) || self.semi_solids.contains(&node)
+    }
+
+    /// Create a navigation graph from the tiles, with the jumps of a player that moves according
+    /// to the given settings, along with the source to update it from.
+    pub fn into_nav_graph(self, settings: NavGraphSettings) -> (NavGraphInner, NavGraphSource) {
+        let mut graph = NavGraphInner::default();
+
+        // Add every tile that can be moved through
+        for x in 0..self.grid_size.x as i32 {
+            for y in 0..self.grid_size.y as i32 {
+                let node = NavNode(ivec2(x, y));
+                if !self.is_blocked(node) {
+                    graph.add_node(node);
+                }
+            }
+        }
+
+        // Calculate possible movements from every node
+        let mut jump_bounds = HashMap::default();
+        for node in graph.nodes().collect::<Vec<_>>() {
+            let bounds = self.add_node_edges(&mut graph, node, &settings);
+            jump_bounds.insert(node, bounds);
+        }
+
+        let source = NavGraphSource {
+            settings,
+            tiles: Arc::new(self),
+            jump_bounds: Arc::new(jump_bounds),
         };
+        (graph, source)
     }
 
-    for node in graph.nodes().collect::<Vec<_>>() {
+    /// Add the edges for the moves that can be made from the node to the graph, returning the
+    /// tiles that the simulated jumps went through.
+    fn add_node_edges(
+        &self,
+        graph: &mut NavGraphInner,
+        node: NavNode,
+        settings: &NavGraphSettings,
+    ) -> NavBounds {
         // walk left or right along the ground
-        let has_ground = is_solid!(node.below());
+        let has_ground = self.is_solid(node.below());
         let maybe_has_ground =
-            has_ground || is_solid!(node.below().left()) || is_solid!(node.below().right());
+            has_ground || self.is_solid(node.below().left()) || self.is_solid(node.below().right());
 
         /////////////////
         // Grounded
@@ -337,7 +770,7 @@ fn create_nav_graph(meta: &MapMeta) -> Arc<NavGraphInner> {
         if maybe_has_ground {
             // Moving Right
             let right = node.right();
-            if graph.contains_node(right) {
+            if !self.is_blocked(right) {
                 graph.add_edge(
                     node,
                     right,
@@ -355,7 +788,7 @@ fn create_nav_graph(meta: &MapMeta) -> Arc<NavGraphInner> {
 
             // Moving Left
             let left = node.left();
-            if graph.contains_node(left) {
+            if !self.is_blocked(left) {
                 graph.add_edge(
                     node,
                     left,
@@ -372,169 +805,14 @@ fn create_nav_graph(meta: &MapMeta) -> Arc<NavGraphInner> {
             }
         }
 
-        if has_ground {
-            /////////////////
-            // JUMPING
-            /////////////////
-            let above1 = node.above();
-            let above2 = above1.above();
-            let above3 = above2.above();
-            let contains_above1 = graph.contains_node(above1);
-            let contains_above2 = graph.contains_node(above2);
-            let contains_above3 = graph.contains_node(above3);
-
-            if contains_above1 {
-                // Jump staight up
-                graph.add_edge(
-                    node,
-                    above1,
-                    NavGraphEdge {
-                        inputs: [PlayerControl {
-                            jump_just_pressed: true,
-                            jump_pressed: true,
-                            ..default()
-                        }]
-                        .into(),
-                        distance: node.distance(&above1),
-                    },
-                );
-            }
-            if contains_above2 && contains_above1 {
-                // Jump staight up
-                graph.add_edge(
-                    node,
-                    above2,
-                    NavGraphEdge {
-                        inputs: [PlayerControl {
-                            jump_just_pressed: true,
-                            jump_pressed: true,
-                            ..default()
-                        }]
-                        .into(),
-                        distance: node.distance(&above2),
-                    },
-                );
-            }
-            if contains_above3 && contains_above2 && contains_above1 {
-                // Jump staight up
-                graph.add_edge(
-                    node,
-                    above2,
-                    NavGraphEdge {
-                        inputs: [PlayerControl {
-                            jump_just_pressed: true,
-                            jump_pressed: true,
-                            ..default()
-                        }]
-                        .into(),
-                        distance: node.distance(&above3),
-                    },
-                );
-            }
-
-            // Jump up and left
-            let above3l2 = above3.left().left();
-            let above2l = above2.left();
-            let contains_above2l = graph.contains_node(above2l);
-            let contains_above3l2 = graph.contains_node(above3l2);
-            if contains_above3l2 && contains_above2 && contains_above3 && contains_above2l {
-                graph.add_edge(
-                    node,
-                    above3l2,
-                    NavGraphEdge {
-                        inputs: std::iter::repeat(PlayerControl {
-                            move_direction: vec2(-1.0, 0.0),
-                            jump_just_pressed: true,
-                            jump_pressed: true,
-                            ..default()
-                        })
-                        .take(20)
-                        .collect(),
-                        distance: node.distance(&above3l2),
-                    },
-                );
-            }
-            let above3l3 = above3.left().left().left();
-            if graph.contains_node(above3l3)
-                && graph.contains_node(above3.left())
-                && contains_above3l2
-                && contains_above2
-                && contains_above3
-                && contains_above2l
-            {
-                graph.add_edge(
-                    node,
-                    above3l3,
-                    NavGraphEdge {
-                        inputs: std::iter::repeat(PlayerControl {
-                            move_direction: vec2(-1.0, 0.0),
-                            jump_just_pressed: true,
-                            jump_pressed: true,
-                            ..default()
-                        })
-                        .take(20)
-                        .collect(),
-                        distance: node.distance(&above3l3),
-                    },
-                );
-            }
-
-            // Jump up and right
-            let above3r2 = above3.right().right();
-            let above2r = above2.right();
-            let contains_above2r = graph.contains_node(above2r);
-            let contains_above3r2 = graph.contains_node(above3r2);
-            if contains_above3r2 && contains_above2 && contains_above3 && contains_above2r {
-                graph.add_edge(
-                    node,
-                    above3r2,
-                    NavGraphEdge {
-                        inputs: std::iter::repeat(PlayerControl {
-                            move_direction: vec2(1.0, 0.0),
-                            jump_just_pressed: true,
-                            jump_pressed: true,
-                            ..default()
-                        })
-                        .take(20)
-                        .collect(),
-                        distance: node.distance(&above3r2),
-                    },
-                );
-            }
-            let above3r3 = above3.right().right().right();
-            if graph.contains_node(above3r3)
-                && graph.contains_node(above3.right())
-                && contains_above3r2
-                && contains_above2
-                && contains_above3
-                && contains_above2r
-            {
-                graph.add_edge(
-                    node,
-                    above3r3,
-                    NavGraphEdge {
-                        inputs: std::iter::repeat(PlayerControl {
-                            move_direction: vec2(1.0, 0.0),
-                            jump_just_pressed: true,
-                            jump_pressed: true,
-                            ..default()
-                        })
-                        .take(20)
-                        .collect(),
-                        distance: node.distance(&above3r3),
-                    },
-                );
-            }
-        }
-
         /////////////////
         // Falling Down
         /////////////////
 
         // Fall straight down
         let below = node.below();
-        if graph.contains_node(below) {
-            if semi_solids.contains(&below) {
+        if !self.is_blocked(below) {
+            if self.semi_solids.contains(&below) {
                 graph.add_edge(
                     node,
                     below,
@@ -569,8 +847,8 @@ fn create_nav_graph(meta: &MapMeta) -> Arc<NavGraphInner> {
 
         // Fall diagonally down right
         let below_right = node.below().right();
-        if graph.contains_node(below_right) {
-            if semi_solids.contains(&below_right) {
+        if !self.is_blocked(below_right) {
+            if self.semi_solids.contains(&below_right) {
                 graph.add_edge(
                     node,
                     below_right,
@@ -617,8 +895,8 @@ fn create_nav_graph(meta: &MapMeta) -> Arc<NavGraphInner> {
         }
         // Fall diagonally down left
         let below_left = node.below().left();
-        if graph.contains_node(below_left) {
-            if semi_solids.contains(&below_left) {
+        if !self.is_blocked(below_left) {
+            if self.semi_solids.contains(&below_left) {
                 graph.add_edge(
                     node,
                     below_left,
@@ -663,62 +941,329 @@ fn create_nav_graph(meta: &MapMeta) -> Arc<NavGraphInner> {
                 );
             }
         }
+        // Jump, unless the nodes are already connected by a simpler move.
+        let mut bounds = NavBounds::EMPTY;
+        if has_ground {
+            for (to, edge) in self.find_jumps(node, settings, &mut bounds) {
+                if !graph.contains_edge(node, to) {
+                    graph.add_edge(node, to, edge);
+                }
+            }
+        }
 
-        // Slow fall right
-        let far_right_below = node.right().right().right().right().below();
-        let path = [
-            node.right(),
-            node.right().right(),
-            node.right().right().right(),
-            node.right().right().right().right(),
-            far_right_below,
-        ];
-        if path.iter().all(|x| graph.contains_node(*x)) {
-            graph.add_edge(
-                node,
-                far_right_below,
-                NavGraphEdge {
-                    inputs: std::iter::repeat(PlayerControl {
-                        move_direction: vec2(1.0, 0.0),
-                        jump_pressed: true,
-                        ..default()
-                    })
-                    .take(20)
-                    .collect(),
-                    // Bias against using this move because it doesn't always work, by adding an
-                    // extra distance.
-                    distance: node.distance(&far_right_below) + 1.0,
-                },
-            );
+        if self.sproingers.contains(&node) {
+            let pos = (node.0.as_vec2() + 0.5) * self.tile_size;
+            add_sproinger_nav_edge(graph, pos, self.tile_size);
         }
-        // Slow fall left
-        let far_left_below = node.left().left().left().left().below();
-        let path = [
-            node.left(),
-            node.left().left(),
-            node.left().left().left(),
-            node.left().left().left().left(),
-            far_left_below,
-        ];
-        if path.iter().all(|x| graph.contains_node(*x)) {
-            graph.add_edge(
-                node,
-                far_left_below,
-                NavGraphEdge {
-                    inputs: std::iter::repeat(PlayerControl {
-                        move_direction: vec2(-1.0, 0.0),
-                        jump_pressed: true,
-                        ..default()
-                    })
-                    .take(20)
-                    .collect(),
-                    // Bias against using this move because it doesn't always work, by adding an
-                    // extra distance.
-                    distance: node.distance(&far_left_below) + 1.0,
-                },
-            );
+
+        bounds
+    }
+
+    /// Get the tiles covered by a player with the given feet position, from the bottom left one to
+    /// the top right one.
+    fn covered_tiles(&self, feet: Vec2, size: Vec2) -> (IVec2, IVec2) {
+        let min = (feet + vec2(0.01 - size.x / 2.0, 0.01)) / self.tile_size;
+        let max = (feet + vec2(size.x / 2.0 - 0.01, size.y - 0.01)) / self.tile_size;
+        (min.floor().as_ivec2(), max.floor().as_ivec2())
+    }
+
+    /// Whether any of the tiles from `min` to `max` can't be moved through.
+    fn is_area_blocked(&self, min: IVec2, max: IVec2) -> bool {
+        (min.x..=max.x).any(|x| (min.y..=max.y).any(|y| self.is_blocked(NavNode(ivec2(x, y)))))
+    }
+
+    /// Find the nodes that a player standing on the given node can jump to, along with the edges
+    /// to get there, adding the tiles that the jumps go through to the bounds.
+    fn find_jumps(
+        &self,
+        node: NavNode,
+        settings: &NavGraphSettings,
+        bounds: &mut NavBounds,
+    ) -> Vec<(NavNode, NavGraphEdge)> {
+        // The quickest jump to every node, and the number of frames it takes.
+        let mut jumps = BTreeMap::<NavNode, (usize, NavJump)>::new();
+
+        let straight_up = NavJump {
+            direction: 0.0,
+            hold_frames: 0,
+            slow_fall: false,
+        };
+        let sideways = [-1.0, 1.0].into_iter().flat_map(|direction| {
+            NAV_JUMP_HOLD_FRAMES
+                .into_iter()
+                .filter(|x| *x > 0)
+                .map(move |hold_frames| NavJump {
+                    direction,
+                    hold_frames,
+                    slow_fall: false,
+                })
+        });
+        let candidates = std::iter::once(straight_up)
+            .chain(sideways)
+            .flat_map(|jump| {
+                [
+                    jump,
+                    NavJump {
+                        slow_fall: true,
+                        ..jump
+                    },
+                ]
+            });
+
+        for jump in candidates {
+            let landings = NAV_JUMP_START_OFFSETS
+                .iter()
+                .map(|offset| {
+                    let feet = vec2(
+                        (node.x as f32 + 0.5 + offset) * self.tile_size.x,
+                        node.y as f32 * self.tile_size.y,
+                    );
+                    self.simulate_jump(feet, jump, settings, bounds)
+                })
+                .collect::<Vec<_>>();
+            let Some((landing, frames)) = landings[0] else {
+                continue;
+            };
+            if landing == node
+                || !landings
+                    .iter()
+                    .all(|x| x.is_some_and(|(x, _)| x == landing))
+                || jumps.get(&landing).is_some_and(|(x, _)| *x <= frames)
+            {
+                continue;
+            }
+            jumps.insert(landing, (frames, jump));
         }
+
+        jumps
+            .into_iter()
+            .map(|(landing, (frames, jump))| {
+                let edge = NavGraphEdge {
+                    inputs: (0..frames).map(|x| jump.control(x)).collect(),
+                    distance: node.distance(&landing),
+                };
+                (landing, edge)
+            })
+            .collect()
     }
 
-    Arc::new(graph)
+    /// Simulate a jump of a player standing with its feet at the given position, following the
+    /// movement of the player states and the physics of the kinematic bodies, adding the tiles
+    /// that it goes through to the bounds.
+    ///
+    /// Returns the node that the player lands on and the number of frames that it takes, or `None`
+    /// if the player bumps into something on the way or lands somewhere without solid ground right
+    /// below it.
+    fn simulate_jump(
+        &self,
+        mut feet: Vec2,
+        jump: NavJump,
+        settings: &NavGraphSettings,
+        bounds: &mut NavBounds,
+    ) -> Option<(NavNode, usize)> {
+        let NavGraphSettings {
+            body_size,
+            stats,
+            gravity,
+            terminal_velocity,
+        } = *settings;
+
+        // Landing checks the tiles right below the feet, too.
+        let mut track_tiles = |feet: Vec2| {
+            let (min, max) = self.covered_tiles(feet, body_size);
+            bounds.extend(min - ivec2(0, 1), max);
+            (min, max)
+        };
+
+        let (min, max) = track_tiles(feet);
+        if self.is_area_blocked(min, max) {
+            return None;
+        }
+
+        let mut velocity = Vec2::ZERO;
+        for frame in 0..NAV_JUMP_MAX_FRAMES {
+            let control = jump.control(frame);
+            let direction = control.move_direction.x;
+
+            if frame == 0 {
+                // Jump off the ground, like in the idle and walk states.
+                velocity.y = stats.jump_speed;
+                velocity.x =
+                    (stats.accel_walk_speed * direction).clamp(-stats.walk_speed, stats.walk_speed);
+            } else {
+                // Steer in the air, like in the midair state.
+                if control.jump_pressed {
+                    velocity.y = velocity.y.max(-stats.slow_fall_speed);
+                }
+                velocity.x = (velocity.x + stats.accel_air_speed * direction)
+                    .clamp(-stats.air_speed, stats.air_speed);
+                if direction == 0.0 {
+                    velocity.x = velocity.x.signum() * (velocity.x.abs() - stats.slowdown).max(0.0);
+                }
+            }
+
+            let last_feet = feet;
+            feet += velocity;
+            let (min, max) = track_tiles(feet);
+
+            // Land on the first tile top that the feet fell through.
+            if velocity.y <= 0.0 {
+                let last_row = (last_feet.y / self.tile_size.y).floor() as i32;
+                let row = (feet.y / self.tile_size.y).floor() as i32;
+                for top_row in (row + 1..=last_row).rev() {
+                    if !(min.x..=max.x).any(|x| self.is_solid(NavNode(ivec2(x, top_row - 1)))) {
+                        continue;
+                    }
+
+                    let landed = vec2(feet.x, top_row as f32 * self.tile_size.y);
+                    let landing = NavNode((landed / self.tile_size).floor().as_ivec2());
+                    let (min, max) = self.covered_tiles(landed, body_size);
+                    let is_on_target = !self.is_blocked(landing)
+                        && self.is_solid(landing.below())
+                        && !self.is_area_blocked(min, max);
+                    return is_on_target.then_some((landing, frame + 1));
+                }
+            }
+
+            if self.is_area_blocked(min, max) {
+                return None;
+            }
+
+            velocity.y = (velocity.y - gravity).max(-terminal_velocity);
+        }
+
+        None
+    }
+}
+
+/// A rectangle of tiles, from `min` to `max`.
+#[derive(Clone, Copy, Debug)]
+struct NavBounds {
+    min: IVec2,
+    max: IVec2,
+}
+
+impl NavBounds {
+    /// Bounds that don't contain any tiles.
+    const EMPTY: Self = Self {
+        min: IVec2::MAX,
+        max: IVec2::MIN,
+    };
+
+    /// Grow the bounds to contain the tiles from `min` to `max`.
+    fn extend(&mut self, min: IVec2, max: IVec2) {
+        self.min = self.min.min(min);
+        self.max = self.max.max(max);
+    }
+
+    /// Whether the bounds contain the node.
+    fn contains(&self, node: NavNode) -> bool {
+        node.cmpge(self.min).all() && node.cmple(self.max).all()
+    }
+}
+
+/// The number of frames that a simulated jump may take before it is given up on.
+const NAV_JUMP_MAX_FRAMES: usize = 240;
+
+/// The numbers of frames that the horizontal input is held for in the simulated jumps, which
+/// decides how far they go.
+const NAV_JUMP_HOLD_FRAMES: [usize; 10] = [0, 3, 6, 9, 12, 16, 20, 28, 36, usize::MAX];
+
+/// The horizontal offsets, as a fraction of the tile width, from the center of the tile that a
+/// jump is simulated from. A jump is only added to the navigation graph if it lands on the same
+/// tile from all of them, so that it still works if the player isn't standing in the middle of
+/// the tile.
+const NAV_JUMP_START_OFFSETS: [f32; 3] = [0.0, -0.125, 0.125];
+
+/// A jump that is simulated to find the edges of the navigation graph.
+#[derive(Clone, Copy, Debug)]
+struct NavJump {
+    /// The horizontal input direction.
+    direction: f32,
+    /// The number of frames that the horizontal input is held for.
+    hold_frames: usize,
+    /// Whether jump is held to fall slowly.
+    slow_fall: bool,
+}
+
+impl NavJump {
+    /// Get the input for the given frame of the jump.
+    fn control(&self, frame: usize) -> PlayerControl {
+        let direction = if frame < self.hold_frames {
+            self.direction
+        } else {
+            0.0
+        };
+        PlayerControl {
+            moving: direction != 0.0,
+            move_direction: vec2(direction, 0.0),
+            jump_just_pressed: frame == 0,
+            jump_pressed: frame == 0 || self.slow_fall,
+            ..default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::*;
+    use crate::headless::load_game;
+
+    /// A map with a floor, a jump-through platform 3 tiles above it and another one 4 tiles above
+    /// that.
+    fn platforms_map(gravity_scale: f32) -> MapMeta {
+        let tile = |x: u32, y: u32, collision| MapTileMeta {
+            pos: uvec2(x, y),
+            idx: 0,
+            collision,
+        };
+        let tiles = (0..12)
+            .map(|x| tile(x, 0, TileCollisionKind::Solid))
+            .chain((5..9).map(|x| tile(x, 3, TileCollisionKind::JumpThrough)))
+            .chain((5..9).map(|x| tile(x, 7, TileCollisionKind::JumpThrough)))
+            .collect();
+        MapMeta {
+            grid_size: uvec2(12, 16),
+            tile_size: vec2(32.0, 32.0),
+            layers: [MapLayerMeta { tiles, ..default() }].into_iter().collect(),
+            rules: MapRulesMeta {
+                gravity_scale: Set(gravity_scale),
+                ..default()
+            },
+            ..default()
+        }
+    }
+
+    #[test]
+    fn jumps_follow_map_gravity() {
+        let game = load_game(Path::new("assets"), Path::new("packs")).unwrap();
+        let assets = game.shared_resource::<AssetServer>().unwrap();
+        let floor = NavNode(ivec2(6, 1));
+        let platform = NavNode(ivec2(6, 4));
+        let high_platform = NavNode(ivec2(6, 8));
+
+        let map = platforms_map(1.0);
+        let settings = NavGraphSettings::for_default_player(&assets, &map.rules);
+        let graph = create_nav_graph(&map, &settings);
+        assert!(graph.contains_edge(floor, platform));
+        assert!(!petgraph::algo::has_path_connecting(
+            &*graph,
+            floor,
+            high_platform,
+            None
+        ));
+
+        // The player jumps twice as high with half the gravity.
+        let map = platforms_map(0.5);
+        let settings = NavGraphSettings::for_default_player(&assets, &map.rules);
+        let graph = create_nav_graph(&map, &settings);
+        assert!(petgraph::algo::has_path_connecting(
+            &*graph,
+            floor,
+            high_platform,
+            None
+        ));
+    }
 }
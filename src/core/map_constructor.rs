@@ -2,13 +2,168 @@
 //!
 //! Map constructors are algorithms that can be used to create or edit game maps, usually through
 //! the jumpy editor.
+//!
+//! The available map constructors are registered by name in the [`MapConstructors`] resource, and
+//! can be run with [`EditorInput::ConstructMap`]. Setting the [`RoundMapConstructor`] runs a map
+//! constructor on the map at the start of every round.
 
 use super::editor::MapManager;
+use crate::prelude::*;
 
+pub mod arena;
 pub mod shiftnanigans;
 
+/// Install this module.
+pub fn install(session: &mut Session) {
+    session.world.init_resource::<MapConstructors>();
+    session.world.init_resource::<RoundMapConstructor>();
+    session
+        .stages
+        .add_system_to_stage(CoreStage::First, construct_round_map);
+}
+
 /// Trait implemented by map constructors.
 pub trait MapConstructor {
     /// Take the map manager and use it to either modify or construct a new map.
     fn construct_map(&self, map_manager: &mut MapManager);
 }
+
+/// Function that creates a map constructor for the map in the given map manager, with the given
+/// random seed.
+pub type MapConstructorFactory = fn(&MapManager, u64) -> Box<dyn MapConstructor>;
+
+/// Resource containing the map constructors that can be run by name.
+#[derive(HasSchema, Clone)]
+pub struct MapConstructors {
+    constructors: Vec<(Ustr, MapConstructorFactory)>,
+}
+
+impl Default for MapConstructors {
+    fn default() -> Self {
+        let mut constructors = Self {
+            constructors: Vec::new(),
+        };
+        constructors.register(ustr("arena"), |map_manager, seed| {
+            Box::new(arena::ArenaMapConstructor::new(map_manager, seed))
+        });
+        constructors.register(ustr("shiftnanigans"), |map_manager, _| {
+            Box::new(shiftnanigans::ShiftnanigansMapConstructor::new(
+                map_manager.get_size(),
+                map_manager.get_tile_size(),
+                &map_manager.get_tile_layers(),
+                &map_manager.get_element_layers(),
+            ))
+        });
+        constructors
+    }
+}
+
+impl MapConstructors {
+    /// Register a map constructor, replacing any map constructor with the same name.
+    pub fn register(&mut self, name: Ustr, factory: MapConstructorFactory) {
+        if let Some(entry) = self.constructors.iter_mut().find(|x| x.0 == name) {
+            entry.1 = factory;
+        } else {
+            self.constructors.push((name, factory));
+        }
+    }
+
+    /// Get the names of the registered map constructors.
+    pub fn names(&self) -> impl Iterator<Item = Ustr> + '_ {
+        self.constructors.iter().map(|x| x.0)
+    }
+
+    /// Run the map constructor with the given name, returning `false` if there is no such map
+    /// constructor.
+    ///
+    /// The [`NavGraph`] is updated around the changes once the map has been constructed.
+    pub fn construct(&self, name: Ustr, seed: u64, map_manager: &mut MapManager) -> bool {
+        let Some((_, factory)) = self.constructors.iter().find(|x| x.0 == name) else {
+            return false;
+        };
+        factory(map_manager, seed).construct_map(map_manager);
+        true
+    }
+}
+
+/// Resource containing the map constructor that is run on the map at the start of every round.
+///
+/// This is carried over from round to round, so that every round is played on a freshly
+/// constructed map.
+#[derive(HasSchema, Clone, Debug, Default)]
+pub struct RoundMapConstructor {
+    /// The name of the map constructor in the [`MapConstructors`].
+    pub constructor: Option<Ustr>,
+    /// Whether the map constructor has already been run this round.
+    constructed: bool,
+}
+
+impl RoundMapConstructor {
+    pub fn new(constructor: Option<Ustr>) -> Self {
+        Self {
+            constructor,
+            constructed: false,
+        }
+    }
+}
+
+/// Run the [`RoundMapConstructor`] once the map has been spawned, before the players spawn.
+fn construct_round_map(
+    map_spawned: ResInit<MapSpawned>,
+    rng: Res<GlobalRng>,
+    score: ResInit<MatchScore>,
+    map_constructors: Res<MapConstructors>,
+    mut round_map_constructor: ResMut<RoundMapConstructor>,
+    mut map_manager: MapManager,
+) {
+    if !**map_spawned || round_map_constructor.constructed {
+        return;
+    }
+    round_map_constructor.constructed = true;
+    let Some(name) = round_map_constructor.constructor else {
+        return;
+    };
+
+    // The random number generator starts with the same seed every round, so the round is added to
+    // get a different map every round.
+    let seed = rng.u64(..).wrapping_add(score.round as u64);
+    if !map_constructors.construct(name, seed, &mut map_manager) {
+        warn!("There is no map constructor named `{name}`");
+    }
+    map_manager.commit_edits();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::headless::start_test_match;
+
+    #[test]
+    fn arena_spawners_are_connected() {
+        let game = start_test_match(1);
+        let world = &game.sessions.get(SessionNames::GAME).unwrap().world;
+
+        let (spawn_nodes, nav_graph_settings) = world.run_system(
+            |map_constructors: Res<MapConstructors>, mut map_manager: MapManager| {
+                assert!(map_constructors.construct(ustr("arena"), 3, &mut map_manager));
+                let tile_size = map_manager.get_tile_size();
+                let spawn_nodes = map_manager
+                    .get_elements()
+                    .into_iter()
+                    .filter(|(entity, ..)| map_manager.is_player_spawner(*entity))
+                    .map(|(_, pos, _)| NavNode((pos / tile_size).floor().as_ivec2()))
+                    .collect::<Vec<_>>();
+                (spawn_nodes, map_manager.get_nav_graph_settings())
+            },
+            (),
+        );
+        assert!(spawn_nodes.len() > 1);
+
+        let graph = create_nav_graph(&export_map_meta(world), &nav_graph_settings);
+        for &from in &spawn_nodes {
+            for &to in &spawn_nodes {
+                assert!(petgraph::algo::has_path_connecting(&*graph, from, to, None));
+            }
+        }
+    }
+}
@@ -0,0 +1,359 @@
+//! Procedural arena generator, which builds a new map of platforms from scratch.
+
+use super::MapConstructor;
+use crate::prelude::*;
+
+/// The number of rows of solid tiles at the bottom of the arena.
+const FLOOR_HEIGHT: u32 = 2;
+/// The vertical distance in tiles between the rows of platforms, which players can jump.
+const PLATFORM_SPACING: u32 = 3;
+/// The smallest width of the sections that a row of platforms is split into.
+const MIN_SECTION_WIDTH: u32 = 6;
+/// The chance that a section of a row has a platform.
+const PLATFORM_CHANCE: f64 = 0.7;
+/// The chance that a section of a row is split further.
+const SPLIT_CHANCE: f64 = 0.75;
+/// The distance in tiles that player spawners are kept apart from each other, if there is room.
+const MIN_SPAWNER_DISTANCE: f32 = 6.0;
+/// The number of arenas that are generated before giving up and spawning the players on the floor.
+const MAX_ATTEMPTS: usize = 10;
+
+/// Map constructor that builds an arena of platforms, using the tiles and elements of the map.
+///
+/// Each row of platforms is split into sections with binary space partitioning, which may contain
+/// a solid or jump-through platform. The player spawners are moved onto the platforms, making sure
+/// that they can all reach each other, and the other elements are scattered over the platforms.
+pub struct ArenaMapConstructor {
+    rng: Rng,
+    size: UVec2,
+    tile_size: Vec2,
+    /// The layer that the tiles are placed on.
+    layer: u32,
+    /// The tile index used for solid tiles.
+    solid_tile: u32,
+    /// The tile index used for jump-through platforms.
+    platform_tile: u32,
+    /// The player spawner elements.
+    spawners: Vec<Entity>,
+    /// The other elements on the map.
+    elements: Vec<Entity>,
+    /// The settings that the navigation graph is made with, to check the spawn points with.
+    nav_graph_settings: NavGraphSettings,
+}
+
+/// A rectangle of tiles in the arena.
+struct Platform {
+    start: UVec2,
+    size: UVec2,
+    collision: TileCollisionKind,
+}
+
+impl ArenaMapConstructor {
+    pub fn new(map_manager: &MapManager, seed: u64) -> Self {
+        // Use the most common tiles of the layer with the most tiles.
+        let tile_layers = map_manager.get_tile_layers();
+        let tile_layer = tile_layers.iter().max_by_key(|x| x.located_tiles.len());
+        let most_common_tile = |kind: TileCollisionKind| {
+            let mut counts = HashMap::<u32, usize>::default();
+            for (_, idx, collision) in tile_layer.iter().flat_map(|x| &x.located_tiles) {
+                if *collision == kind {
+                    *counts.entry(*idx).or_default() += 1;
+                }
+            }
+            counts
+                .into_iter()
+                .max_by_key(|&(idx, count)| (count, std::cmp::Reverse(idx)))
+                .map(|(idx, _)| idx)
+        };
+        let solid_tile = most_common_tile(TileCollisionKind::Solid).unwrap_or_default();
+        let platform_tile = most_common_tile(TileCollisionKind::JumpThrough).unwrap_or(solid_tile);
+
+        let (spawners, elements): (Vec<_>, Vec<_>) = map_manager
+            .get_elements()
+            .into_iter()
+            .map(|(entity, ..)| entity)
+            .partition(|&entity| map_manager.is_player_spawner(entity));
+
+        Self {
+            rng: Rng::with_seed(seed),
+            size: map_manager.get_size(),
+            tile_size: map_manager.get_tile_size(),
+            layer: tile_layer.map_or(0, |x| x.layer_index),
+            solid_tile,
+            platform_tile,
+            spawners,
+            elements,
+            nav_graph_settings: map_manager.get_nav_graph_settings(),
+        }
+    }
+
+    /// Generate the floor and the rows of platforms.
+    fn generate_platforms(&self) -> Vec<Platform> {
+        let mut platforms = vec![self.floor()];
+        let mut y = FLOOR_HEIGHT - 1 + PLATFORM_SPACING;
+        // Leave room above the top row for the players.
+        while y + PLATFORM_SPACING < self.size.y {
+            let mut sections = Vec::new();
+            self.split_section(0, self.size.x, &mut sections);
+            for (start, width) in sections {
+                if !self.rng.chance(PLATFORM_CHANCE) {
+                    continue;
+                }
+                // Leave a gap of at least one tile to the next section.
+                let platform_width = self.rng.u32(3..=width - 2);
+                let offset = self.rng.u32(1..=width - 1 - platform_width);
+                platforms.push(Platform {
+                    start: uvec2(start + offset, y),
+                    size: uvec2(platform_width, 1),
+                    collision: if self.rng.bool() {
+                        TileCollisionKind::JumpThrough
+                    } else {
+                        TileCollisionKind::Solid
+                    },
+                });
+            }
+            y += PLATFORM_SPACING;
+        }
+        platforms
+    }
+
+    fn floor(&self) -> Platform {
+        Platform {
+            start: UVec2::ZERO,
+            size: uvec2(self.size.x, FLOOR_HEIGHT),
+            collision: TileCollisionKind::Solid,
+        }
+    }
+
+    /// Recursively split a section of a row into smaller sections.
+    fn split_section(&self, start: u32, width: u32, sections: &mut Vec<(u32, u32)>) {
+        if width >= MIN_SECTION_WIDTH * 2 && self.rng.chance(SPLIT_CHANCE) {
+            let split = self.rng.u32(MIN_SECTION_WIDTH..=width - MIN_SECTION_WIDTH);
+            self.split_section(start, split, sections);
+            self.split_section(start + split, width - split, sections);
+        } else {
+            sections.push((start, width));
+        }
+    }
+
+    /// Get the tiles that players can stand in on top of the platforms.
+    fn standing_tiles(platforms: &[Platform]) -> Vec<UVec2> {
+        platforms
+            .iter()
+            .flat_map(|platform| {
+                let y = platform.start.y + platform.size.y;
+                (platform.start.x..platform.start.x + platform.size.x).map(move |x| uvec2(x, y))
+            })
+            .collect()
+    }
+
+    /// Pick the tiles to put the player spawners in, keeping them apart if there is room.
+    fn pick_spawn_points(&self, platforms: &[Platform]) -> Vec<UVec2> {
+        let mut candidates = Self::standing_tiles(platforms);
+        self.rng.shuffle(&mut candidates);
+
+        let mut spawn_points = Vec::<UVec2>::new();
+        for &candidate in &candidates {
+            if spawn_points.len() == self.spawners.len() {
+                break;
+            }
+            if spawn_points
+                .iter()
+                .all(|x| x.as_vec2().distance(candidate.as_vec2()) >= MIN_SPAWNER_DISTANCE)
+            {
+                spawn_points.push(candidate);
+            }
+        }
+        // Fall back to putting spawners closer together.
+        for &candidate in &candidates {
+            if spawn_points.len() == self.spawners.len() {
+                break;
+            }
+            if !spawn_points.contains(&candidate) {
+                spawn_points.push(candidate);
+            }
+        }
+        spawn_points
+    }
+
+    /// Get how high a player can jump, and how far it can move sideways on the way up, in pixels.
+    fn jump_range(&self) -> Vec2 {
+        let NavGraphSettings { stats, gravity, .. } = self.nav_graph_settings;
+        let mut range = Vec2::ZERO;
+        let mut velocity = vec2(
+            stats.accel_walk_speed.min(stats.walk_speed),
+            stats.jump_speed,
+        );
+        // Like the simulated jumps of the navigation graph, but only up to the top of the jump.
+        while velocity.y > 0.0 && gravity > 0.0 {
+            range += velocity;
+            velocity.x = (velocity.x + stats.accel_air_speed).min(stats.air_speed);
+            velocity.y -= gravity;
+        }
+        range
+    }
+
+    /// Whether a player standing on one platform can get onto the other one.
+    fn can_move(&self, from: &Platform, to: &Platform, jump_range: Vec2) -> bool {
+        let top = |x: &Platform| (x.start.y + x.size.y) as i32;
+        let end = |x: &Platform| (x.start.x + x.size.x) as i32;
+        let rise = top(to) - top(from);
+        let gap = (to.start.x as i32 - end(from))
+            .max(from.start.x as i32 - end(to))
+            .max(0);
+        if gap as f32 * self.tile_size.x > jump_range.x
+            || rise as f32 * self.tile_size.y > jump_range.y
+        {
+            return false;
+        }
+
+        // Players can't move through solid platforms, so the lower platform has to stick out from
+        // under a solid platform above it.
+        let (lower, upper) = if rise > 0 { (from, to) } else { (to, from) };
+        gap > 0
+            || rise == 0
+            || upper.collision == TileCollisionKind::JumpThrough
+            || lower.start.x < upper.start.x
+            || end(lower) > end(upper)
+    }
+
+    /// Whether the spawn points can all reach each other.
+    ///
+    /// This checks which platforms players can jump and fall between, instead of making the
+    /// [`NavGraph`] of the arena, which simulates the jumps from every tile and is too slow to do
+    /// for every arena that is generated.
+    fn is_connected(&self, platforms: &[Platform], spawn_points: &[UVec2]) -> bool {
+        let jump_range = self.jump_range();
+
+        // Players can get off of every platform by falling, so every platform that can be reached
+        // from the floor can reach the others, too.
+        let mut reached = vec![false; platforms.len()];
+        reached[0] = true;
+        let mut queue = vec![0];
+        while let Some(from) = queue.pop() {
+            for (to, platform) in platforms.iter().enumerate() {
+                if !reached[to] && self.can_move(&platforms[from], platform, jump_range) {
+                    reached[to] = true;
+                    queue.push(to);
+                }
+            }
+        }
+
+        spawn_points.iter().all(|pos| {
+            platforms.iter().zip(&reached).any(|(platform, reached)| {
+                *reached
+                    && platform.start.y + platform.size.y == pos.y
+                    && (platform.start.x..platform.start.x + platform.size.x).contains(&pos.x)
+            })
+        })
+    }
+
+    /// Whether the spawn points can all reach each other in the navigation graph of the arena.
+    fn is_nav_graph_connected(&self, platforms: &[Platform], spawn_points: &[UVec2]) -> bool {
+        let map_meta = MapMeta {
+            grid_size: self.size,
+            tile_size: self.tile_size,
+            layers: [MapLayerMeta {
+                tiles: self.tiles(platforms).collect(),
+                ..default()
+            }]
+            .into_iter()
+            .collect(),
+            ..default()
+        };
+        let graph = create_nav_graph(&map_meta, &self.nav_graph_settings);
+
+        spawn_points.iter().all(|from| {
+            spawn_points.iter().all(|to| {
+                from == to
+                    || petgraph::algo::has_path_connecting(
+                        &*graph,
+                        NavNode(from.as_ivec2()),
+                        NavNode(to.as_ivec2()),
+                        None,
+                    )
+            })
+        })
+    }
+
+    /// Get the tiles of the platforms.
+    fn tiles<'a>(&'a self, platforms: &'a [Platform]) -> impl Iterator<Item = MapTileMeta> + 'a {
+        platforms.iter().flat_map(move |platform| {
+            let idx = self.tile_idx(platform.collision);
+            (0..platform.size.y).flat_map(move |y| {
+                (0..platform.size.x).map(move |x| MapTileMeta {
+                    pos: platform.start + uvec2(x, y),
+                    idx,
+                    collision: platform.collision,
+                })
+            })
+        })
+    }
+
+    fn tile_idx(&self, collision: TileCollisionKind) -> u32 {
+        if collision == TileCollisionKind::JumpThrough {
+            self.platform_tile
+        } else {
+            self.solid_tile
+        }
+    }
+
+    /// Get the world position of the center of a tile.
+    fn tile_center(&self, pos: UVec2) -> Vec2 {
+        (pos.as_vec2() + 0.5) * self.tile_size
+    }
+}
+
+impl MapConstructor for ArenaMapConstructor {
+    fn construct_map(&self, map_manager: &mut MapManager) {
+        if self.size.x < MIN_SECTION_WIDTH
+            || self.size.y < FLOOR_HEIGHT + PLATFORM_SPACING
+            || map_manager.get_layers_total() == 0
+        {
+            warn!("The map is too small to construct an arena on");
+            return;
+        }
+
+        let (platforms, spawn_points) = (0..MAX_ATTEMPTS)
+            .find_map(|_| {
+                let platforms = self.generate_platforms();
+                let spawn_points = self.pick_spawn_points(&platforms);
+                self.is_connected(&platforms, &spawn_points)
+                    .then_some((platforms, spawn_points))
+            })
+            .unwrap_or_else(|| {
+                // Players can always reach each other by walking along the floor.
+                let spawn_points = (0..self.spawners.len() as u32)
+                    .map(|i| {
+                        let x = (i + 1) * self.size.x / (self.spawners.len() as u32 + 1);
+                        uvec2(x, FLOOR_HEIGHT)
+                    })
+                    .collect();
+                (vec![self.floor()], spawn_points)
+            });
+        if cfg!(debug_assertions) && !self.is_nav_graph_connected(&platforms, &spawn_points) {
+            warn!("The spawners of the arena can't all reach each other in the navigation graph");
+        }
+
+        map_manager.clear_tiles();
+        for platform in &platforms {
+            map_manager.fill_rect(
+                self.layer,
+                platform.start,
+                platform.size,
+                &Some(self.tile_idx(platform.collision)),
+                platform.collision,
+            );
+        }
+
+        for (&entity, &pos) in self.spawners.iter().zip(&spawn_points) {
+            map_manager.move_element(entity, &self.tile_center(pos));
+        }
+        let standing_tiles = Self::standing_tiles(&platforms);
+        for &entity in &self.elements {
+            if let Some(&pos) = self.rng.sample(&standing_tiles) {
+                map_manager.move_element(entity, &self.tile_center(pos));
+            }
+        }
+    }
+}
@@ -0,0 +1,506 @@
+//! Exporting the map from the world of a running match.
+//!
+//! The map may be modified after it has been spawned, through the [`MapManager`], so the map
+//! metadata is re-constructed from the [`SpawnedMapMeta`] resource and the tile layer and element
+//! entities with a [`SpawnedMapLayerMeta`]. The resulting [`MapMeta`] can then be saved as a
+//! [`MapFile`], in the same YAML format as the maps in the asset pack, and a [`MapFile`] can be
+//! turned back into a [`MapMeta`] with the same asset pack.
+
+use std::path::Path;
+
+use crate::prelude::*;
+
+/// The file extension used for map files.
+pub const MAP_FILE_EXTENSION: &str = "map.yaml";
+
+/// An error that may occur while exporting a map, or while turning a [`MapFile`] back into a
+/// [`MapMeta`].
+#[derive(Debug, thiserror::Error)]
+pub enum MapExportError {
+    #[error("Error writing map: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Error serializing map: {0}")]
+    Serialize(#[from] serde_yaml::Error),
+    #[error("The map references an asset that was not loaded from the asset pack")]
+    MissingAssetPath,
+    #[error("The map references the asset `{0}`, which is not in the asset pack")]
+    UnknownAsset(String),
+    #[error("Invalid background color `{0}`")]
+    InvalidColor(String),
+}
+
+/// Re-construct the [`MapMeta`] of the map spawned in the world.
+pub fn export_map_meta(world: &World) -> MapMeta {
+    world.run_system(collect_map_meta, ())
+}
+
+fn collect_map_meta(
+    entities: Res<Entities>,
+    spawned_map_meta: Res<SpawnedMapMeta>,
+    spawned_map_layer_metas: Comp<SpawnedMapLayerMeta>,
+    tile_layers: Comp<TileLayer>,
+    tiles: Comp<Tile>,
+    tile_collisions: Comp<TileCollisionKind>,
+    element_handles: Comp<ElementHandle>,
+    original_element_handles: Comp<OriginalElementHandle>,
+    element_spawn_positions: Comp<ElementSpawnPos>,
+    transforms: Comp<Transform>,
+) -> MapMeta {
+    let layers = spawned_map_meta
+        .layer_names
+        .iter()
+        .enumerate()
+        .map(|(layer_idx, id)| {
+            let layer_idx = layer_idx as u32;
+            let tile_layer = entities
+                .iter_with((&tile_layers, &spawned_map_layer_metas))
+                .find(|(_, (_, layer_meta))| layer_meta.layer_idx == layer_idx)
+                .map(|(_, (tile_layer, _))| tile_layer);
+
+            let mut layer = MapLayerMeta {
+                id: *id,
+                ..default()
+            };
+
+            // Layers created in the editor have a tile layer with a default atlas until a tilemap
+            // is selected.
+            if let Some(tile_layer) = tile_layer.filter(|x| x.atlas != default()) {
+                layer.tilemap = Set(tile_layer.atlas);
+
+                for y in 0..spawned_map_meta.grid_size.y {
+                    for x in 0..spawned_map_meta.grid_size.x {
+                        let pos = uvec2(x, y);
+                        let Some(tile_ent) = tile_layer.get(pos) else {
+                            continue;
+                        };
+                        let Some(tile) = tiles.get(tile_ent) else {
+                            continue;
+                        };
+                        layer.tiles.push(MapTileMeta {
+                            pos,
+                            idx: tile.idx,
+                            // Empty tiles don't have a collision component, see `spawn_map()`.
+                            collision: tile_collisions.get(tile_ent).copied().unwrap_or_default(),
+                        });
+                    }
+                }
+            }
+
+            for (ent, (element_handle, layer_meta, transform)) in
+                entities.iter_with((&element_handles, &spawned_map_layer_metas, &transforms))
+            {
+                if layer_meta.layer_idx == layer_idx {
+                    layer.elements.push(ElementSpawn {
+                        // Export the element where it was placed, not where it has moved to.
+                        pos: element_spawn_positions
+                            .get(ent)
+                            .map_or(transform.translation.truncate(), |x| x.0),
+                        element: original_element_handles
+                            .get(ent)
+                            .map_or(element_handle.0, |x| x.0),
+                    });
+                }
+            }
+
+            layer
+        })
+        .collect();
+
+    MapMeta {
+        name: spawned_map_meta.name,
+        background: (*spawned_map_meta.background).clone(),
+        background_color: spawned_map_meta.background_color,
+        grid_size: spawned_map_meta.grid_size,
+        tile_size: spawned_map_meta.tile_size,
+        layers,
+        rules: (*spawned_map_meta.rules).clone(),
+    }
+}
+
+/// The serialized form of a [`MapMeta`], matching the `.map.yaml` asset format.
+///
+/// Asset handles are stored as absolute paths in the asset pack that they were loaded from.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MapFile {
+    pub name: String,
+    pub background: MapFileBackground,
+    /// The background color, formatted as `rgba(r, g, b, a)`.
+    pub background_color: String,
+    pub grid_size: UVec2,
+    pub tile_size: Vec2,
+    pub layers: Vec<MapFileLayer>,
+    #[serde(default)]
+    pub rules: MapFileRules,
+}
+
+/// The serialized form of a [`MapRulesMeta`].
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MapFileRules {
+    pub gravity_scale: Option<f32>,
+    pub terminal_velocity: Option<f32>,
+    pub friction_lerp: Option<f32>,
+    pub kill_zone_border: Option<f32>,
+    pub wrap_x: bool,
+    pub wrap_y: bool,
+    #[serde(default, with = "humantime_serde")]
+    pub respawn_invincibility_time: Option<Duration>,
+    pub banned_items: Vec<String>,
+    pub boosted_items: Vec<String>,
+    pub boost_chance: Option<f32>,
+}
+
+/// The serialized form of a [`BackgroundMeta`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MapFileBackground {
+    pub speed: Vec2,
+    pub layers: Vec<MapFileParallaxLayer>,
+}
+
+/// The serialized form of a [`ParallaxLayerMeta`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MapFileParallaxLayer {
+    pub image: String,
+    pub size: Vec2,
+    pub depth: f32,
+    pub scale: f32,
+    pub offset: Vec2,
+}
+
+/// The serialized form of a [`MapLayerMeta`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MapFileLayer {
+    pub id: String,
+    pub tilemap: Option<String>,
+    pub tiles: Vec<MapFileTile>,
+    pub elements: Vec<MapFileElement>,
+}
+
+/// The serialized form of a [`MapTileMeta`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MapFileTile {
+    pub pos: UVec2,
+    pub idx: u32,
+    pub collision: TileCollisionKind,
+}
+
+/// The serialized form of an [`ElementSpawn`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MapFileElement {
+    pub pos: Vec2,
+    pub element: String,
+}
+
+/// Get the path of the asset that a handle was loaded from, relative to the root of its pack.
+fn asset_path(assets: &AssetServer, handle: UntypedHandle) -> Result<String, MapExportError> {
+    let asset = assets
+        .get_asset_untyped(handle)
+        .ok_or(MapExportError::MissingAssetPath)?;
+    let path = Path::new("/").join(&asset.loc.path);
+    Ok(path.to_string_lossy().replace('\\', "/"))
+}
+
+/// Find the asset that was loaded from the given path among the handles.
+fn find_asset<T>(
+    assets: &AssetServer,
+    handles: impl IntoIterator<Item = Handle<T>>,
+    path: &str,
+) -> Result<Handle<T>, MapExportError> {
+    handles
+        .into_iter()
+        .find(|x| asset_path(assets, x.untyped()).is_ok_and(|x| x == path))
+        .ok_or_else(|| MapExportError::UnknownAsset(path.into()))
+}
+
+/// Parse a color formatted as `rgba(r, g, b, a)`.
+fn parse_rgba(color: &str) -> Option<Color> {
+    let values = color.trim().strip_prefix("rgba(")?.strip_suffix(')')?;
+    let mut values = values.split(',').map(|x| x.trim().parse::<u8>());
+    let mut next = || values.next()?.ok();
+    Some(Color::rgba_u8(next()?, next()?, next()?, next()?))
+}
+
+impl MapFile {
+    /// Create a map file from map metadata.
+    pub fn from_meta(meta: &MapMeta, assets: &AssetServer) -> Result<Self, MapExportError> {
+        let [r, g, b, a] = meta.background_color.as_rgba_u8();
+
+        Ok(Self {
+            name: meta.name.to_string(),
+            background: MapFileBackground {
+                speed: meta.background.speed,
+                layers: meta
+                    .background
+                    .layers
+                    .iter()
+                    .map(|layer| {
+                        Ok(MapFileParallaxLayer {
+                            image: asset_path(assets, layer.image.untyped())?,
+                            size: layer.size,
+                            depth: layer.depth,
+                            scale: layer.scale,
+                            offset: layer.offset,
+                        })
+                    })
+                    .collect::<Result<_, MapExportError>>()?,
+            },
+            background_color: format!("rgba({r}, {g}, {b}, {a})"),
+            grid_size: meta.grid_size,
+            tile_size: meta.tile_size,
+            layers: meta
+                .layers
+                .iter()
+                .map(|layer| {
+                    Ok(MapFileLayer {
+                        id: layer.id.to_string(),
+                        tilemap: match layer.tilemap {
+                            Set(handle) => Some(asset_path(assets, handle.untyped())?),
+                            Unset => None,
+                        },
+                        tiles: layer
+                            .tiles
+                            .iter()
+                            .map(|tile| MapFileTile {
+                                pos: tile.pos,
+                                idx: tile.idx,
+                                collision: tile.collision,
+                            })
+                            .collect(),
+                        elements: layer
+                            .elements
+                            .iter()
+                            .map(|element| {
+                                Ok(MapFileElement {
+                                    pos: element.pos,
+                                    element: asset_path(assets, element.element.untyped())?,
+                                })
+                            })
+                            .collect::<Result<_, MapExportError>>()?,
+                    })
+                })
+                .collect::<Result<_, MapExportError>>()?,
+            rules: {
+                let rules = &meta.rules;
+                let items = |items: &SVec<Handle<ElementMeta>>| {
+                    items
+                        .iter()
+                        .map(|x| asset_path(assets, x.untyped()))
+                        .collect::<Result<_, MapExportError>>()
+                };
+                MapFileRules {
+                    gravity_scale: rules.gravity_scale.option(),
+                    terminal_velocity: rules.terminal_velocity.option(),
+                    friction_lerp: rules.friction_lerp.option(),
+                    kill_zone_border: rules.kill_zone_border.option(),
+                    wrap_x: rules.wrap_x,
+                    wrap_y: rules.wrap_y,
+                    respawn_invincibility_time: rules.respawn_invincibility_time.option(),
+                    banned_items: items(&rules.banned_items)?,
+                    boosted_items: items(&rules.boosted_items)?,
+                    boost_chance: rules.boost_chance.option(),
+                }
+            },
+        })
+    }
+
+    /// Get the map metadata back from the map file, by finding the assets that it references in
+    /// the loaded asset pack.
+    pub fn to_meta(&self, assets: &AssetServer) -> Result<MapMeta, MapExportError> {
+        let core = &assets.root::<GameMeta>().core;
+        let images = super::map_import::known_images(core, assets);
+        let elements = |paths: &[String]| {
+            paths
+                .iter()
+                .map(|x| find_asset(assets, core.map_elements.iter().copied(), x))
+                .collect::<Result<_, MapExportError>>()
+        };
+
+        Ok(MapMeta {
+            name: self.name.as_str().into(),
+            background: BackgroundMeta {
+                speed: self.background.speed,
+                layers: self
+                    .background
+                    .layers
+                    .iter()
+                    .map(|layer| {
+                        Ok(ParallaxLayerMeta {
+                            image: find_asset(assets, images.iter().copied(), &layer.image)?,
+                            size: layer.size,
+                            depth: layer.depth,
+                            scale: layer.scale,
+                            offset: layer.offset,
+                        })
+                    })
+                    .collect::<Result<_, MapExportError>>()?,
+            },
+            background_color: parse_rgba(&self.background_color)
+                .ok_or_else(|| MapExportError::InvalidColor(self.background_color.clone()))?,
+            grid_size: self.grid_size,
+            tile_size: self.tile_size,
+            layers: self
+                .layers
+                .iter()
+                .map(|layer| {
+                    Ok(MapLayerMeta {
+                        id: layer.id.as_str().into(),
+                        tilemap: match &layer.tilemap {
+                            Some(path) => {
+                                Set(find_asset(assets, core.map_tilesets.iter().copied(), path)?)
+                            }
+                            None => Unset,
+                        },
+                        tiles: layer
+                            .tiles
+                            .iter()
+                            .map(|tile| MapTileMeta {
+                                pos: tile.pos,
+                                idx: tile.idx,
+                                collision: tile.collision,
+                            })
+                            .collect(),
+                        elements: layer
+                            .elements
+                            .iter()
+                            .map(|element| {
+                                Ok(ElementSpawn {
+                                    pos: element.pos,
+                                    element: find_asset(
+                                        assets,
+                                        core.map_elements.iter().copied(),
+                                        &element.element,
+                                    )?,
+                                })
+                            })
+                            .collect::<Result<_, MapExportError>>()?,
+                    })
+                })
+                .collect::<Result<_, MapExportError>>()?,
+            rules: {
+                let rules = &self.rules;
+                MapRulesMeta {
+                    gravity_scale: rules.gravity_scale.map_or(Unset, Set),
+                    terminal_velocity: rules.terminal_velocity.map_or(Unset, Set),
+                    friction_lerp: rules.friction_lerp.map_or(Unset, Set),
+                    kill_zone_border: rules.kill_zone_border.map_or(Unset, Set),
+                    wrap_x: rules.wrap_x,
+                    wrap_y: rules.wrap_y,
+                    respawn_invincibility_time: rules.respawn_invincibility_time.map_or(Unset, Set),
+                    banned_items: elements(&rules.banned_items)?,
+                    boosted_items: elements(&rules.boosted_items)?,
+                    boost_chance: rules.boost_chance.map_or(Unset, Set),
+                }
+            },
+        })
+    }
+
+    /// Create a map file from the map spawned in the world of a running match.
+    pub fn from_world(world: &World, assets: &AssetServer) -> Result<Self, MapExportError> {
+        Self::from_meta(&export_map_meta(world), assets)
+    }
+
+    /// Save the map file to disk.
+    pub fn save(&self, path: &Path) -> Result<(), MapExportError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = std::fs::File::create(path)?;
+        serde_yaml::to_writer(std::io::BufWriter::new(file), self)?;
+        Ok(())
+    }
+}
+
+/// Get the directory that maps are exported to by default.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn map_export_dir() -> std::path::PathBuf {
+    directories::ProjectDirs::from("org", "fishfolk", "jumpy")
+        .data_dir()
+        .join("maps")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::headless::start_test_match;
+
+    #[test]
+    fn exported_map_matches_loaded_map() {
+        let game = start_test_match(1);
+
+        let world = &game.sessions.get(SessionNames::GAME).unwrap().world;
+        let map = world.resource::<LoadedMap>().0.clone();
+        let exported = export_map_meta(world);
+
+        assert_eq!(exported.name, map.name);
+        assert_eq!(exported.grid_size, map.grid_size);
+        assert_eq!(exported.layers.len(), map.layers.len());
+        for (exported, original) in exported.layers.iter().zip(map.layers.iter()) {
+            assert_eq!(exported.id, original.id);
+            let tiles = |layer: &MapLayerMeta| {
+                let mut tiles = layer
+                    .tiles
+                    .iter()
+                    .map(|x| (x.pos.to_array(), x.idx, x.collision))
+                    .collect::<Vec<_>>();
+                tiles.sort_by_key(|x| (x.0, x.1));
+                tiles
+            };
+            assert_eq!(tiles(exported), tiles(original));
+            let elements = |layer: &MapLayerMeta| {
+                layer
+                    .elements
+                    .iter()
+                    .map(|x| (x.pos, x.element))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(elements(exported), elements(original));
+        }
+
+        let assets = game.shared_resource::<AssetServer>().unwrap();
+        let file = MapFile::from_world(world, &assets).unwrap();
+        assert!(file.layers.iter().all(|x| x
+            .elements
+            .iter()
+            .all(|x| x.element.starts_with('/') && x.element.ends_with(".element.yaml"))));
+
+        // The map file resolves back to the same assets.
+        let resolved = file.to_meta(&assets).unwrap();
+        assert_eq!(resolved.name, exported.name);
+        for (resolved, exported) in resolved.layers.iter().zip(exported.layers.iter()) {
+            let elements = |layer: &MapLayerMeta| {
+                layer
+                    .elements
+                    .iter()
+                    .map(|x| (x.pos, x.element))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(elements(resolved), elements(exported));
+        }
+    }
+
+    #[test]
+    fn exported_elements_keep_spawn_position() {
+        let game = start_test_match(1);
+
+        let world = &game.sessions.get(SessionNames::GAME).unwrap().world;
+        let map = world.resource::<LoadedMap>().0.clone();
+
+        // Move every element away from where it was spawned.
+        world.run_system(
+            |entities: Res<Entities>,
+             element_handles: Comp<ElementHandle>,
+             mut transforms: CompMut<Transform>| {
+                for (_, (_, transform)) in entities.iter_with((&element_handles, &mut transforms)) {
+                    transform.translation += Vec3::new(100.0, 100.0, 0.0);
+                }
+            },
+            (),
+        );
+
+        let exported = export_map_meta(world);
+        for (exported, original) in exported.layers.iter().zip(map.layers.iter()) {
+            let positions =
+                |layer: &MapLayerMeta| layer.elements.iter().map(|x| x.pos).collect::<Vec<_>>();
+            assert_eq!(positions(exported), positions(original));
+        }
+    }
+}
@@ -0,0 +1,876 @@
+//! Importing maps made with the [Tiled](https://www.mapeditor.org/) map editor.
+//!
+//! Tiled maps saved as JSON (`.tmj`) or XML (`.tmx`) are read into a [`TiledMap`], which is then
+//! converted to a [`MapMeta`] by resolving the tilesets, objects and images that it references
+//! against the assets of the [`CoreMeta`]:
+//!
+//! - Tile layers become [`MapLayerMeta::tiles`]. The tileset of the layer must use the same image
+//!   as one of the [`CoreMeta::map_tilesets`], and the collision of each tile is read from the
+//!   `collision` custom property of the tile in the tileset (`solid`, `jump_through` or `empty`).
+//! - Object layers become [`ElementSpawn`]s, matching the name, or the class if the object has no
+//!   name, against the names of the [`CoreMeta::map_elements`].
+//! - Image layers become [`ParallaxLayerMeta`]s of the map background, using the `depth` and
+//!   `scale` custom properties of the layer. The background speed is read from the
+//!   `background_speed_x` and `background_speed_y` custom properties of the map.
+//!
+//! Infinite maps and compressed tile layer data are not supported.
+
+use std::path::{Component, Path, PathBuf};
+
+use base64::Engine;
+use serde::de::Error as _;
+use xml::reader::{EventReader, XmlEvent};
+
+use crate::prelude::*;
+
+/// The bits of a Tiled global tile ID that are used as flip flags.
+const FLIP_FLAGS: u32 = 0xF000_0000;
+
+/// An error that may occur while importing a Tiled map.
+#[derive(Debug, thiserror::Error)]
+pub enum TiledImportError {
+    #[error("Error reading map: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Error parsing JSON map: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Error parsing TMX map: {0}")]
+    Xml(#[from] xml::reader::Error),
+    #[error("Invalid TMX map: {0}")]
+    InvalidTmx(String),
+    #[error("Unsupported map file `{0}`, expected a `.tmj`, `.json` or `.tmx` file")]
+    UnsupportedFormat(PathBuf),
+    #[error("Infinite maps are not supported")]
+    InfiniteMap,
+    #[error("Layer `{layer}` has invalid tile data: {reason}")]
+    InvalidTileData { layer: String, reason: String },
+    #[error("Layer `{layer}` uses more than one tileset")]
+    MultipleTilesets { layer: String },
+    #[error("Layer `{layer}` uses tile {gid}, which is not in any tileset")]
+    MissingTileset { layer: String, gid: u32 },
+    #[error("No tilemap in the asset pack uses `{image}`, the image of tileset `{tileset}`")]
+    UnknownTilemap { tileset: String, image: PathBuf },
+    #[error("Tile {tile} of tileset `{tileset}` has an invalid collision `{value}`")]
+    InvalidCollision {
+        tileset: String,
+        tile: u32,
+        value: String,
+    },
+    #[error("There is no map element named `{0}`")]
+    UnknownElement(String),
+    #[error("No image in the asset pack matches `{0}`")]
+    UnknownImage(PathBuf),
+}
+
+/// A map made with Tiled.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct TiledMap {
+    /// The name of the map, taken from the file name when loaded with [`TiledMap::load()`].
+    #[serde(skip)]
+    pub name: String,
+    /// The directory that the paths in the map are relative to.
+    #[serde(skip)]
+    pub base_dir: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    #[serde(rename = "tilewidth")]
+    pub tile_width: u32,
+    #[serde(rename = "tileheight")]
+    pub tile_height: u32,
+    #[serde(default)]
+    pub infinite: bool,
+    /// The background color, formatted as `#RRGGBB` or `#AARRGGBB`.
+    #[serde(rename = "backgroundcolor", default)]
+    pub background_color: Option<String>,
+    #[serde(default)]
+    pub properties: Vec<TiledProperty>,
+    #[serde(default)]
+    pub tilesets: Vec<TiledTileset>,
+    #[serde(default)]
+    pub layers: Vec<TiledLayer>,
+}
+
+/// A custom property of a Tiled map, layer or tile.
+#[derive(Deserialize, Clone, Debug)]
+pub struct TiledProperty {
+    pub name: String,
+    pub value: serde_json::Value,
+}
+
+/// A tileset of a Tiled map.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct TiledTileset {
+    #[serde(rename = "firstgid", default)]
+    pub first_gid: u32,
+    /// The path of the external tileset file, if the tileset isn't embedded in the map.
+    #[serde(default)]
+    pub source: Option<PathBuf>,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub image: PathBuf,
+    #[serde(default)]
+    pub tiles: Vec<TiledTile>,
+}
+
+/// The custom properties of a tile in a tileset.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct TiledTile {
+    pub id: u32,
+    #[serde(default)]
+    pub properties: Vec<TiledProperty>,
+}
+
+/// A layer of a Tiled map.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TiledLayer {
+    TileLayer(TiledTileLayer),
+    ObjectGroup(TiledObjectLayer),
+    ImageLayer(TiledImageLayer),
+    Group(TiledGroupLayer),
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct TiledTileLayer {
+    pub name: String,
+    /// The global tile IDs of the layer, row by row from the top of the map.
+    #[serde(deserialize_with = "deserialize_tile_data", default)]
+    pub data: Vec<u32>,
+    /// The compression of base64 tile data, which isn't supported.
+    #[serde(default)]
+    pub compression: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct TiledObjectLayer {
+    pub name: String,
+    #[serde(default)]
+    pub objects: Vec<TiledObject>,
+}
+
+/// An object in an object layer, positioned in pixels from the top left of the map.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct TiledObject {
+    #[serde(default)]
+    pub name: String,
+    /// The class of the object, which was called the type before Tiled 1.9.
+    #[serde(alias = "type", default)]
+    pub class: String,
+    pub x: f32,
+    pub y: f32,
+    #[serde(default)]
+    pub width: f32,
+    #[serde(default)]
+    pub height: f32,
+    /// The tile of a tile object, which is positioned by its bottom left corner.
+    #[serde(default)]
+    pub gid: Option<u32>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct TiledImageLayer {
+    pub name: String,
+    pub image: PathBuf,
+    #[serde(rename = "imagewidth", default)]
+    pub image_width: f32,
+    #[serde(rename = "imageheight", default)]
+    pub image_height: f32,
+    #[serde(rename = "offsetx", default)]
+    pub offset_x: f32,
+    #[serde(rename = "offsety", default)]
+    pub offset_y: f32,
+    #[serde(default)]
+    pub properties: Vec<TiledProperty>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct TiledGroupLayer {
+    pub name: String,
+    #[serde(default)]
+    pub layers: Vec<TiledLayer>,
+}
+
+/// Import a Tiled map file as a [`MapMeta`].
+pub fn import_tiled_map(path: &Path, assets: &AssetServer) -> Result<MapMeta, TiledImportError> {
+    TiledMap::load(path)?.to_map_meta(assets)
+}
+
+impl TiledMap {
+    /// Load a Tiled map from a `.tmj`, `.json` or `.tmx` file, along with its external tilesets.
+    pub fn load(path: &Path) -> Result<Self, TiledImportError> {
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        let base_dir = base_dir
+            .canonicalize()
+            .unwrap_or_else(|_| base_dir.to_path_buf());
+        let data = std::fs::read_to_string(path)?;
+        let mut map = match path.extension().and_then(|x| x.to_str()) {
+            Some("tmj" | "json") => Self::from_json(&data)?,
+            Some("tmx") => Self::from_tmx(&data)?,
+            _ => return Err(TiledImportError::UnsupportedFormat(path.to_path_buf())),
+        };
+        map.name = path
+            .file_stem()
+            .map(|x| x.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        for tileset in &mut map.tilesets {
+            let Some(source) = tileset.source.take() else {
+                continue;
+            };
+            let data = std::fs::read_to_string(base_dir.join(&source))?;
+            let external = match source.extension().and_then(|x| x.to_str()) {
+                Some("tsj" | "json") => serde_json::from_str::<TiledTileset>(&data)?,
+                Some("tsx") => tmx::parse_tileset(&tmx::parse(&data)?)?,
+                _ => return Err(TiledImportError::UnsupportedFormat(source.clone())),
+            };
+            // Images in external tilesets are relative to the tileset file.
+            let tileset_dir = source.parent().unwrap_or(Path::new(""));
+            *tileset = TiledTileset {
+                first_gid: tileset.first_gid,
+                source: None,
+                image: tileset_dir.join(&external.image),
+                ..external
+            };
+        }
+
+        map.base_dir = base_dir;
+        Ok(map)
+    }
+
+    /// Parse a map saved in the Tiled JSON format.
+    pub fn from_json(data: &str) -> Result<Self, TiledImportError> {
+        Ok(serde_json::from_str(data)?)
+    }
+
+    /// Parse a map saved in the Tiled XML format.
+    pub fn from_tmx(data: &str) -> Result<Self, TiledImportError> {
+        tmx::parse_map(&tmx::parse(data)?)
+    }
+
+    /// Convert the map to a [`MapMeta`], resolving the assets that it uses.
+    pub fn to_map_meta(&self, assets: &AssetServer) -> Result<MapMeta, TiledImportError> {
+        if self.infinite {
+            return Err(TiledImportError::InfiniteMap);
+        }
+        let core = &assets.root::<GameMeta>().core;
+        let tile_size = vec2(self.tile_width as f32, self.tile_height as f32);
+        let map_height = self.height as f32 * tile_size.y;
+
+        let mut meta = MapMeta {
+            name: property(&self.properties, "name")
+                .and_then(|x| x.as_str())
+                .unwrap_or(self.name.as_str())
+                .into(),
+            background_color: self
+                .background_color
+                .as_deref()
+                .and_then(parse_color)
+                .unwrap_or_default(),
+            grid_size: uvec2(self.width, self.height),
+            tile_size,
+            ..default()
+        };
+        meta.background.speed = vec2(
+            property_f32(&self.properties, "background_speed_x").unwrap_or_default(),
+            property_f32(&self.properties, "background_speed_y").unwrap_or_default(),
+        );
+
+        let mut layers = Vec::new();
+        flatten_layers(&self.layers, &mut layers);
+        for layer in layers {
+            match layer {
+                TiledLayer::TileLayer(layer) => {
+                    meta.layers
+                        .push(self.convert_tile_layer(layer, core, assets)?);
+                }
+                TiledLayer::ObjectGroup(layer) => {
+                    let elements = layer
+                        .objects
+                        .iter()
+                        .map(|object| {
+                            let name = if object.name.is_empty() {
+                                &object.class
+                            } else {
+                                &object.name
+                            };
+                            let element = core
+                                .map_elements
+                                .iter()
+                                .copied()
+                                .find(|&x| assets.get(x).name.eq_ignore_ascii_case(name))
+                                .ok_or_else(|| TiledImportError::UnknownElement(name.clone()))?;
+                            // Tile objects are positioned by their bottom left corner.
+                            let top = if object.gid.is_some() {
+                                object.y - object.height
+                            } else {
+                                object.y
+                            };
+                            let center = vec2(
+                                object.x + object.width / 2.0,
+                                map_height - (top + object.height / 2.0),
+                            );
+                            Ok(ElementSpawn {
+                                pos: center,
+                                element,
+                            })
+                        })
+                        .collect::<Result<_, TiledImportError>>()?;
+                    meta.layers.push(MapLayerMeta {
+                        id: layer.name.as_str().into(),
+                        elements,
+                        ..default()
+                    });
+                }
+                TiledLayer::ImageLayer(layer) => {
+                    let image_path = self.base_dir.join(&layer.image);
+                    let image = known_images(core, assets)
+                        .into_iter()
+                        .find(|&x| is_asset_at(assets, x.untyped(), &image_path))
+                        .ok_or(TiledImportError::UnknownImage(image_path))?;
+                    meta.background.layers.push(ParallaxLayerMeta {
+                        image,
+                        size: vec2(layer.image_width, layer.image_height),
+                        depth: property_f32(&layer.properties, "depth").unwrap_or(1.0),
+                        scale: property_f32(&layer.properties, "scale").unwrap_or(1.0),
+                        offset: vec2(layer.offset_x, -layer.offset_y),
+                    });
+                }
+                TiledLayer::Group(_) => unreachable!("Group layers are flattened"),
+            }
+        }
+
+        Ok(meta)
+    }
+
+    fn convert_tile_layer(
+        &self,
+        layer: &TiledTileLayer,
+        core: &CoreMeta,
+        assets: &AssetServer,
+    ) -> Result<MapLayerMeta, TiledImportError> {
+        let invalid_data = |reason: String| TiledImportError::InvalidTileData {
+            layer: layer.name.clone(),
+            reason,
+        };
+        if let Some(compression) = &layer.compression {
+            return Err(invalid_data(format!(
+                "`{compression}` compression is not supported"
+            )));
+        }
+        if layer.data.len() != (self.width * self.height) as usize {
+            return Err(invalid_data(format!(
+                "expected {} tiles, found {}",
+                self.width * self.height,
+                layer.data.len()
+            )));
+        }
+
+        let mut tileset = None::<&TiledTileset>;
+        let mut tiles = SVec::new();
+        for (i, &gid) in layer.data.iter().enumerate() {
+            // Flipped tiles aren't supported, so the flip flags are ignored.
+            let gid = gid & !FLIP_FLAGS;
+            if gid == 0 {
+                continue;
+            }
+            let tile_tileset = self
+                .tilesets
+                .iter()
+                .filter(|x| x.first_gid <= gid)
+                .max_by_key(|x| x.first_gid)
+                .ok_or_else(|| TiledImportError::MissingTileset {
+                    layer: layer.name.clone(),
+                    gid,
+                })?;
+            if tileset.is_some_and(|x| x.first_gid != tile_tileset.first_gid) {
+                return Err(TiledImportError::MultipleTilesets {
+                    layer: layer.name.clone(),
+                });
+            }
+            tileset = Some(tile_tileset);
+
+            let idx = gid - tile_tileset.first_gid;
+            let collision = tile_tileset
+                .tiles
+                .iter()
+                .find(|x| x.id == idx)
+                .and_then(|x| property(&x.properties, "collision"))
+                .map(|value| {
+                    let value = value.as_str().unwrap_or_default();
+                    parse_collision(value).ok_or_else(|| TiledImportError::InvalidCollision {
+                        tileset: tile_tileset.name.clone(),
+                        tile: idx,
+                        value: value.to_string(),
+                    })
+                })
+                .transpose()?
+                .unwrap_or_default();
+
+            let (x, row) = (i as u32 % self.width, i as u32 / self.width);
+            tiles.push(MapTileMeta {
+                // Tiled rows start at the top of the map.
+                pos: uvec2(x, self.height - 1 - row),
+                idx,
+                collision,
+            });
+        }
+
+        let tilemap = match tileset {
+            Some(tileset) => {
+                let image_path = self.base_dir.join(&tileset.image);
+                let atlas = core
+                    .map_tilesets
+                    .iter()
+                    .copied()
+                    .find(|&x| is_asset_at(assets, assets.get(x).image.untyped(), &image_path))
+                    .ok_or_else(|| TiledImportError::UnknownTilemap {
+                        tileset: tileset.name.clone(),
+                        image: image_path,
+                    })?;
+                Set(atlas)
+            }
+            None => Unset,
+        };
+
+        Ok(MapLayerMeta {
+            id: layer.name.as_str().into(),
+            tilemap,
+            tiles,
+            ..default()
+        })
+    }
+}
+
+/// Collect the layers inside of group layers into a flat list.
+fn flatten_layers<'a>(layers: &'a [TiledLayer], flattened: &mut Vec<&'a TiledLayer>) {
+    for layer in layers {
+        match layer {
+            TiledLayer::Group(group) => flatten_layers(&group.layers, flattened),
+            layer => flattened.push(layer),
+        }
+    }
+}
+
+fn property<'a>(properties: &'a [TiledProperty], name: &str) -> Option<&'a serde_json::Value> {
+    properties.iter().find(|x| x.name == name).map(|x| &x.value)
+}
+
+/// Get a number property, which is stored as a string in TMX maps.
+fn property_f32(properties: &[TiledProperty], name: &str) -> Option<f32> {
+    let value = property(properties, name)?;
+    value
+        .as_f64()
+        .map(|x| x as f32)
+        .or_else(|| value.as_str()?.parse().ok())
+}
+
+fn parse_collision(value: &str) -> Option<TileCollisionKind> {
+    match value.to_lowercase().replace(['_', '-', ' '], "").as_str() {
+        "" | "empty" => Some(TileCollisionKind::Empty),
+        "solid" => Some(TileCollisionKind::Solid),
+        "jumpthrough" => Some(TileCollisionKind::JumpThrough),
+        _ => None,
+    }
+}
+
+/// Parse a Tiled color, formatted as `#RRGGBB` or `#AARRGGBB`.
+fn parse_color(color: &str) -> Option<Color> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    match hex.len() {
+        6 => Some(Color::rgba_u8(byte(0)?, byte(2)?, byte(4)?, 255)),
+        8 => Some(Color::rgba_u8(byte(2)?, byte(4)?, byte(6)?, byte(0)?)),
+        _ => None,
+    }
+}
+
+/// Get the images that may be used by image layers: the backgrounds of the maps and the images of
+/// the tilesets in the asset pack.
+pub(crate) fn known_images(core: &CoreMeta, assets: &AssetServer) -> Vec<Handle<Image>> {
+    let maps = core.stable_maps.iter().chain(core.experimental_maps.iter());
+    maps.flat_map(|&map| {
+        assets
+            .get(map)
+            .background
+            .layers
+            .iter()
+            .map(|x| x.image)
+            .collect::<Vec<_>>()
+    })
+    .chain(core.map_tilesets.iter().map(|&x| assets.get(x).image))
+    .collect()
+}
+
+/// Whether an asset was loaded from the given file path.
+fn is_asset_at(assets: &AssetServer, handle: UntypedHandle, path: &Path) -> bool {
+    let Some(asset) = assets.get_asset_untyped(handle) else {
+        return false;
+    };
+    let asset_path = asset
+        .loc
+        .path
+        .strip_prefix("/")
+        .unwrap_or(asset.loc.path.as_path());
+    normalize_path(path).ends_with(asset_path)
+}
+
+/// Remove the `.` and `..` components of a path.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Deserialize the tile data of a JSON tile layer, which is either an array of global tile IDs or
+/// a base64 string.
+fn deserialize_tile_data<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<u32>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum TileData {
+        Gids(Vec<u32>),
+        Base64(String),
+    }
+    match TileData::deserialize(deserializer)? {
+        TileData::Gids(gids) => Ok(gids),
+        TileData::Base64(data) => decode_base64_tiles(&data).map_err(D::Error::custom),
+    }
+}
+
+/// Decode uncompressed base64 tile data, which stores the global tile IDs as little endian `u32`s.
+fn decode_base64_tiles(data: &str) -> Result<Vec<u32>, String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(data.trim())
+        .map_err(|e| e.to_string())?;
+    if bytes.len() % 4 != 0 {
+        return Err("the data is not a list of 32-bit tile IDs".into());
+    }
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+        .collect())
+}
+
+/// Parsing of the Tiled XML format.
+mod tmx {
+    use super::*;
+
+    /// An element of an XML document.
+    #[derive(Default)]
+    pub struct XmlElement {
+        pub name: String,
+        pub attributes: HashMap<String, String>,
+        pub children: Vec<XmlElement>,
+        pub text: String,
+    }
+
+    impl XmlElement {
+        fn attr(&self, name: &str) -> Option<&str> {
+            self.attributes.get(name).map(|x| x.as_str())
+        }
+
+        fn attr_or_default<T: std::str::FromStr + Default>(
+            &self,
+            name: &str,
+        ) -> Result<T, TiledImportError> {
+            self.attr(name)
+                .map(|x| {
+                    x.parse().map_err(|_| {
+                        TiledImportError::InvalidTmx(format!(
+                            "Invalid `{name}` attribute of `{}`: `{x}`",
+                            self.name
+                        ))
+                    })
+                })
+                .transpose()
+                .map(|x| x.unwrap_or_default())
+        }
+
+        fn child(&self, name: &str) -> Option<&XmlElement> {
+            self.children.iter().find(|x| x.name == name)
+        }
+
+        fn properties(&self) -> Vec<TiledProperty> {
+            self.child("properties")
+                .map(|properties| {
+                    properties
+                        .children
+                        .iter()
+                        .filter(|x| x.name == "property")
+                        .map(|x| TiledProperty {
+                            name: x.attr("name").unwrap_or_default().to_string(),
+                            // Multi-line string properties are stored in the element text.
+                            value: x.attr("value").unwrap_or(x.text.as_str()).into(),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+    }
+
+    /// Parse an XML document into its root element.
+    pub fn parse(data: &str) -> Result<XmlElement, TiledImportError> {
+        let mut stack = vec![XmlElement::default()];
+        for event in EventReader::from_str(data) {
+            match event? {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } => stack.push(XmlElement {
+                    name: name.local_name,
+                    attributes: attributes
+                        .into_iter()
+                        .map(|x| (x.name.local_name, x.value))
+                        .collect(),
+                    ..default()
+                }),
+                XmlEvent::EndElement { .. } => {
+                    let element = stack.pop().unwrap();
+                    stack.last_mut().unwrap().children.push(element);
+                }
+                XmlEvent::Characters(text) | XmlEvent::CData(text) => {
+                    stack.last_mut().unwrap().text.push_str(&text);
+                }
+                _ => (),
+            }
+        }
+        stack
+            .pop()
+            .and_then(|mut document| document.children.pop())
+            .ok_or_else(|| TiledImportError::InvalidTmx("The document is empty".into()))
+    }
+
+    pub fn parse_map(map: &XmlElement) -> Result<TiledMap, TiledImportError> {
+        if map.name != "map" {
+            return Err(TiledImportError::InvalidTmx(format!(
+                "Expected a `map` element, found `{}`",
+                map.name
+            )));
+        }
+        Ok(TiledMap {
+            width: map.attr_or_default("width")?,
+            height: map.attr_or_default("height")?,
+            tile_width: map.attr_or_default("tilewidth")?,
+            tile_height: map.attr_or_default("tileheight")?,
+            infinite: map.attr("infinite") == Some("1"),
+            background_color: map.attr("backgroundcolor").map(|x| x.to_string()),
+            properties: map.properties(),
+            tilesets: map
+                .children
+                .iter()
+                .filter(|x| x.name == "tileset")
+                .map(parse_tileset)
+                .collect::<Result<_, _>>()?,
+            layers: parse_layers(map)?,
+            ..default()
+        })
+    }
+
+    pub fn parse_tileset(tileset: &XmlElement) -> Result<TiledTileset, TiledImportError> {
+        Ok(TiledTileset {
+            first_gid: tileset.attr_or_default("firstgid")?,
+            source: tileset.attr("source").map(PathBuf::from),
+            name: tileset.attr("name").unwrap_or_default().to_string(),
+            image: tileset
+                .child("image")
+                .and_then(|x| x.attr("source"))
+                .map(PathBuf::from)
+                .unwrap_or_default(),
+            tiles: tileset
+                .children
+                .iter()
+                .filter(|x| x.name == "tile")
+                .map(|tile| {
+                    Ok(TiledTile {
+                        id: tile.attr_or_default("id")?,
+                        properties: tile.properties(),
+                    })
+                })
+                .collect::<Result<_, TiledImportError>>()?,
+        })
+    }
+
+    fn parse_layers(parent: &XmlElement) -> Result<Vec<TiledLayer>, TiledImportError> {
+        let mut layers = Vec::new();
+        for element in &parent.children {
+            let name = element.attr("name").unwrap_or_default().to_string();
+            layers.push(match element.name.as_str() {
+                "layer" => TiledLayer::TileLayer(TiledTileLayer {
+                    data: parse_tile_data(element, &name)?,
+                    name,
+                    ..default()
+                }),
+                "objectgroup" => TiledLayer::ObjectGroup(TiledObjectLayer {
+                    name,
+                    objects: element
+                        .children
+                        .iter()
+                        .filter(|x| x.name == "object")
+                        .map(|object| {
+                            Ok(TiledObject {
+                                name: object.attr("name").unwrap_or_default().to_string(),
+                                class: object
+                                    .attr("class")
+                                    .or(object.attr("type"))
+                                    .unwrap_or_default()
+                                    .to_string(),
+                                x: object.attr_or_default("x")?,
+                                y: object.attr_or_default("y")?,
+                                width: object.attr_or_default("width")?,
+                                height: object.attr_or_default("height")?,
+                                gid: object.attr("gid").and_then(|x| x.parse().ok()),
+                            })
+                        })
+                        .collect::<Result<_, TiledImportError>>()?,
+                }),
+                "imagelayer" => {
+                    let image = element.child("image");
+                    TiledLayer::ImageLayer(TiledImageLayer {
+                        name,
+                        image: image
+                            .and_then(|x| x.attr("source"))
+                            .map(PathBuf::from)
+                            .unwrap_or_default(),
+                        image_width: image.map_or(Ok(0.0), |x| x.attr_or_default("width"))?,
+                        image_height: image.map_or(Ok(0.0), |x| x.attr_or_default("height"))?,
+                        offset_x: element.attr_or_default("offsetx")?,
+                        offset_y: element.attr_or_default("offsety")?,
+                        properties: element.properties(),
+                    })
+                }
+                "group" => TiledLayer::Group(TiledGroupLayer {
+                    name,
+                    layers: parse_layers(element)?,
+                }),
+                _ => continue,
+            });
+        }
+        Ok(layers)
+    }
+
+    fn parse_tile_data(layer: &XmlElement, name: &str) -> Result<Vec<u32>, TiledImportError> {
+        let invalid_data = |reason: String| TiledImportError::InvalidTileData {
+            layer: name.to_string(),
+            reason,
+        };
+        let Some(data) = layer.child("data") else {
+            return Ok(Vec::new());
+        };
+        if let Some(compression) = data.attr("compression") {
+            return Err(invalid_data(format!(
+                "`{compression}` compression is not supported"
+            )));
+        }
+        if data.child("chunk").is_some() {
+            return Err(TiledImportError::InfiniteMap);
+        }
+        match data.attr("encoding") {
+            Some("csv") => data
+                .text
+                .split(',')
+                .map(|x| x.trim().parse().map_err(|_| invalid_data(format!("`{x}`"))))
+                .collect(),
+            Some("base64") => decode_base64_tiles(&data.text).map_err(invalid_data),
+            // Without an encoding, every tile is stored as its own element.
+            None => data
+                .children
+                .iter()
+                .filter(|x| x.name == "tile")
+                .map(|x| x.attr_or_default("gid"))
+                .collect(),
+            Some(encoding) => Err(invalid_data(format!(
+                "`{encoding}` encoding is not supported"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::headless::load_game;
+
+    #[test]
+    fn json_and_tmx_maps_match() {
+        let game = load_game(Path::new("assets"), Path::new("packs")).unwrap();
+        let assets = game.shared_resource::<AssetServer>().unwrap();
+        let core = &assets.root::<GameMeta>().core;
+        let atlas_image = assets
+            .get_asset_untyped(assets.get(core.map_tilesets[0]).image.untyped())
+            .unwrap()
+            .loc
+            .path
+            .clone();
+        let atlas_image = atlas_image.to_string_lossy();
+        let atlas_image = atlas_image.trim_start_matches('/');
+
+        let json = format!(
+            r#"{{
+                "width": 3, "height": 2, "tilewidth": 16, "tileheight": 16,
+                "tilesets": [{{
+                    "firstgid": 1, "name": "tiles", "image": "{atlas_image}",
+                    "tiles": [{{
+                        "id": 1,
+                        "properties": [{{"name": "collision", "type": "string", "value": "solid"}}]
+                    }}]
+                }}],
+                "layers": [
+                    {{"type": "tilelayer", "name": "tiles", "data": [0, 0, 1, 2, 2, 0]}},
+                    {{"type": "objectgroup", "name": "elements", "objects": [
+                        {{"name": "Player Spawner", "x": 8, "y": 4, "width": 0, "height": 0}}
+                    ]}}
+                ]
+            }}"#
+        );
+        let tmx = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <map width="3" height="2" tilewidth="16" tileheight="16">
+              <tileset firstgid="1" name="tiles">
+                <image source="{atlas_image}"/>
+                <tile id="1">
+                  <properties><property name="collision" value="solid"/></properties>
+                </tile>
+              </tileset>
+              <layer name="tiles" width="3" height="2">
+                <data encoding="csv">0,0,1,2,2,0</data>
+              </layer>
+              <objectgroup name="elements">
+                <object class="Player Spawner" x="8" y="4"/>
+              </objectgroup>
+            </map>"#
+        );
+
+        for map in [TiledMap::from_json(&json), TiledMap::from_tmx(&tmx)] {
+            let meta = map.unwrap().to_map_meta(&assets).unwrap();
+            assert_eq!(meta.grid_size, uvec2(3, 2));
+            assert_eq!(meta.layers.len(), 2);
+
+            let tiles = meta.layers[0]
+                .tiles
+                .iter()
+                .map(|x| (x.pos, x.idx, x.collision))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                tiles,
+                [
+                    (uvec2(2, 1), 0, TileCollisionKind::Empty),
+                    (uvec2(0, 0), 1, TileCollisionKind::Solid),
+                    (uvec2(1, 0), 1, TileCollisionKind::Solid),
+                ]
+            );
+            assert_eq!(meta.layers[0].tilemap.option(), Some(core.map_tilesets[0]));
+
+            let element = &meta.layers[1].elements[0];
+            assert_eq!(element.pos, vec2(8.0, 28.0));
+            assert_eq!(assets.get(element.element).name.as_str(), "Player Spawner");
+        }
+    }
+}
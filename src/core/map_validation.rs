@@ -0,0 +1,166 @@
+//! Map validation.
+//!
+//! [`validate_map()`] checks a [`MapMeta`] for problems that make the map broken or unplayable.
+//! It is run when a map is spawned, logging the problems as warnings, and by the
+//! `jumpy-map-lint` binary, which is used to check the maps in the asset pack in CI.
+
+use crate::prelude::*;
+
+/// A problem found in a map by [`validate_map()`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum MapProblem {
+    #[error("Layer `{layer}` has a tile at {pos}, outside of the {grid_size} map")]
+    TileOutOfBounds {
+        layer: Ustr,
+        pos: UVec2,
+        grid_size: UVec2,
+    },
+    #[error("Layer `{layer}` has more than one tile at {pos}")]
+    DuplicateTile { layer: Ustr, pos: UVec2 },
+    #[error("Layer `{layer}` has tiles, but no tilemap")]
+    MissingTilemap { layer: Ustr },
+    #[error(
+        "The map has {count} player spawners, but needs at least {}",
+        MAX_PLAYERS
+    )]
+    NotEnoughSpawners { count: usize },
+    #[error("The player spawner at {pos} is inside of a solid tile")]
+    SpawnerInSolidTile { pos: Vec2 },
+    #[error("Layer `{layer}` has an element at {pos} that couldn't be loaded")]
+    MissingElement { layer: Ustr, pos: Vec2 },
+    #[error("The player spawner at {from} can't reach the player spawner at {to}")]
+    UnreachableSpawner { from: Vec2, to: Vec2 },
+}
+
+/// Check a map for problems.
+pub fn validate_map(meta: &MapMeta, assets: &AssetServer) -> Vec<MapProblem> {
+    let mut problems = Vec::new();
+    let mut solid_tiles = HashSet::default();
+    let mut spawners = Vec::new();
+    let mut sproingers = Vec::new();
+
+    for layer in meta.layers.iter() {
+        let mut positions = HashSet::default();
+        for tile in layer.tiles.iter() {
+            if tile.pos.cmpge(meta.grid_size).any() {
+                problems.push(MapProblem::TileOutOfBounds {
+                    layer: layer.id,
+                    pos: tile.pos,
+                    grid_size: meta.grid_size,
+                });
+            }
+            if !positions.insert(tile.pos) {
+                problems.push(MapProblem::DuplicateTile {
+                    layer: layer.id,
+                    pos: tile.pos,
+                });
+            }
+            if tile.collision == TileCollisionKind::Solid {
+                solid_tiles.insert(tile.pos);
+            }
+        }
+        if !layer.tiles.is_empty() && layer.tilemap.option().is_none() {
+            problems.push(MapProblem::MissingTilemap { layer: layer.id });
+        }
+
+        for element in layer.elements.iter() {
+            let element_meta = assets
+                .try_get_untyped(element.element.untyped())
+                .and_then(|x| x.try_cast_ref::<ElementMeta>().ok().cloned());
+            let Some(element_meta) = element_meta else {
+                problems.push(MapProblem::MissingElement {
+                    layer: layer.id,
+                    pos: element.pos,
+                });
+                continue;
+            };
+            let Some(data) = assets.try_get_untyped(element_meta.data.untyped()) else {
+                problems.push(MapProblem::MissingElement {
+                    layer: layer.id,
+                    pos: element.pos,
+                });
+                continue;
+            };
+            if data.try_cast_ref::<PlayerSpawner>().is_ok() {
+                spawners.push(element.pos);
+            } else if data.try_cast_ref::<SproingerMeta>().is_ok() {
+                sproingers.push(element.pos);
+            }
+        }
+    }
+
+    if spawners.len() < MAX_PLAYERS {
+        problems.push(MapProblem::NotEnoughSpawners {
+            count: spawners.len(),
+        });
+    }
+
+    let tile_pos = |pos: Vec2| (pos / meta.tile_size).floor().as_ivec2();
+    let (embedded, reachable): (Vec<_>, Vec<_>) = spawners.into_iter().partition(|&pos| {
+        let tile_pos = tile_pos(pos);
+        tile_pos.cmpge(IVec2::ZERO).all() && solid_tiles.contains(&tile_pos.as_uvec2())
+    });
+    for pos in embedded {
+        problems.push(MapProblem::SpawnerInSolidTile { pos });
+    }
+
+    // Sproinger edges are added to the navigation graph when the sproingers are spawned, so they
+    // have to be added here, too.
+    let nav_graph_settings = NavGraphSettings::for_default_player(assets, &meta.rules);
+    let mut graph = (*create_nav_graph(meta, &nav_graph_settings)).clone();
+    for pos in sproingers {
+        add_sproinger_nav_edge(&mut graph, pos, meta.tile_size);
+    }
+    for &from in &reachable {
+        for &to in &reachable {
+            if from != to
+                && !petgraph::algo::has_path_connecting(
+                    &graph,
+                    NavNode(tile_pos(from)),
+                    NavNode(tile_pos(to)),
+                    None,
+                )
+            {
+                problems.push(MapProblem::UnreachableSpawner { from, to });
+            }
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::*;
+    use crate::headless::load_game;
+
+    #[test]
+    fn broken_tiles_are_reported() {
+        let game = load_game(Path::new("assets"), Path::new("packs")).unwrap();
+        let assets = game.shared_resource::<AssetServer>().unwrap();
+        let core = &assets.root::<GameMeta>().core;
+        let mut map = (*assets.get(core.stable_maps[0])).clone();
+
+        let layer = &mut map.layers[0];
+        let tile = layer.tiles[0].clone();
+        layer.tiles.push(tile.clone());
+        layer.tiles.push(MapTileMeta {
+            pos: map.grid_size,
+            ..tile.clone()
+        });
+        let layer = layer.id;
+
+        let problems = validate_map(&map, &assets);
+        assert!(problems.contains(&MapProblem::DuplicateTile {
+            layer,
+            pos: tile.pos
+        }));
+        assert!(problems.contains(&MapProblem::TileOutOfBounds {
+            layer,
+            pos: map.grid_size,
+            grid_size: map.grid_size
+        }));
+    }
+}
@@ -0,0 +1,264 @@
+//! Round and score tracking.
+//!
+//! A match is played as a series of rounds. Every player spawns once per round, and the round is
+//! over when there is at most one player left alive, who is awarded a point. The game session is
+//! then re-created on the next map of the [`MapPlaylist`], carrying over the [`MatchScore`], until
+//! a player reaches the [`score_to_win`][CoreConfigMeta::score_to_win].
+//!
+//! Matches with a single player, like when testing a map in the editor, don't have rounds and the
+//! player re-spawns when they die.
+
+use crate::prelude::*;
+
+/// Install this module.
+pub fn install(session: &mut Session) {
+    session.world.init_resource::<MatchScore>();
+    session.world.init_resource::<RoundState>();
+    session.world.init_resource::<MapPlaylist>();
+    session
+        .stages
+        .add_system_to_stage(CoreStage::Last, update_round_state);
+}
+
+/// Resource containing the score of the match, which is carried over from round to round.
+#[derive(HasSchema, Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct MatchScore {
+    /// The index of the current round, starting from zero.
+    pub round: u32,
+    /// The number of rounds that each player has won.
+    pub scores: [u32; MAX_PLAYERS],
+}
+
+impl MatchScore {
+    /// Get the player that has won the match, if any player has reached the score to win.
+    pub fn winner(&self, score_to_win: u32) -> Option<u32> {
+        self.scores
+            .iter()
+            .position(|&score| score_to_win > 0 && score >= score_to_win)
+            .map(|i| i as u32)
+    }
+}
+
+/// Resource containing the maps that the rounds of the match are played on, which is carried over
+/// from round to round.
+///
+/// Every round, and every time the match is restarted, the match moves on to the next map of the
+/// playlist. If the playlist is empty, the rounds are played on the
+/// [`stable_maps`][CoreMeta::stable_maps] in order instead, and restarting keeps the current map.
+#[derive(HasSchema, Clone, Debug, Default)]
+pub struct MapPlaylist {
+    /// The name of the playlist, if it is one of the [`map_playlists`][CoreMeta::map_playlists].
+    pub name: Option<Ustr>,
+    pub maps: Vec<Handle<MapMeta>>,
+    /// Whether the maps are played in a random order, which is different for every pass through
+    /// the playlist.
+    pub shuffle: bool,
+    /// The seed used to shuffle the maps.
+    pub seed: u64,
+    /// The number of maps that have been played before the current map.
+    pub position: u32,
+}
+
+impl MapPlaylist {
+    pub fn new(maps: Vec<Handle<MapMeta>>, shuffle: bool, seed: u64) -> Self {
+        Self {
+            name: None,
+            maps,
+            shuffle,
+            seed,
+            position: 0,
+        }
+    }
+
+    /// Create a playlist from one of the [`map_playlists`][CoreMeta::map_playlists].
+    pub fn from_meta(meta: &MapPlaylistMeta, seed: u64) -> Self {
+        Self {
+            name: Some(meta.name),
+            ..Self::new(meta.maps.iter().copied().collect(), meta.shuffle, seed)
+        }
+    }
+
+    /// Create a shuffled playlist of the stable maps, and optionally the experimental maps.
+    pub fn random_maps(core: &CoreMeta, include_experimental: bool, seed: u64) -> Self {
+        let experimental_maps = if include_experimental {
+            &core.experimental_maps[..]
+        } else {
+            &[]
+        };
+        let maps = core.stable_maps.iter().chain(experimental_maps).copied();
+        Self::new(maps.collect(), true, seed)
+    }
+
+    /// Get the current map.
+    pub fn current(&self) -> Option<Handle<MapMeta>> {
+        self.map_at(self.position)
+    }
+
+    /// Get the map after the current map.
+    pub fn next(&self) -> Option<Handle<MapMeta>> {
+        self.map_at(self.position + 1)
+    }
+
+    /// Move on to the next map.
+    pub fn advance(&mut self) {
+        if !self.maps.is_empty() {
+            self.position += 1;
+        }
+    }
+
+    fn map_at(&self, position: u32) -> Option<Handle<MapMeta>> {
+        if self.maps.is_empty() {
+            return None;
+        }
+        let len = self.maps.len() as u32;
+        let idx = (position % len) as usize;
+        if !self.shuffle {
+            return Some(self.maps[idx]);
+        }
+
+        let rng = Rng::with_seed(self.seed.wrapping_add((position / len) as u64));
+        let mut order = (0..self.maps.len()).collect::<Vec<_>>();
+        rng.shuffle(&mut order);
+        Some(self.maps[order[idx]])
+    }
+}
+
+/// Resource containing the state of the current round.
+#[derive(HasSchema, Clone, Debug, Default)]
+pub enum RoundState {
+    /// The round is being played.
+    #[default]
+    Playing,
+    /// The round is over and the results are being shown.
+    RoundOver {
+        /// The player that won the round, or [`None`] if nobody survived.
+        winner: Option<u32>,
+        /// The time left before the next round starts.
+        timer: Timer,
+    },
+    /// The next round should be started, by re-creating the game session with
+    /// [`SessionExt::start_next_round`][crate::sessions::SessionExt::start_next_round].
+    NextRound {
+        /// The map to play the next round on.
+        map: Arc<MapMeta>,
+    },
+    /// A player has reached the score to win and the match is over.
+    MatchOver {
+        /// The player that won the match.
+        winner: u32,
+    },
+}
+
+impl RoundState {
+    /// Whether or not the round is still being played.
+    pub fn is_playing(&self) -> bool {
+        matches!(self, RoundState::Playing)
+    }
+
+    /// Whether the round has ended and the session should move on to the next round or the match
+    /// results, so that no more frames have to be simulated.
+    pub fn has_ended(&self) -> bool {
+        matches!(
+            self,
+            RoundState::NextRound { .. } | RoundState::MatchOver { .. }
+        )
+    }
+}
+
+/// Get whether the active players in the match play in rounds, instead of re-spawning.
+pub fn has_rounds(player_inputs: &MatchInputs) -> bool {
+    player_inputs.players.iter().filter(|x| x.active).count() > 1
+}
+
+/// Get the map that the next round is played on: the next map of the [`MapPlaylist`], or else the
+/// stable map after the current one.
+fn next_round_map(
+    core: &CoreMeta,
+    assets: &AssetServer,
+    playlist: &MapPlaylist,
+    map: &LoadedMap,
+    score: &MatchScore,
+) -> MapMeta {
+    if let Some(handle) = playlist.next() {
+        return (*assets.get(handle)).clone();
+    }
+    let stable_maps = &core.stable_maps;
+    if stable_maps.is_empty() {
+        return (*map.0).clone();
+    }
+    let current = stable_maps
+        .iter()
+        .position(|&handle| assets.get(handle).name == map.name);
+    let next = current.map_or(score.round as usize, |i| i + 1) % stable_maps.len();
+    (*assets.get(stable_maps[next])).clone()
+}
+
+fn update_round_state(
+    meta: Root<GameMeta>,
+    assets: Res<AssetServer>,
+    map: Res<LoadedMap>,
+    playlist: Res<MapPlaylist>,
+    replay_rounds: ResInit<ReplayRounds>,
+    entities: Res<Entities>,
+    player_indexes: Comp<PlayerIdx>,
+    killed_players: Comp<PlayerKilled>,
+    player_inputs: Res<MatchInputs>,
+    players_have_spawned: ResInit<PlayersHaveSpawned>,
+    time: Res<Time>,
+    mut score: ResMut<MatchScore>,
+    mut round_state: ResMut<RoundState>,
+) {
+    match &mut *round_state {
+        RoundState::Playing => {
+            if !has_rounds(&player_inputs) {
+                return;
+            }
+
+            // Wait for every player to spawn before checking who is still alive.
+            let all_spawned = player_inputs
+                .players
+                .iter()
+                .zip(players_have_spawned.players)
+                .all(|(player, spawned)| !player.active || spawned);
+            if !all_spawned {
+                return;
+            }
+
+            let alive_players = entities
+                .iter_with(&player_indexes)
+                .filter(|(ent, _)| !killed_players.contains(*ent))
+                .map(|(_, idx)| idx.0)
+                .collect::<Vec<_>>();
+            if alive_players.len() > 1 {
+                return;
+            }
+
+            let winner = alive_players.first().copied();
+            if let Some(winner) = winner {
+                score.scores[winner as usize] += 1;
+            }
+
+            *round_state = RoundState::RoundOver {
+                winner,
+                timer: Timer::new(meta.core.config.round_end_delay, TimerMode::Once),
+            };
+        }
+        RoundState::RoundOver { timer, .. } => {
+            timer.tick(time.delta());
+            if timer.finished() {
+                *round_state = match score.winner(meta.core.config.score_to_win) {
+                    Some(winner) => RoundState::MatchOver { winner },
+                    None => {
+                        // Replays are played back on the maps that they were recorded on.
+                        let map = match replay_rounds.get(score.round + 1) {
+                            Some(round) => round.map.clone(),
+                            None => next_round_map(&meta.core, &assets, &playlist, &map, &score),
+                        };
+                        RoundState::NextRound { map: Arc::new(map) }
+                    }
+                };
+            }
+        }
+        RoundState::NextRound { .. } | RoundState::MatchOver { .. } => (),
+    }
+}
@@ -41,6 +41,7 @@ pub struct CoreMeta {
     pub stable_maps: SVec<Handle<MapMeta>>,
     pub map_elements: SVec<Handle<ElementMeta>>,
     pub experimental_maps: SVec<Handle<MapMeta>>,
+    pub map_playlists: SVec<MapPlaylistMeta>,
 }
 
 #[derive(HasSchema, Clone, Debug)]
@@ -90,4 +91,9 @@ pub struct CoreConfigMeta {
     #[serde(default)]
     #[serde(with = "humantime_serde")]
     pub respawn_invincibility_time: Duration,
+    /// The number of rounds that a player needs to win to win the match.
+    pub score_to_win: u32,
+    /// The time that the round results are shown before the next round starts.
+    #[serde(with = "humantime_serde")]
+    pub round_end_delay: Duration,
 }
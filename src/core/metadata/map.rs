@@ -15,6 +15,78 @@ pub struct MapMeta {
     pub tile_size: Vec2,
     /// The layers of the map
     pub layers: SVec<MapLayerMeta>,
+    /// Overrides of the core rules on this map
+    pub rules: MapRulesMeta,
+}
+
+/// Overrides of the core rules for a map, like its physics. Rules that aren't set use the
+/// [`CoreMeta`].
+#[derive(HasSchema, Clone, Debug, Default)]
+#[repr(C)]
+pub struct MapRulesMeta {
+    /// Multiplier for the gravity of every physics body, like players and items.
+    pub gravity_scale: Maybe<f32>,
+    /// Overrides [`PhysicsMeta::terminal_velocity`].
+    pub terminal_velocity: Maybe<f32>,
+    /// Overrides [`PhysicsMeta::friction_lerp`].
+    pub friction_lerp: Maybe<f32>,
+    /// The distance that things can go past the left, right and bottom edges of the map before
+    /// they are out of bounds.
+    pub kill_zone_border: Maybe<f32>,
+    /// Whether things that leave the left or right edge of the map come back in on the other side.
+    pub wrap_x: bool,
+    /// Whether things that leave the bottom or top edge of the map come back in on the other side.
+    pub wrap_y: bool,
+    /// Overrides [`CoreConfigMeta::respawn_invincibility_time`].
+    pub respawn_invincibility_time: Maybe<Duration>,
+    /// Items that are removed from the map.
+    pub banned_items: SVec<Handle<ElementMeta>>,
+    /// Items that the other items of the same category on the map may be replaced with.
+    pub boosted_items: SVec<Handle<ElementMeta>>,
+    /// The chance that an item is replaced with one of the [`boosted_items`][Self::boosted_items].
+    pub boost_chance: Maybe<f32>,
+}
+
+impl MapRulesMeta {
+    /// The distance past the edges of the map that things are out of bounds at, by default.
+    pub const DEFAULT_KILL_ZONE_BORDER: f32 = 500.0;
+    /// The chance that an item is replaced with a boosted item, by default.
+    pub const DEFAULT_BOOST_CHANCE: f32 = 0.5;
+
+    /// Get the core physics with the overrides of the map applied.
+    pub fn physics(&self, physics: &PhysicsMeta) -> PhysicsMeta {
+        PhysicsMeta {
+            gravity: physics.gravity * self.gravity_scale(),
+            terminal_velocity: self
+                .terminal_velocity
+                .option()
+                .unwrap_or(physics.terminal_velocity),
+            friction_lerp: self.friction_lerp.option().unwrap_or(physics.friction_lerp),
+            stop_threshold: physics.stop_threshold,
+        }
+    }
+
+    pub fn gravity_scale(&self) -> f32 {
+        self.gravity_scale.option().unwrap_or(1.0)
+    }
+
+    pub fn kill_zone_border(&self) -> f32 {
+        self.kill_zone_border
+            .option()
+            .unwrap_or(Self::DEFAULT_KILL_ZONE_BORDER)
+    }
+
+    pub fn respawn_invincibility_time(&self, config: &CoreConfigMeta) -> Duration {
+        self.respawn_invincibility_time
+            .option()
+            .unwrap_or(config.respawn_invincibility_time)
+    }
+
+    pub fn boost_chance(&self) -> f32 {
+        self.boost_chance
+            .option()
+            .unwrap_or(Self::DEFAULT_BOOST_CHANCE)
+    }
 }
 
 #[derive(HasSchema, Clone, Debug, Default)]
@@ -58,14 +130,37 @@ pub struct MapTileMeta {
     pub collision: TileCollisionKind,
 }
 
+/// A named list of maps, which the rounds of a match are played on.
+#[derive(HasSchema, Clone, Debug, Default)]
+#[repr(C)]
+pub struct MapPlaylistMeta {
+    pub name: Ustr,
+    pub maps: SVec<Handle<MapMeta>>,
+    /// Whether the maps are played in a random order, instead of the order that they are listed in.
+    pub shuffle: bool,
+}
+
 impl MapMeta {
     /// Checks if the given position is out of the bounds of the map.
     pub fn is_out_of_bounds(&self, pos: &Vec3) -> bool {
-        const KILL_ZONE_BORDER: f32 = 500.0;
-        let map_width = self.grid_size.x as f32 * self.tile_size.x;
-        let left_kill_zone = -KILL_ZONE_BORDER;
-        let right_kill_zone = map_width + KILL_ZONE_BORDER;
-        let bottom_kill_zone = -KILL_ZONE_BORDER;
-        pos.x < left_kill_zone || pos.x > right_kill_zone || pos.y < bottom_kill_zone
+        is_out_of_map_bounds(self.grid_size, self.tile_size, &self.rules, pos)
     }
 }
+
+/// Checks if the given position is out of the bounds of a map with the given size and rules.
+///
+/// Things can't go out of bounds past the edges that the map wraps around.
+pub fn is_out_of_map_bounds(
+    grid_size: UVec2,
+    tile_size: Vec2,
+    rules: &MapRulesMeta,
+    pos: &Vec3,
+) -> bool {
+    let kill_zone_border = rules.kill_zone_border();
+    let map_width = grid_size.x as f32 * tile_size.x;
+    let left_kill_zone = -kill_zone_border;
+    let right_kill_zone = map_width + kill_zone_border;
+    let bottom_kill_zone = -kill_zone_border;
+    (!rules.wrap_x && (pos.x < left_kill_zone || pos.x > right_kill_zone))
+        || (!rules.wrap_y && pos.y < bottom_kill_zone)
+}
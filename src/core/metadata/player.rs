@@ -67,7 +67,7 @@ pub struct PlayerLayerMeta {
     pub animations: SMap<Ustr, AnimatedSprite>,
 }
 
-#[derive(HasSchema, Deserialize, Clone, Debug, Default)]
+#[derive(HasSchema, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
 #[repr(C)]
 pub struct PlayerStatsMeta {
     pub jump_speed: f32,
@@ -144,9 +144,13 @@ fn update_kinematic_bodies(
     mut collision_world: CollisionWorld,
     mut transforms: CompMut<Transform>,
     time: Res<Time>,
+    map: Res<SpawnedMapMeta>,
 ) {
     puffin::profile_function!();
 
+    let physics = map.rules.physics(&meta.core.physics);
+    let gravity_scale = map.rules.gravity_scale();
+
     // This value was previously using dt / (1 / crate::FPS), this was changed
     // to no longer have FPS impact velocity, the dt * 60 is a holdover to avoid having
     // to update all velocities.
@@ -307,27 +311,27 @@ fn update_kinematic_bodies(
                 body.velocity.x *= if let Some(friction) = body.frame_friction_override {
                     friction
                 } else {
-                    meta.core.physics.friction_lerp
+                    physics.friction_lerp
                 };
                 body.frame_friction_override = None;
 
-                if body.velocity.x.abs() <= meta.core.physics.stop_threshold {
+                if body.velocity.x.abs() <= physics.stop_threshold {
                     body.velocity.x = 0.0;
                 }
 
-                body.velocity.y *= meta.core.physics.friction_lerp;
+                body.velocity.y *= physics.friction_lerp;
             }
 
-            if body.velocity.y <= meta.core.physics.gravity {
+            if body.velocity.y <= physics.gravity {
                 body.velocity.y = 0.0;
             }
         }
 
         if !body.is_on_ground && body.has_mass {
-            body.velocity.y -= body.gravity * time_factor;
+            body.velocity.y -= body.gravity * gravity_scale * time_factor;
 
-            if body.velocity.y < -meta.core.physics.terminal_velocity {
-                body.velocity.y = -meta.core.physics.terminal_velocity;
+            if body.velocity.y < -physics.terminal_velocity {
+                body.velocity.y = -physics.terminal_velocity;
             }
         }
 
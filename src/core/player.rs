@@ -1,19 +1,9 @@
 //! Player controller, states, and animation implementation.
 
-use std::collections::VecDeque;
-
 use crate::prelude::*;
 
 mod state;
 pub use state::*;
-use turborand::GenCore;
-
-const PLAYER_COLORS: [Color; 4] = [
-    Color::RED,
-    Color::GREEN,
-    Color::BLUE,
-    Color::rgb(1.0, 0.0, 1.0),
-];
 
 pub fn plugin(session: &mut Session) {
     session.install_plugin(state::plugin);
@@ -22,11 +12,9 @@ pub fn plugin(session: &mut Session) {
     session
         .stages
         .add_system_to_stage(CoreStage::First, hydrate_players)
-        .add_system_to_stage(CoreStage::First, player_ai_system)
         .add_system_to_stage(CoreStage::PostUpdate, play_itemless_fin_animations)
         .add_system_to_stage(CoreStage::PostUpdate, player_facial_animations)
         .add_system_to_stage(CoreStage::PostUpdate, equip_hats)
-        .add_system_to_stage(CoreStage::Last, delete_dead_ai_swords)
         .add_system_to_stage(CoreStage::Last, update_player_layers);
 }
 
@@ -128,6 +116,56 @@ impl Emote {
 #[derive(Clone, HasSchema, Default)]
 pub struct PlayerKilled {
     pub hit_from: Option<Vec2>,
+    /// The player that is responsible for the kill, if any.
+    pub killer: Option<Entity>,
+    /// What killed the player.
+    pub cause: DeathCause,
+}
+
+/// The way that a player was killed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DeathCause {
+    /// The cause of death isn't known.
+    #[default]
+    Unknown,
+    /// Shot by a bullet.
+    Bullet,
+    /// Hit by a sword.
+    Sword,
+    /// Caught in an explosion, like the one of a grenade, mine, or kick bomb.
+    Explosion,
+    /// Stomped on by a player wearing stomp boots.
+    Stomp,
+    /// Impaled by spikes.
+    Spikes,
+    /// Hit by a thrown crate.
+    Crate,
+    /// Fell out of the map.
+    OutOfBounds,
+}
+
+/// A player kill, as recorded in the [`KillEvents`].
+#[derive(Clone, Copy, Debug)]
+pub struct KillEvent {
+    /// The time since the start of the round that the kill happened at.
+    pub time: Duration,
+    /// The index of the player that was killed.
+    pub victim: u32,
+    /// The index of the player that is responsible for the kill, if any.
+    ///
+    /// This is the same as the `victim` for suicides.
+    pub killer: Option<u32>,
+    /// What killed the player.
+    pub cause: DeathCause,
+}
+
+/// Resource containing every kill of the current round, in the order that they happened.
+///
+/// This can be read by anything that wants to know about kills, such as a kill feed or AI training
+/// telemetry.
+#[derive(HasSchema, Clone, Debug, Default)]
+pub struct KillEvents {
+    pub events: Vec<KillEvent>,
 }
 
 /// Events that can be used to trigger player actions, such as killing, setting inventory, etc.
@@ -137,12 +175,23 @@ pub struct PlayerCommand;
 impl PlayerCommand {
     /// Kill a player.
     ///
+    /// The `killer` is the player responsible for the kill, which is the player themselves for
+    /// suicides. The kill is recorded in the [`KillEvents`].
+    ///
     /// > **Note:** This doesn't despawn the player, it just puts the player into it's death animation.
-    pub fn kill(player: Entity, hit_from: Option<Vec2>) -> StaticSystem<(), ()> {
+    pub fn kill(
+        player: Entity,
+        hit_from: Option<Vec2>,
+        killer: Option<Entity>,
+        cause: DeathCause,
+    ) -> StaticSystem<(), ()> {
         (move |entities: Res<Entities>,
+               time: Res<Time>,
                mut players_killed: CompMut<PlayerKilled>,
                mut items_dropped: CompMut<ItemDropped>,
                mut inventories: CompMut<Inventory>,
+               mut stats: ResMutInit<MatchStats>,
+               mut kill_events: ResMutInit<KillEvents>,
                player_indexes: Comp<PlayerIdx>| {
             if players_killed.contains(player) {
                 // No need to kill him again
@@ -168,7 +217,23 @@ impl PlayerCommand {
             // Update the inventory
             inventories.insert(player, Inventory(None));
 
-            players_killed.insert(player, PlayerKilled { hit_from });
+            let killer_idx = killer.and_then(|x| player_indexes.get(x)).map(|x| x.0);
+            stats.record_kill(idx.0, killer_idx);
+            kill_events.events.push(KillEvent {
+                time: time.elapsed(),
+                victim: idx.0,
+                killer: killer_idx,
+                cause,
+            });
+
+            players_killed.insert(
+                player,
+                PlayerKilled {
+                    hit_from,
+                    killer,
+                    cause,
+                },
+            );
         })
         .system()
     }
@@ -217,7 +282,12 @@ impl PlayerCommand {
     pub fn set_inventory(player: Entity, item: Option<Entity>) -> StaticSystem<(), ()> {
         (move |mut items_grabbed: CompMut<ItemGrabbed>,
                mut items_dropped: CompMut<ItemDropped>,
-               mut inventories: CompMut<Inventory>| {
+               mut inventories: CompMut<Inventory>,
+               mut stats: ResMutInit<MatchStats>,
+               mut pickup_events: ResMutInit<ItemPickupEvents>,
+               time: Res<Time>,
+               element_handles: Comp<ElementHandle>,
+               player_indexes: Comp<PlayerIdx>| {
             let inventory = inventories.get(player).cloned().unwrap_or_default();
 
             // If there was a previous item, drop it
@@ -228,6 +298,14 @@ impl PlayerCommand {
             // If there is a new item, grab it
             if let Some(item) = item {
                 items_grabbed.insert(item, ItemGrabbed { player });
+                if let Some(idx) = player_indexes.get(player) {
+                    stats.record_item_pickup(idx.0);
+                    pickup_events.events.push(ItemPickupEvent {
+                        time: time.elapsed(),
+                        player: idx.0,
+                        element: element_handles.get(item).map(|x| x.0),
+                    });
+                }
             }
 
             // Update the inventory
@@ -248,225 +326,12 @@ impl PlayerCommand {
     }
 }
 
-#[derive(Clone, Debug, HasSchema)]
-pub struct AiPlayer {
-    /// Tick timer that is used for AI pausing logic.
-    tick: Timer,
-    /// Indicates the player is taking pause for the given number of ticks.
-    pausing: u32,
-    /// Buffers planned AI movements
-    movement_buffer: Option<VecDeque<PlayerControl>>,
-    /// The player that the AI is targeting.
-    target_player: Option<Entity>,
-}
-
-impl Default for AiPlayer {
-    fn default() -> Self {
-        Self {
-            tick: Timer::from_seconds(0.5, TimerMode::Repeating),
-            pausing: 0,
-            movement_buffer: Default::default(),
-            target_player: Default::default(),
-        }
-    }
-}
-
-#[derive(Debug, HasSchema, Clone)]
-#[schema(no_default)]
-pub struct PathfindingDebugLines {
-    pub entities: Vec<Entity>,
-}
-
-impl FromWorld for PathfindingDebugLines {
-    fn from_world(world: &mut World) -> Self {
-        let entities = world.run_initialized_system(
-            |mut entities: ResMutInit<Entities>, mut transforms: CompMut<Transform>| {
-                (0..MAX_PLAYERS)
-                    .map(|_| {
-                        let ent = entities.create();
-
-                        transforms
-                            .insert(ent, Transform::from_translation(Vec3::new(0.0, 0.0, -1.0)));
-
-                        ent
-                    })
-                    .collect::<Vec<_>>()
-            },
-            (),
-        );
-
-        Self { entities }
-    }
-}
-
-fn player_ai_system(
-    entities: Res<Entities>,
-    nav_graph: ResMutInit<NavGraph>,
-    mut player_inputs: ResMutInit<MatchInputs>,
-    mut ai_players: CompMut<AiPlayer>,
-    player_indexes: Comp<PlayerIdx>,
-    map: Res<LoadedMap>,
-    transforms: Comp<Transform>,
-    pathfinding_debug_line: ResMutInit<PathfindingDebugLines>,
-    mut paths: CompMut<Path2d>,
-    bodies: Comp<KinematicBody>,
-    debug_settings: ResInit<DebugSettings>,
-    rng: Res<GlobalRng>,
-    time: Res<Time>,
-) {
-    const SWORD_SWING_DIST: f32 = 10.0;
-    const AI_SPEED_MULTIPLIER: f32 = 0.65;
-
-    for (ai_ent, (player_idx, transform, ai_player)) in
-        entities.iter_with((&player_indexes, &transforms, &mut ai_players))
-    {
-        // Tick the AI timer
-        ai_player.tick.tick(time.delta());
-
-        // If a tick has elapsed
-        if ai_player.tick.just_finished() {
-            // If the player isn't pausing, then there's a 40% chance
-            if ai_player.pausing == 0 && rng.chance(0.4) {
-                // That we will pause for a random number of ticks between 0 and 2
-                ai_player.pausing = (rng.f32_normalized() * 2.0).round() as u32
-            }
-
-            // If the player is pausing
-            if ai_player.pausing > 0 {
-                // Subtract a tick from how long they should pause.
-                ai_player.pausing -= 1;
-            }
-        }
-
-        // If the player is pausing, don't have the AI move this frame.
-        if ai_player.pausing > 0 {
-            continue;
-        }
-
-        let target_transform = match ai_player.target_player {
-            Some(target_player) if transforms.contains(target_player) => {
-                transforms.get(target_player).unwrap()
-            }
-            _ => {
-                let players = entities
-                    .iter_with((&player_indexes, &transforms))
-                    .filter(|(ent, _)| *ent != ai_ent)
-                    .collect::<Vec<_>>();
-                if players.is_empty() {
-                    continue;
-                }
-
-                let (target_player, (_, transform)) = players[rng.gen_usize() % players.len()];
-
-                ai_player.target_player = Some(target_player);
-                transform
-            }
-        };
-        let target_pos = target_transform.translation.truncate();
-        let tile = (target_pos / map.tile_size).floor().as_ivec2();
-        let target_node = NavNode(tile);
-        let ai_pos = transform.translation.truncate();
-        let tile = (ai_pos / map.tile_size).floor().as_ivec2();
-        let current_node = NavNode(tile);
-
-        // Complete any previous movement instructions if we are in the middle of any
-        if let Some(movement_buffer) = &mut ai_player.movement_buffer {
-            if let Some(control) = movement_buffer.pop_front() {
-                player_inputs.players[player_idx.0 as usize].control = control;
-                if movement_buffer.is_empty() {
-                    ai_player.movement_buffer = None;
-                }
-                continue;
-            }
-        }
-
-        let path = petgraph::algo::astar(
-            nav_graph.as_ref(),
-            current_node,
-            |x| x == target_node,
-            |(_, _, edge)| edge.distance,
-            |_| 0.0,
-        );
-
-        if let Some((_cost, path)) = path {
-            if debug_settings.show_pathfinding_lines {
-                paths.insert(
-                    pathfinding_debug_line.entities[player_idx.0 as usize],
-                    Path2d {
-                        points: path
-                            .iter()
-                            .map(|x| x.0.as_vec2() * map.tile_size + map.tile_size / 2.0)
-                            .collect(),
-                        thickness: 2.0,
-                        color: PLAYER_COLORS[player_idx.0 as usize],
-                        ..default()
-                    },
-                );
-            }
-
-            if let Some(&next_node) = path.get(1) {
-                let edge = nav_graph.edge_weight(current_node, next_node).unwrap();
-                let mut movement_buffer = edge.inputs.clone();
-                let mut first_movement = movement_buffer.pop_front().unwrap();
-
-                // Slow down the AI movement according to the fixed multiplier
-                first_movement.move_direction *= vec2(AI_SPEED_MULTIPLIER, 1.0);
-
-                // This is a hack to prevent us from getting stuck when we think we should be falling
-                // straight down and we actually need to move off of the block we're half-standing on.
-                //
-                // If we aren't moving at all, just move in the direction of player 1
-                if bodies.get(ai_ent).unwrap().velocity == Vec2::ZERO
-                    && first_movement.move_direction == Vec2::ZERO
-                {
-                    let sign = (path.get(2).unwrap_or(&next_node).x as f32 * map.tile_size.x
-                        - transform.translation.x)
-                        .signum();
-                    first_movement.move_direction.x = sign;
-                }
-
-                player_inputs.players[player_idx.0 as usize].control = first_movement;
-                if !movement_buffer.is_empty() {
-                    ai_player.movement_buffer = Some(movement_buffer)
-                }
-            }
-
-            if (target_pos - ai_pos).length() < SWORD_SWING_DIST {
-                player_inputs.players[player_idx.0 as usize]
-                    .control
-                    .shoot_just_pressed = true;
-                player_inputs.players[player_idx.0 as usize]
-                    .control
-                    .shoot_pressed = true;
-            }
-        } else if debug_settings.show_pathfinding_lines {
-            let pos =
-                current_node.0.as_vec2() * map.tile_size + map.tile_size / 2.0 - vec2(0.0, 4.0);
-            paths.insert(
-                pathfinding_debug_line.entities[player_idx.0 as usize],
-                Path2d {
-                    points: vec![pos, pos + vec2(0.0, 4.0)],
-                    thickness: 8.0,
-                    color: Color::RED,
-                    ..default()
-                },
-            );
-        }
-
-        if !debug_settings.show_pathfinding_lines {
-            paths.remove(pathfinding_debug_line.entities[player_idx.0 as usize]);
-        }
-    }
-}
-
-/// Resource that tracks which players have already been spawned before.
-///
-/// This lets us handle re-spawns differently, like not spawning you with a hat on a re-spawn.
+/// Resource that tracks which players have already been spawned in this round.
 ///
-/// TODO: This is probably temporary and will be removed when we have the proper tournament flow
-/// down.
+/// This lets us handle re-spawns differently, like not spawning you with a hat on a re-spawn, and
+/// makes sure that players only spawn once per round.
 #[derive(Debug, Clone, HasSchema, Default)]
-struct PlayersHaveSpawned {
+pub struct PlayersHaveSpawned {
     /// For each player, whether they have spawned before.
     pub players: [bool; MAX_PLAYERS],
 }
@@ -476,7 +341,6 @@ struct PlayersHaveSpawned {
 struct Hat(Handle<HatMeta>);
 
 fn hydrate_players(
-    mut commands: Commands,
     mut entities: ResMutInit<Entities>,
     game_meta: Root<GameMeta>,
     player_inputs: Res<MatchInputs>,
@@ -499,6 +363,7 @@ fn hydrate_players(
     mut item_throws: CompMut<ItemThrow>,
     mut items: CompMut<Item>,
     mut hats: CompMut<Hat>,
+    map: Res<SpawnedMapMeta>,
 ) {
     let mut not_hydrated_bitset = player_states.bitset().clone();
     not_hydrated_bitset.bit_not();
@@ -518,9 +383,9 @@ fn hydrate_players(
         let player_has_spawned = &mut players_have_spawned.players[player_idx.0 as usize];
         let player_handle = player_inputs.players[player_idx.0 as usize].selected_player;
         let player_hat = &player_inputs.players[player_idx.0 as usize].selected_hat;
-        let is_ai = player_inputs.players[player_idx.0 as usize]
-            .control_source
-            .is_none();
+        let player_input = &player_inputs.players[player_idx.0 as usize];
+        let is_ai = player_input.control_source.is_none();
+        let ai_difficulty = player_input.ai_difficulty;
 
         let meta = assets.get(player_handle);
 
@@ -536,7 +401,7 @@ fn hydrate_players(
         inventories.insert(player_entity, default());
         invincibles.insert(
             player_entity,
-            Invincibility::new(game_meta.core.config.respawn_invincibility_time),
+            Invincibility::new(map.rules.respawn_invincibility_time(&game_meta.core.config)),
         );
         element_kill_callbacks.insert(
             player_entity,
@@ -694,78 +559,7 @@ fn hydrate_players(
 
         // Handle AI players
         if is_ai {
-            ai_players.insert(player_entity, default());
-
-            // Give the player a sword NOTE: It's not good that we're duplicating the sword hydrate
-            // functionality here, and this is pretty hacky, but the AI as it stands is temporary
-            // anyway, so it's fine for now.
-            commands.add(
-                move |mut entities: ResMutInit<Entities>,
-                      mut swords: CompMut<sword::Sword>,
-                      mut element_handles: CompMut<ElementHandle>,
-                      assets: Res<AssetServer>,
-                      mut hydrated: CompMut<MapElementHydrated>,
-                      mut bodies: CompMut<KinematicBody>,
-                      mut atlas_sprites: CompMut<AtlasSprite>,
-                      mut items: CompMut<Item>,
-                      mut transforms: CompMut<Transform>,
-                      game_meta: Root<GameMeta>,
-                      mut attachments: CompMut<PlayerBodyAttachment>,
-                      mut inventories: CompMut<Inventory>| {
-                    let element_handle = game_meta
-                        .core
-                        .map_elements
-                        .iter()
-                        .find(|handle| {
-                            assets
-                                .get(assets.get(**handle).data)
-                                .try_cast_ref::<SwordMeta>()
-                                .is_ok()
-                        })
-                        .unwrap();
-                    let element_meta = assets.get(*element_handle);
-                    if let Ok(SwordMeta {
-                        atlas,
-                        body_size,
-                        can_rotate,
-                        bounciness,
-                        grab_offset,
-                        ..
-                    }) = assets.get(element_meta.data).try_cast_ref()
-                    {
-                        let sword_ent = entities.create();
-                        inventories.insert(player_entity, Inventory(Some(sword_ent)));
-                        items.insert(sword_ent, Item);
-                        swords.insert(sword_ent, sword::Sword::default());
-                        atlas_sprites.insert(sword_ent, AtlasSprite::new(*atlas));
-                        transforms.insert(sword_ent, default());
-                        element_handles.insert(sword_ent, ElementHandle(*element_handle));
-                        hydrated.insert(sword_ent, MapElementHydrated);
-                        bodies.insert(
-                            sword_ent,
-                            KinematicBody {
-                                shape: ColliderShape::Rectangle { size: *body_size },
-                                has_mass: true,
-                                has_friction: true,
-                                can_rotate: *can_rotate,
-                                bounciness: *bounciness,
-                                gravity: game_meta.core.physics.gravity,
-                                ..default()
-                            },
-                        );
-                        attachments.insert(
-                            sword_ent,
-                            PlayerBodyAttachment {
-                                sync_color: false,
-                                sync_animation: false,
-                                player: player_entity,
-                                head: false,
-                                offset: grab_offset.extend(1.0),
-                            },
-                        );
-                    }
-                },
-            );
+            ai_players.insert(player_entity, AiPlayer::new(ai_difficulty));
         }
     }
 
@@ -795,24 +589,6 @@ fn player_kill_callback(player_entity: Entity) -> StaticSystem<(), ()> {
     .system()
 }
 
-/// System that makes sure the swords held by AI are despawned when they are killed.
-fn delete_dead_ai_swords(
-    mut entities: ResMutInit<Entities>,
-    ai_players: Comp<AiPlayer>,
-    items: Comp<Item>,
-    dropped: Comp<ItemDropped>,
-) {
-    let mut to_kill = Vec::new();
-    for (ent, (_item, dropped)) in entities.iter_with((&items, &dropped)) {
-        if ai_players.contains(dropped.player) {
-            to_kill.push(ent);
-        }
-    }
-    for entity in to_kill {
-        entities.kill(entity);
-    }
-}
-
 /// Animate the player's fins while
 fn play_itemless_fin_animations(
     entities: Res<Entities>,
@@ -6,7 +6,10 @@ use bones_framework::prelude::HasSchema;
 pub use turborand::prelude::*;
 
 pub fn plugin(session: &mut Session) {
-    session.world.init_resource::<GlobalRng>();
+    let seed = session.world.get_resource::<RngSeed>().map(|x| x.0);
+    let seed = seed.unwrap_or(RngSeed::default().0);
+    session.world.insert_resource(RngSeed(seed));
+    session.world.insert_resource(GlobalRng::with_seed(seed));
 }
 
 /// Resource that can produce deterministic, pseudo-random numbers.
@@ -17,6 +20,26 @@ pub struct GlobalRng(AtomicRng);
 
 impl Default for GlobalRng {
     fn default() -> Self {
-        Self(AtomicRng::with_seed(7))
+        Self::with_seed(RngSeed::default().0)
+    }
+}
+
+impl GlobalRng {
+    /// Create a new random number generator with the given seed.
+    pub fn with_seed(seed: u64) -> Self {
+        Self(AtomicRng::with_seed(seed))
+    }
+}
+
+/// Resource containing the seed that the [`GlobalRng`] of the match was created with.
+///
+/// Insert this into the session world before installing the
+/// [`MatchPlugin`][crate::core::MatchPlugin] to start the match with a different seed.
+#[derive(Clone, Copy, Debug, HasSchema, Deref, DerefMut)]
+pub struct RngSeed(pub u64);
+
+impl Default for RngSeed {
+    fn default() -> Self {
+        Self(7)
     }
 }
@@ -0,0 +1,937 @@
+//! Deterministic match input recording and replay.
+//!
+//! Every match records the [`MatchInputs`] of each simulation frame into the [`ReplayRecorder`]
+//! resource, which is carried over from round to round. The recording can be saved to a
+//! [`ReplayFile`], which also stores the map of every round, the [`GlobalRng`] seed, the starting
+//! [`MatchScore`] and the [`RoundMapConstructor`], and later be played back frame for frame
+//! through the [`JumpyDefaultMatchRunner`][crate::core::JumpyDefaultMatchRunner], or the
+//! [`HeadlessMatchRunner`][crate::headless::HeadlessMatchRunner], using a [`ReplayPlayback`].
+//!
+//! Asset handles are stored in the file as indexes into the lists in [`CoreMeta`], and the maps
+//! are stored as [`MapFile`]s, so a replay can only be played back with the same asset pack it was
+//! recorded with.
+
+use std::{collections::HashMap, path::Path};
+
+use crate::prelude::*;
+
+/// The version of the replay file format.
+///
+/// This must be incremented whenever the format changes in a way that older replays can't be
+/// read anymore.
+pub const REPLAY_FORMAT_VERSION: u32 = 2;
+
+/// The number of frames between the checksums stored in a replay.
+pub const REPLAY_CHECKSUM_INTERVAL: u32 = FPS as u32;
+
+/// The file extension used for replay files.
+pub const REPLAY_FILE_EXTENSION: &str = "replay.yaml";
+
+/// Install this module.
+pub fn install(session: &mut Session) {
+    // The recorder is carried over from the previous round of the match, if there was one.
+    if session.world.get_resource::<ReplayRecorder>().is_none() {
+        let start_score = *session.world.resource::<MatchScore>();
+        session.world.insert_resource(ReplayRecorder {
+            start_score,
+            ..default()
+        });
+    }
+
+    // Check the playback of a replay against the checksums recorded for this round.
+    let round = session.world.resource::<MatchScore>().round;
+    let checksums = session
+        .world
+        .get_resource::<ReplayRounds>()
+        .and_then(|rounds| rounds.get(round).map(|x| x.checksums.clone()));
+    if let Some(checksums) = checksums {
+        session.world.insert_resource(ReferenceChecksums {
+            checksums,
+            ..default()
+        });
+    }
+
+    session
+        .stages
+        .add_system_to_stage(CoreStage::Last, record_checksum);
+}
+
+/// Record the [`StateChecksum`] every [`REPLAY_CHECKSUM_INTERVAL`] frames, so that playback can
+/// detect when it diverges from the recorded match.
+fn record_checksum(checksum: Res<StateChecksum>, mut recorder: ResMut<ReplayRecorder>) {
+    if let Some(frame) = checksum.frame {
+        if frame % REPLAY_CHECKSUM_INTERVAL == 0 {
+            recorder.checksums.push((frame, checksum.value));
+        }
+    }
+}
+
+/// An error that may occur while saving or loading a replay.
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    #[error("Error reading or writing replay: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Error parsing replay: {0}")]
+    Parse(#[from] serde_yaml::Error),
+    #[error("Unsupported replay format version {0}, expected {REPLAY_FORMAT_VERSION}")]
+    UnsupportedVersion(u32),
+    #[error("Error in a replay map: {0}")]
+    Map(#[from] MapExportError),
+    #[error("The replay doesn't have any rounds")]
+    NoRounds,
+    #[error("The replay references a {0} that is not in the core metadata")]
+    MissingAsset(&'static str),
+}
+
+/// Resource that records the inputs of every frame of the match.
+///
+/// Consecutive frames with identical inputs are merged to keep the recording small.
+#[derive(HasSchema, Clone, Default)]
+pub struct ReplayRecorder {
+    /// The score at the start of the first recorded round.
+    pub start_score: MatchScore,
+    /// The rounds that were played before the current one.
+    pub rounds: Vec<RecordedRound>,
+    /// The recorded frames of the current round.
+    pub frames: Vec<RecordedFrame>,
+    /// The [`StateChecksum`] of every [`REPLAY_CHECKSUM_INTERVAL`]th frame of the current round.
+    pub checksums: Vec<(u32, u64)>,
+}
+
+/// A round that has been recorded by the [`ReplayRecorder`].
+#[derive(Clone, Debug)]
+pub struct RecordedRound {
+    /// The map that the round was played on, before it was changed by the
+    /// [`RoundMapConstructor`].
+    pub map: MapMeta,
+    /// The recorded frames.
+    pub frames: Vec<RecordedFrame>,
+    /// The [`StateChecksum`] of every [`REPLAY_CHECKSUM_INTERVAL`]th frame.
+    pub checksums: Vec<(u32, u64)>,
+}
+
+/// One or more consecutive frames with identical inputs.
+#[derive(Clone, Debug)]
+pub struct RecordedFrame {
+    /// The number of frames that these inputs were held for.
+    pub repeat: u32,
+    /// The control input of each player.
+    pub controls: [PlayerControl; MAX_PLAYERS],
+    /// The editor input of each player.
+    pub editor_inputs: [Option<EditorInput>; MAX_PLAYERS],
+}
+
+impl ReplayRecorder {
+    /// Record the inputs for the next frame.
+    pub fn record(&mut self, inputs: &MatchInputs) {
+        let controls = std::array::from_fn(|i| inputs.players[i].control);
+        let editor_inputs = std::array::from_fn(|i| inputs.players[i].editor_input.clone());
+
+        if let Some(last) = self.frames.last_mut() {
+            if last.controls == controls
+                && last.editor_inputs.iter().all(Option::is_none)
+                && editor_inputs.iter().all(Option::is_none)
+            {
+                last.repeat += 1;
+                return;
+            }
+        }
+
+        self.frames.push(RecordedFrame {
+            repeat: 1,
+            controls,
+            editor_inputs,
+        });
+    }
+
+    /// Finish recording the current round, which was played on the given map, before the
+    /// recorder is carried over to the next round.
+    pub fn finish_round(&mut self, map: MapMeta) {
+        self.rounds.push(RecordedRound {
+            map,
+            frames: std::mem::take(&mut self.frames),
+            checksums: std::mem::take(&mut self.checksums),
+        });
+    }
+
+    /// The total number of frames recorded, in every round.
+    pub fn frame_count(&self) -> u64 {
+        self.rounds
+            .iter()
+            .flat_map(|x| &x.frames)
+            .chain(&self.frames)
+            .map(|x| x.repeat as u64)
+            .sum()
+    }
+}
+
+/// Plays back recorded frames, replacing the live player input.
+#[derive(Clone, Debug, Default)]
+pub struct ReplayPlayback {
+    /// The recorded frames of every round.
+    rounds: Vec<Vec<RecordedFrame>>,
+    /// The round of the match that the first recorded round was.
+    first_round: u32,
+    /// The index of the round that is currently being played.
+    round_idx: usize,
+    /// The index of the recorded frame that is currently being played.
+    frame_idx: usize,
+    /// The number of times the current recorded frame has already been played.
+    repeat_idx: u32,
+}
+
+impl ReplayPlayback {
+    /// Create a playback from the recorded frames of every round, starting with the given round
+    /// of the match.
+    pub fn new(rounds: Vec<Vec<RecordedFrame>>, first_round: u32) -> Self {
+        Self {
+            rounds,
+            first_round,
+            round_idx: 0,
+            frame_idx: 0,
+            repeat_idx: 0,
+        }
+    }
+
+    /// Whether or not every recorded frame of the last round has been played.
+    pub fn is_finished(&self) -> bool {
+        self.round_idx + 1 >= self.rounds.len()
+            && self
+                .rounds
+                .get(self.round_idx)
+                .map_or(true, |x| self.frame_idx >= x.len())
+    }
+
+    /// Write the inputs of the next recorded frame of the given round of the match into the match
+    /// inputs.
+    ///
+    /// Once the round is finished the player controls are reset to their defaults.
+    pub fn apply_next_frame(&mut self, round: u32, inputs: &mut MatchInputs) {
+        let round_idx = round.saturating_sub(self.first_round) as usize;
+        if round_idx != self.round_idx {
+            self.round_idx = round_idx;
+            self.frame_idx = 0;
+            self.repeat_idx = 0;
+        }
+
+        let Some(frame) = self
+            .rounds
+            .get(self.round_idx)
+            .and_then(|x| x.get(self.frame_idx))
+        else {
+            for player in &mut inputs.players {
+                player.control = default();
+                player.editor_input = None;
+            }
+            return;
+        };
+
+        for (i, player) in inputs.players.iter_mut().enumerate() {
+            player.control = frame.controls[i];
+            player.editor_input = frame.editor_inputs[i].clone();
+        }
+
+        self.repeat_idx += 1;
+        if self.repeat_idx >= frame.repeat {
+            self.repeat_idx = 0;
+            self.frame_idx += 1;
+        }
+    }
+}
+
+/// The serialized form of a replay.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReplayFile {
+    /// The replay format version, see [`REPLAY_FORMAT_VERSION`].
+    pub version: u32,
+    /// The version of the game that recorded the replay.
+    pub game_version: String,
+    /// The seed of the [`GlobalRng`].
+    pub seed: u64,
+    /// The score at the start of the first round.
+    #[serde(default)]
+    pub score: MatchScore,
+    /// The name of the [`RoundMapConstructor`] that is run on the map of every round.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub round_map_constructor: Option<String>,
+    /// The players in the match.
+    pub players: [ReplayPlayer; MAX_PLAYERS],
+    /// The recorded rounds, in order.
+    pub rounds: Vec<ReplayRound>,
+}
+
+/// A round in a [`ReplayFile`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReplayRound {
+    /// The map that the round was played on, before it was changed by the
+    /// [`RoundMapConstructor`].
+    pub map: MapFile,
+    /// The recorded frames.
+    pub frames: Vec<ReplayFrame>,
+    /// The [`StateChecksum`] of every [`REPLAY_CHECKSUM_INTERVAL`]th frame, by frame.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checksums: Vec<(u32, u64)>,
+}
+
+/// A player in a [`ReplayFile`].
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ReplayPlayer {
+    pub active: bool,
+    /// Index into [`CoreMeta::players`].
+    pub player: usize,
+    /// Index into [`CoreMeta::player_hats`].
+    pub hat: Option<usize>,
+    /// The control source of the player, or [`None`] if it was an AI.
+    pub control_source: Option<ControlSource>,
+    /// The difficulty of the player if it was an AI.
+    #[serde(default)]
+    pub ai_difficulty: AiDifficulty,
+}
+
+/// One or more frames in a [`ReplayFile`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReplayFrame {
+    #[serde(default = "one", skip_serializing_if = "is_one")]
+    pub repeat: u32,
+    pub controls: [PlayerControl; MAX_PLAYERS],
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub editor_inputs: Vec<(u8, ReplayEditorInput)>,
+}
+
+fn one() -> u32 {
+    1
+}
+fn is_one(n: &u32) -> bool {
+    *n == 1
+}
+
+/// The serialized form of an [`EditorInput`].
+///
+/// Element handles are stored as indexes into [`CoreMeta::map_elements`] and tilemap handles as
+/// indexes into [`CoreMeta::map_tilesets`].
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum ReplayEditorInput {
+    SpawnElement {
+        element: usize,
+        translation: Vec2,
+        layer: u8,
+    },
+    MoveEntity {
+        entity: (u32, u32),
+        pos: Vec2,
+    },
+    DeleteEntity {
+        entity: (u32, u32),
+    },
+    CreateLayer {
+        id: String,
+    },
+    RenameLayer {
+        layer: u8,
+        name: String,
+    },
+    DeleteLayer {
+        layer: u8,
+    },
+    MoveLayer {
+        layer: u8,
+        down: bool,
+    },
+    SetTilemap {
+        layer: u8,
+        tilemap: Option<usize>,
+    },
+    SetTile {
+        layer: u8,
+        pos: UVec2,
+        tilemap_tile_idx: Option<u32>,
+        collision: TileCollisionKind,
+    },
+    RenameMap {
+        name: String,
+    },
+    RandomizeTiles {
+        tile_layers: Vec<LocatedTileLayer>,
+        element_layers: Vec<(u32, Vec<(Vec2, usize)>)>,
+        tile_size: Vec2,
+    },
+    ConstructMap {
+        name: String,
+        every_round: bool,
+    },
+    FillRect {
+        layer: u8,
+        start: UVec2,
+        size: UVec2,
+        tilemap_tile_idx: Option<u32>,
+        collision: TileCollisionKind,
+    },
+    FloodFill {
+        layer: u8,
+        pos: UVec2,
+        tilemap_tile_idx: Option<u32>,
+        collision: TileCollisionKind,
+        mode: FloodFillMode,
+    },
+    CopyRegion {
+        start: UVec2,
+        size: UVec2,
+    },
+    PasteRegion {
+        pos: UVec2,
+    },
+    ResizeMap {
+        size: UVec2,
+        offset: IVec2,
+    },
+    ShiftMap {
+        offset: IVec2,
+    },
+    Undo,
+    Redo,
+}
+
+/// Helper to find the index of a handle in a list of handles.
+fn index_of<T>(
+    handles: &[Handle<T>],
+    handle: Handle<T>,
+    kind: &'static str,
+) -> Result<usize, ReplayError> {
+    handles
+        .iter()
+        .position(|x| *x == handle)
+        .ok_or(ReplayError::MissingAsset(kind))
+}
+
+/// Helper to get the handle at an index in a list of handles.
+fn handle_at<T>(
+    handles: &[Handle<T>],
+    idx: usize,
+    kind: &'static str,
+) -> Result<Handle<T>, ReplayError> {
+    handles
+        .get(idx)
+        .copied()
+        .ok_or(ReplayError::MissingAsset(kind))
+}
+
+impl ReplayEditorInput {
+    fn from_input(input: &EditorInput, core: &CoreMeta) -> Result<Self, ReplayError> {
+        let element = |handle| index_of(&core.map_elements, handle, "map element");
+        let entity = |entity: &Entity| (entity.index(), entity.generation());
+        Ok(match input {
+            EditorInput::SpawnElement {
+                handle,
+                translation,
+                layer,
+            } => Self::SpawnElement {
+                element: element(*handle)?,
+                translation: *translation,
+                layer: *layer,
+            },
+            EditorInput::MoveEntity { entity: ent, pos } => Self::MoveEntity {
+                entity: entity(ent),
+                pos: *pos,
+            },
+            EditorInput::DeleteEntity { entity: ent } => Self::DeleteEntity {
+                entity: entity(ent),
+            },
+            EditorInput::CreateLayer { id } => Self::CreateLayer { id: id.clone() },
+            EditorInput::RenameLayer { layer, name } => Self::RenameLayer {
+                layer: *layer,
+                name: name.clone(),
+            },
+            EditorInput::DeleteLayer { layer } => Self::DeleteLayer { layer: *layer },
+            EditorInput::MoveLayer { layer, down } => Self::MoveLayer {
+                layer: *layer,
+                down: *down,
+            },
+            EditorInput::SetTilemap { layer, handle } => Self::SetTilemap {
+                layer: *layer,
+                tilemap: handle
+                    .map(|x| index_of(&core.map_tilesets, x, "tilemap"))
+                    .transpose()?,
+            },
+            EditorInput::SetTile {
+                layer,
+                pos,
+                tilemap_tile_idx,
+                collision,
+            } => Self::SetTile {
+                layer: *layer,
+                pos: *pos,
+                tilemap_tile_idx: *tilemap_tile_idx,
+                collision: *collision,
+            },
+            EditorInput::RenameMap { name } => Self::RenameMap { name: name.clone() },
+            EditorInput::RandomizeTiles {
+                tile_layers,
+                element_layers,
+                tile_size,
+            } => Self::RandomizeTiles {
+                tile_layers: tile_layers.clone(),
+                element_layers: element_layers
+                    .iter()
+                    .map(|layer| {
+                        Ok((
+                            layer.layer_index,
+                            layer
+                                .located_elements
+                                .iter()
+                                .map(|(pos, handle)| Ok((*pos, element(*handle)?)))
+                                .collect::<Result<_, ReplayError>>()?,
+                        ))
+                    })
+                    .collect::<Result<_, ReplayError>>()?,
+                tile_size: *tile_size,
+            },
+            EditorInput::ConstructMap { name, every_round } => Self::ConstructMap {
+                name: name.clone(),
+                every_round: *every_round,
+            },
+            EditorInput::FillRect {
+                layer,
+                start,
+                size,
+                tilemap_tile_idx,
+                collision,
+            } => Self::FillRect {
+                layer: *layer,
+                start: *start,
+                size: *size,
+                tilemap_tile_idx: *tilemap_tile_idx,
+                collision: *collision,
+            },
+            EditorInput::FloodFill {
+                layer,
+                pos,
+                tilemap_tile_idx,
+                collision,
+                mode,
+            } => Self::FloodFill {
+                layer: *layer,
+                pos: *pos,
+                tilemap_tile_idx: *tilemap_tile_idx,
+                collision: *collision,
+                mode: *mode,
+            },
+            EditorInput::CopyRegion { start, size } => Self::CopyRegion {
+                start: *start,
+                size: *size,
+            },
+            EditorInput::PasteRegion { pos } => Self::PasteRegion { pos: *pos },
+            EditorInput::ResizeMap { size, offset } => Self::ResizeMap {
+                size: *size,
+                offset: *offset,
+            },
+            EditorInput::ShiftMap { offset } => Self::ShiftMap { offset: *offset },
+            EditorInput::Undo => Self::Undo,
+            EditorInput::Redo => Self::Redo,
+        })
+    }
+
+    fn into_input(self, core: &CoreMeta) -> Result<EditorInput, ReplayError> {
+        let element = |idx| handle_at(&core.map_elements, idx, "map element");
+        let entity = |(index, generation)| Entity::new(index, generation);
+        Ok(match self {
+            Self::SpawnElement {
+                element: idx,
+                translation,
+                layer,
+            } => EditorInput::SpawnElement {
+                handle: element(idx)?,
+                translation,
+                layer,
+            },
+            Self::MoveEntity { entity: ent, pos } => EditorInput::MoveEntity {
+                entity: entity(ent),
+                pos,
+            },
+            Self::DeleteEntity { entity: ent } => EditorInput::DeleteEntity {
+                entity: entity(ent),
+            },
+            Self::CreateLayer { id } => EditorInput::CreateLayer { id },
+            Self::RenameLayer { layer, name } => EditorInput::RenameLayer { layer, name },
+            Self::DeleteLayer { layer } => EditorInput::DeleteLayer { layer },
+            Self::MoveLayer { layer, down } => EditorInput::MoveLayer { layer, down },
+            Self::SetTilemap { layer, tilemap } => EditorInput::SetTilemap {
+                layer,
+                handle: tilemap
+                    .map(|x| handle_at(&core.map_tilesets, x, "tilemap"))
+                    .transpose()?,
+            },
+            Self::SetTile {
+                layer,
+                pos,
+                tilemap_tile_idx,
+                collision,
+            } => EditorInput::SetTile {
+                layer,
+                pos,
+                tilemap_tile_idx,
+                collision,
+            },
+            Self::RenameMap { name } => EditorInput::RenameMap { name },
+            Self::RandomizeTiles {
+                tile_layers,
+                element_layers,
+                tile_size,
+            } => EditorInput::RandomizeTiles {
+                tile_layers,
+                element_layers: element_layers
+                    .into_iter()
+                    .map(|(layer_index, elements)| {
+                        Ok(ElementLayer {
+                            layer_index,
+                            located_elements: elements
+                                .into_iter()
+                                .map(|(pos, idx)| Ok((pos, element(idx)?)))
+                                .collect::<Result<_, ReplayError>>()?,
+                        })
+                    })
+                    .collect::<Result<_, ReplayError>>()?,
+                tile_size,
+            },
+            Self::ConstructMap { name, every_round } => {
+                EditorInput::ConstructMap { name, every_round }
+            }
+            Self::FillRect {
+                layer,
+                start,
+                size,
+                tilemap_tile_idx,
+                collision,
+            } => EditorInput::FillRect {
+                layer,
+                start,
+                size,
+                tilemap_tile_idx,
+                collision,
+            },
+            Self::FloodFill {
+                layer,
+                pos,
+                tilemap_tile_idx,
+                collision,
+                mode,
+            } => EditorInput::FloodFill {
+                layer,
+                pos,
+                tilemap_tile_idx,
+                collision,
+                mode,
+            },
+            Self::CopyRegion { start, size } => EditorInput::CopyRegion { start, size },
+            Self::PasteRegion { pos } => EditorInput::PasteRegion { pos },
+            Self::ResizeMap { size, offset } => EditorInput::ResizeMap { size, offset },
+            Self::ShiftMap { offset } => EditorInput::ShiftMap { offset },
+            Self::Undo => EditorInput::Undo,
+            Self::Redo => EditorInput::Redo,
+        })
+    }
+}
+
+/// A replay that has been resolved against the loaded assets and is ready to be played.
+pub struct Replay {
+    /// The seed of the [`GlobalRng`].
+    pub seed: u64,
+    /// The score at the start of the first round.
+    pub score: MatchScore,
+    /// The name of the [`RoundMapConstructor`] that is run on the map of every round.
+    pub round_map_constructor: Option<Ustr>,
+    /// The players in the match.
+    pub player_info: [PlayerInput; MAX_PLAYERS],
+    /// The maps and the checksums of the rounds, which always has at least one round.
+    pub rounds: ReplayRounds,
+    /// The recorded inputs.
+    pub playback: ReplayPlayback,
+}
+
+/// Resource containing the maps and the checksums of the rounds of a replay that is being played
+/// back, which is carried over from round to round.
+#[derive(HasSchema, Clone, Default)]
+pub struct ReplayRounds {
+    /// The round of the match that the first recorded round was.
+    pub first_round: u32,
+    pub rounds: Vec<ReplayRoundInfo>,
+}
+
+/// A round in the [`ReplayRounds`].
+#[derive(Clone, Debug)]
+pub struct ReplayRoundInfo {
+    /// The map that the round is played on.
+    pub map: MapMeta,
+    /// The checksums that playback is expected to match.
+    pub checksums: HashMap<u32, u64>,
+}
+
+impl ReplayRounds {
+    /// Whether the match is a replay that is being played back.
+    pub fn is_replay(&self) -> bool {
+        !self.rounds.is_empty()
+    }
+
+    /// Get the given round of the match.
+    pub fn get(&self, round: u32) -> Option<&ReplayRoundInfo> {
+        self.rounds
+            .get(round.checked_sub(self.first_round)? as usize)
+    }
+}
+
+impl ReplayFile {
+    /// Create a replay file from the world of a running match.
+    pub fn from_world(world: &World, assets: &AssetServer) -> Result<Self, ReplayError> {
+        let core = &assets.root::<GameMeta>().core;
+        let map = world.resource::<LoadedMap>();
+        let seed = world.resource::<RngSeed>().0;
+        let round_map_constructor = world.resource::<RoundMapConstructor>().constructor;
+        let inputs = world.resource::<MatchInputs>();
+        let recorder = world.resource::<ReplayRecorder>();
+
+        let mut players: [ReplayPlayer; MAX_PLAYERS] = default();
+        for (slot, player) in players.iter_mut().zip(&inputs.players) {
+            *slot = ReplayPlayer {
+                active: player.active,
+                player: if player.active {
+                    index_of(&core.players, player.selected_player, "player")?
+                } else {
+                    0
+                },
+                hat: player
+                    .selected_hat
+                    .map(|x| index_of(&core.player_hats, x, "hat"))
+                    .transpose()?,
+                control_source: player.control_source,
+                ai_difficulty: player.ai_difficulty,
+            };
+        }
+
+        let frames = |frames: &[RecordedFrame]| {
+            frames
+                .iter()
+                .map(|frame| {
+                    Ok(ReplayFrame {
+                        repeat: frame.repeat,
+                        controls: frame.controls,
+                        editor_inputs: frame
+                            .editor_inputs
+                            .iter()
+                            .enumerate()
+                            .filter_map(|(i, x)| x.as_ref().map(|x| (i as u8, x)))
+                            .map(|(i, input)| Ok((i, ReplayEditorInput::from_input(input, core)?)))
+                            .collect::<Result<_, ReplayError>>()?,
+                    })
+                })
+                .collect::<Result<_, ReplayError>>()
+        };
+        let rounds = recorder
+            .rounds
+            .iter()
+            .map(|x| (&x.map, &x.frames, &x.checksums))
+            .chain([(&*map.0, &recorder.frames, &recorder.checksums)])
+            .map(|(map, recorded_frames, checksums)| {
+                Ok(ReplayRound {
+                    map: MapFile::from_meta(map, assets)?,
+                    frames: frames(recorded_frames)?,
+                    checksums: checksums.clone(),
+                })
+            })
+            .collect::<Result<_, ReplayError>>()?;
+
+        Ok(Self {
+            version: REPLAY_FORMAT_VERSION,
+            game_version: env!("CARGO_PKG_VERSION").into(),
+            seed,
+            score: recorder.start_score,
+            round_map_constructor: round_map_constructor.map(|x| x.to_string()),
+            players,
+            rounds,
+        })
+    }
+
+    /// Load a replay file from disk.
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        let file = std::fs::File::open(path)?;
+        let replay: Self = serde_yaml::from_reader(std::io::BufReader::new(file))?;
+        if replay.version != REPLAY_FORMAT_VERSION {
+            return Err(ReplayError::UnsupportedVersion(replay.version));
+        }
+        Ok(replay)
+    }
+
+    /// Save the replay file to disk.
+    pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = std::fs::File::create(path)?;
+        serde_yaml::to_writer(std::io::BufWriter::new(file), self)?;
+        Ok(())
+    }
+
+    /// Resolve the replay against the loaded assets so that it can be played.
+    pub fn resolve(self, assets: &AssetServer) -> Result<Replay, ReplayError> {
+        let core = &assets.root::<GameMeta>().core;
+        if self.rounds.is_empty() {
+            return Err(ReplayError::NoRounds);
+        }
+
+        let mut player_info: [PlayerInput; MAX_PLAYERS] = default();
+        for (info, player) in player_info.iter_mut().zip(self.players) {
+            *info = PlayerInput {
+                active: player.active,
+                selected_player: handle_at(&core.players, player.player, "player")?,
+                selected_hat: player
+                    .hat
+                    .map(|x| handle_at(&core.player_hats, x, "hat"))
+                    .transpose()?,
+                control_source: player.control_source,
+                ai_difficulty: player.ai_difficulty,
+                ..default()
+            };
+        }
+
+        let mut rounds = Vec::with_capacity(self.rounds.len());
+        let mut frames = Vec::with_capacity(self.rounds.len());
+        for round in self.rounds {
+            rounds.push(ReplayRoundInfo {
+                map: round.map.to_meta(assets)?,
+                checksums: round.checksums.into_iter().collect(),
+            });
+            frames.push(
+                round
+                    .frames
+                    .into_iter()
+                    .map(|frame| {
+                        let mut editor_inputs: [Option<EditorInput>; MAX_PLAYERS] = default();
+                        for (i, input) in frame.editor_inputs {
+                            if let Some(slot) = editor_inputs.get_mut(i as usize) {
+                                *slot = Some(input.into_input(core)?);
+                            }
+                        }
+                        Ok(RecordedFrame {
+                            repeat: frame.repeat,
+                            controls: frame.controls,
+                            editor_inputs,
+                        })
+                    })
+                    .collect::<Result<_, ReplayError>>()?,
+            );
+        }
+
+        Ok(Replay {
+            seed: self.seed,
+            score: self.score,
+            round_map_constructor: self.round_map_constructor.as_deref().map(ustr),
+            player_info,
+            rounds: ReplayRounds {
+                first_round: self.score.round,
+                rounds,
+            },
+            playback: ReplayPlayback::new(frames, self.score.round),
+        })
+    }
+}
+
+/// Get the directory that replays are saved to by default.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn replay_dir() -> std::path::PathBuf {
+    directories::ProjectDirs::from("org", "fishfolk", "jumpy")
+        .data_dir()
+        .join("replays")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::headless::{load_game, start_replay, start_test_match_with};
+
+    /// The number of frames to record after the first one.
+    const FRAMES: u32 = 300;
+
+    fn checksum(game: &Game) -> StateChecksum {
+        let world = &game.sessions.get(SessionNames::GAME).unwrap().world;
+        *world.resource::<StateChecksum>()
+    }
+
+    /// The input of the local player on the given frame, which runs, jumps and shoots.
+    fn scripted_control(frame: u32) -> PlayerControl {
+        PlayerControl {
+            move_direction: vec2(if frame % 120 < 60 { 1.0 } else { -1.0 }, 0.0),
+            jump_pressed: frame % 40 < 10,
+            jump_just_pressed: frame % 40 == 0,
+            grab_pressed: frame % 50 == 0,
+            grab_just_pressed: frame % 50 == 0,
+            shoot_pressed: frame % 30 == 0,
+            shoot_just_pressed: frame % 30 == 0,
+            ..default()
+        }
+    }
+
+    /// Record a match with a local player and an AI player and save the replay to the given path,
+    /// returning the checksum of every frame.
+    fn record_match(path: &Path) -> Vec<StateChecksum> {
+        let mut game = start_test_match_with(&[Some(ControlSource::Keyboard1), None]);
+        let mut checksums = vec![checksum(&game)];
+        for frame in 0..FRAMES {
+            let world = &game.sessions.get(SessionNames::GAME).unwrap().world;
+            world.resource_mut::<MatchInputs>().players[0].control = scripted_control(frame);
+            game.step(Instant::now());
+            checksums.push(checksum(&game));
+        }
+
+        let world = &game.sessions.get(SessionNames::GAME).unwrap().world;
+        let assets = game.shared_resource::<AssetServer>().unwrap();
+        ReplayFile::from_world(world, &assets)
+            .unwrap()
+            .save(path)
+            .unwrap();
+        checksums
+    }
+
+    #[test]
+    fn replay_plays_back_identically() {
+        let path = std::env::temp_dir().join(format!(
+            "jumpy-test-{}.{REPLAY_FILE_EXTENSION}",
+            std::process::id()
+        ));
+        let recorded = record_match(&path);
+
+        let mut game = load_game(Path::new("assets"), Path::new("packs")).unwrap();
+        let replay = {
+            let assets = game.shared_resource::<AssetServer>().unwrap();
+            ReplayFile::load(&path).unwrap().resolve(&assets).unwrap()
+        };
+        std::fs::remove_file(&path).unwrap();
+        start_replay(&mut game, replay);
+
+        let mut played = Vec::new();
+        for _ in 0..=FRAMES {
+            game.step(Instant::now());
+            played.push(checksum(&game));
+        }
+        assert_eq!(played, recorded);
+
+        let world = &game.sessions.get(SessionNames::GAME).unwrap().world;
+        assert_eq!(world.resource::<ReferenceChecksums>().mismatch, None);
+    }
+
+    #[test]
+    fn replay_with_other_version_is_rejected() {
+        let path = std::env::temp_dir().join(format!(
+            "jumpy-test-version-{}.{REPLAY_FILE_EXTENSION}",
+            std::process::id()
+        ));
+        record_match(&path);
+
+        let mut replay = ReplayFile::load(&path).unwrap();
+        replay.version = REPLAY_FORMAT_VERSION + 1;
+        replay.save(&path).unwrap();
+        let result = ReplayFile::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            result,
+            Err(ReplayError::UnsupportedVersion(v)) if v == REPLAY_FORMAT_VERSION + 1
+        ));
+    }
+}
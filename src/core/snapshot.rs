@@ -0,0 +1,212 @@
+//! World state snapshots.
+//!
+//! A [`WorldSnapshot`] holds a complete copy of a match [`World`] at a single frame: every
+//! entity and component, and every resource, including the [`RapierContext`], the
+//! [`CollisionCache`], the [`GlobalRng`], the [`NavGraph`], the [`SpawnerEntities`], and the
+//! [`Time`]. Restoring a snapshot puts the world back into exactly that state, so that stepping
+//! it again with the same inputs produces the same frames.
+//!
+//! This is the building block for rollback networking, rewinding, and instant restarts.
+
+use std::collections::VecDeque;
+
+use crate::prelude::*;
+
+/// A copy of the complete state of a match world.
+#[derive(Clone, Debug)]
+pub struct WorldSnapshot {
+    world: World,
+}
+
+impl WorldSnapshot {
+    /// Take a snapshot of the world.
+    pub fn capture(world: &World) -> Self {
+        Self {
+            world: world.clone(),
+        }
+    }
+
+    /// Restore the world to the state it was in when the snapshot was taken.
+    ///
+    /// The snapshot is left untouched, so it may be restored any number of times.
+    pub fn restore(&self, world: &mut World) {
+        *world = self.world.clone();
+    }
+
+    /// Get the world stored in the snapshot.
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+}
+
+/// Resource that is `true` while frames that have already been played are being simulated again
+/// after restoring a snapshot.
+///
+/// Systems with effects outside of the world, like playing sounds, should skip them while this is
+/// set, so that they don't happen twice.
+#[derive(HasSchema, Clone, Copy, Debug, Default, Deref, DerefMut)]
+pub struct Resimulating(pub bool);
+
+/// Resource that is `true` while the latest frames were simulated with predicted input, which may
+/// still turn out to be wrong and be rolled back.
+///
+/// Things that happen outside of the simulation because of its state, like moving on to the next
+/// round, should wait until this is `false`, so that every player does them after the same frame.
+#[derive(HasSchema, Clone, Copy, Debug, Default, Deref, DerefMut)]
+pub struct Predicted(pub bool);
+
+/// A fixed size history of snapshots, indexed by frame.
+///
+/// Once the history is full, taking a new snapshot drops the oldest one.
+#[derive(Clone, Debug)]
+pub struct SnapshotHistory {
+    capacity: usize,
+    snapshots: VecDeque<(u32, WorldSnapshot)>,
+}
+
+impl SnapshotHistory {
+    /// Create a history that keeps up to `capacity` snapshots.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "Snapshot history capacity must not be zero");
+        Self {
+            capacity,
+            snapshots: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Take a snapshot of the world for the given frame.
+    ///
+    /// Any snapshots for this or later frames are dropped first, because they belong to a
+    /// timeline that no longer exists.
+    pub fn capture(&mut self, frame: u32, world: &World) {
+        self.snapshots.retain(|(f, _)| *f < frame);
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots
+            .push_back((frame, WorldSnapshot::capture(world)));
+    }
+
+    /// Get the snapshot for the given frame.
+    pub fn get(&self, frame: u32) -> Option<&WorldSnapshot> {
+        self.snapshots
+            .iter()
+            .find(|(f, _)| *f == frame)
+            .map(|(_, snapshot)| snapshot)
+    }
+
+    /// Restore the world to the state it was in at the given frame.
+    ///
+    /// Returns `false`, leaving the world untouched, if there is no snapshot for the frame.
+    pub fn restore(&mut self, frame: u32, world: &mut World) -> bool {
+        let Some(snapshot) = self.get(frame) else {
+            return false;
+        };
+        snapshot.restore(world);
+        self.snapshots.retain(|(f, _)| *f <= frame);
+        true
+    }
+
+    /// The oldest frame that can be restored.
+    pub fn oldest_frame(&self) -> Option<u32> {
+        self.snapshots.front().map(|(frame, _)| *frame)
+    }
+
+    /// The latest frame that can be restored.
+    pub fn latest_frame(&self) -> Option<u32> {
+        self.snapshots.back().map(|(frame, _)| *frame)
+    }
+
+    /// Remove all snapshots.
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::headless::start_test_match;
+
+    /// The state that must be identical when re-simulating the same frames.
+    #[derive(Debug, PartialEq)]
+    struct SimState {
+        transforms: Vec<(Entity, Vec3, Quat)>,
+        velocities: Vec<(Entity, Vec2)>,
+        next_random: u64,
+    }
+
+    fn sim_state(world: &World) -> SimState {
+        world.run_system(collect_sim_state, ())
+    }
+
+    fn collect_sim_state(
+        entities: Res<Entities>,
+        transforms: Comp<Transform>,
+        bodies: Comp<KinematicBody>,
+        rng: Res<GlobalRng>,
+    ) -> SimState {
+        SimState {
+            transforms: entities
+                .iter_with(&transforms)
+                .map(|(ent, transform)| (ent, transform.translation, transform.rotation))
+                .collect(),
+            velocities: entities
+                .iter_with(&bodies)
+                .map(|(ent, body)| (ent, body.velocity))
+                .collect(),
+            // Clone the RNG so that we don't advance it.
+            next_random: (*rng).clone().u64(..),
+        }
+    }
+
+    fn game_world(game: &mut Game) -> &mut World {
+        &mut game.sessions.get_mut(SessionNames::GAME).unwrap().world
+    }
+
+    fn step(game: &mut Game, frames: u32) {
+        for _ in 0..frames {
+            game.step(Instant::now());
+        }
+    }
+
+    #[test]
+    fn restore_and_resimulate_is_identical() {
+        let mut game = start_test_match(MAX_PLAYERS);
+        // Let the players spawn and start moving around.
+        step(&mut game, 60);
+
+        let snapshot = WorldSnapshot::capture(game_world(&mut game));
+        step(&mut game, 120);
+        let expected = sim_state(game_world(&mut game));
+
+        snapshot.restore(game_world(&mut game));
+        step(&mut game, 120);
+        assert_eq!(sim_state(game_world(&mut game)), expected);
+
+        // Restoring the same snapshot again must still give the same result.
+        snapshot.restore(game_world(&mut game));
+        step(&mut game, 120);
+        assert_eq!(sim_state(game_world(&mut game)), expected);
+    }
+
+    #[test]
+    fn history_restores_by_frame() {
+        let mut game = start_test_match(MAX_PLAYERS);
+        // Let the players spawn and start moving around.
+        step(&mut game, 60);
+        let mut history = SnapshotHistory::new(8);
+
+        history.capture(0, game_world(&mut game));
+        step(&mut game, 30);
+        history.capture(1, game_world(&mut game));
+        let expected = sim_state(game_world(&mut game));
+        step(&mut game, 30);
+        history.capture(2, game_world(&mut game));
+
+        assert!(history.restore(1, game_world(&mut game)));
+        assert_eq!(sim_state(game_world(&mut game)), expected);
+        assert_eq!(history.latest_frame(), Some(1));
+        assert!(!history.restore(2, game_world(&mut game)));
+    }
+}
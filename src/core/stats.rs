@@ -0,0 +1,57 @@
+//! Per-player match statistics.
+//!
+//! The [`MatchStats`] are carried over from round to round, like the [`MatchScore`], and are shown
+//! on the results screen once the match is over.
+
+use crate::prelude::*;
+
+/// Install this module.
+pub fn install(session: &mut Session) {
+    session.world.init_resource::<MatchStats>();
+}
+
+/// Resource containing the statistics of every player in the match.
+#[derive(HasSchema, Clone, Copy, Debug, Default)]
+pub struct MatchStats {
+    /// The statistics of each player, by player index.
+    pub players: [PlayerStats; MAX_PLAYERS],
+}
+
+/// The statistics of a single player.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlayerStats {
+    /// The number of other players that this player has killed.
+    pub kills: u32,
+    /// The number of times that this player has died, including suicides.
+    pub deaths: u32,
+    /// The number of times that this player has killed themselves, such as by falling out of the
+    /// map.
+    pub suicides: u32,
+    /// The number of items that this player has picked up.
+    pub items_picked_up: u32,
+    /// The number of times that this player has killed each other player, by player index.
+    pub victims: [u32; MAX_PLAYERS],
+}
+
+impl MatchStats {
+    /// Record that a player has been killed, and by which player, if any.
+    ///
+    /// Players that are killed by themselves count as suicides.
+    pub fn record_kill(&mut self, victim: u32, killer: Option<u32>) {
+        self.players[victim as usize].deaths += 1;
+        match killer {
+            Some(killer) if killer == victim => self.players[victim as usize].suicides += 1,
+            Some(killer) => {
+                let killer = &mut self.players[killer as usize];
+                killer.kills += 1;
+                killer.victims[victim as usize] += 1;
+            }
+            None => (),
+        }
+    }
+
+    /// Record that a player has picked up an item.
+    pub fn record_item_pickup(&mut self, player: u32) {
+        self.players[player as usize].items_picked_up += 1;
+    }
+}
@@ -0,0 +1,259 @@
+//! Headless match simulation.
+//!
+//! Runs complete matches without a window, GPU, or renderer. This is used by the
+//! `jumpy-headless` binary to regression-test maps and AI in CI.
+
+use std::path::Path;
+
+use crate::{core::MatchPlugin, prelude::*};
+
+/// Create a game with the core plugins installed and the asset pack loaded from disk, ready to
+/// run headless matches.
+pub fn load_game(asset_dir: &Path, packs_dir: &Path) -> Result<Game, String> {
+    // Initialize the Bevy task pool manually so that we can use it to load assets.
+    bevy_tasks::IoTaskPool::init(bevy_tasks::TaskPool::new);
+
+    let mut game = Game::new();
+    GameMeta::schema();
+    game.install_plugin(DefaultGamePlugin)
+        .install_plugin(crate::core::game_plugin)
+        .init_shared_resource::<AssetServer>()
+        .register_default_assets();
+
+    // There is no renderer, but the camera still sizes itself according to the window.
+    game.insert_shared_resource(Window {
+        size: vec2(1920.0, 1080.0),
+        ..default()
+    });
+
+    // Load the asset pack
+    {
+        let mut asset_server = game.shared_resource_mut::<AssetServer>().unwrap();
+        asset_server.set_io(FileAssetIo::new(asset_dir, packs_dir));
+        bevy_tasks::block_on(asset_server.load_assets())
+            .map_err(|e| format!("Error loading assets: {e}"))?;
+    }
+
+    Ok(game)
+}
+
+/// A match to simulate without a renderer.
+pub struct HeadlessMatch {
+    /// The map to play the match on.
+    pub map: MapMeta,
+    /// The players taking part in the match.
+    pub player_info: [PlayerInput; MAX_PLAYERS],
+    /// The maximum number of fixed frames to simulate before the match is stopped, if the first
+    /// round hasn't ended by then.
+    pub max_frames: u64,
+}
+
+/// The results of a headless match.
+#[derive(Clone, Debug, Default)]
+pub struct HeadlessMatchReport {
+    /// The name of the map that was played.
+    pub map_name: Ustr,
+    /// The number of fixed frames that were simulated.
+    pub frames_played: u64,
+    /// Every kill that happened during the match, in the order they happened.
+    pub kills: Vec<HeadlessKill>,
+    /// The index of the winning player, or [`None`] if the match was a draw.
+    pub winner: Option<u32>,
+}
+
+/// A kill that happened during a headless match.
+#[derive(Clone, Copy, Debug)]
+pub struct HeadlessKill {
+    /// The fixed frame that the kill happened on.
+    pub frame: u64,
+    /// The index of the player that was killed.
+    pub player: u32,
+    /// The index of the player responsible for the kill, if any.
+    pub killer: Option<u32>,
+    /// What killed the player.
+    pub cause: DeathCause,
+}
+
+/// Resource used to collect the statistics of a headless match while it is running.
+#[derive(HasSchema, Clone, Default)]
+pub struct HeadlessMatchStats {
+    /// The number of fixed frames that have been simulated.
+    pub frame: u64,
+    /// The kills recorded so far.
+    pub kills: Vec<HeadlessKill>,
+}
+
+/// Session runner that advances the match by exactly one fixed frame every time it is stepped,
+/// regardless of how much real time has passed.
+///
+/// Unlike [`JumpyDefaultMatchRunner`][crate::core::JumpyDefaultMatchRunner], this doesn't read
+/// any local input, so every player in the match is expected to be an AI, or to have its input
+/// played back from a replay.
+#[derive(Default)]
+pub struct HeadlessMatchRunner {
+    /// If this is set, the player inputs are read from the replay.
+    pub replay: Option<ReplayPlayback>,
+}
+
+impl SessionRunner for HeadlessMatchRunner {
+    fn step(&mut self, _frame_start: Instant, world: &mut World, stages: &mut SystemStages) {
+        const STEP: f64 = 1.0 / FPS as f64;
+
+        world
+            .resource_mut::<Time>()
+            .advance_exact(Duration::from_secs_f64(STEP));
+
+        {
+            let round = world.resource::<MatchScore>().round;
+            let mut player_inputs = world.resource_mut::<MatchInputs>();
+            if let Some(replay) = &mut self.replay {
+                replay.apply_next_frame(round, &mut player_inputs);
+            }
+            world
+                .resource_mut::<ReplayRecorder>()
+                .record(&player_inputs);
+        }
+
+        stages.run(world);
+
+        world.resource_mut::<HeadlessMatchStats>().frame += 1;
+    }
+}
+
+/// Session plugin that records [`HeadlessMatchStats`] for the match.
+pub fn session_plugin(session: &mut Session) {
+    session.world.init_resource::<HeadlessMatchStats>();
+    session
+        .stages
+        .add_system_to_stage(CoreStage::Last, record_kills);
+}
+
+/// Record the players that have been killed since the last frame.
+fn record_kills(kill_events: ResInit<KillEvents>, mut stats: ResMut<HeadlessMatchStats>) {
+    let frame = stats.frame;
+    let recorded = stats.kills.len();
+    for event in &kill_events.events[recorded..] {
+        stats.kills.push(HeadlessKill {
+            frame,
+            player: event.victim,
+            killer: event.killer,
+            cause: event.cause,
+        });
+    }
+}
+
+/// Start a game session for the match that advances one fixed frame every time the game is
+/// stepped.
+///
+/// The game must already have its assets loaded, and must not have a game session running.
+pub fn start_match(game: &mut Game, map: MapMeta, player_info: [PlayerInput; MAX_PLAYERS]) {
+    game.sessions.start_game(MatchPlugin { map, player_info });
+    let session = game.sessions.get_mut(SessionNames::GAME).unwrap();
+    session.install_plugin(session_plugin);
+    session.runner = Box::<HeadlessMatchRunner>::default();
+}
+
+/// Start a game session that plays back a replay, advancing one fixed frame every time the game
+/// is stepped.
+///
+/// The game must already have its assets loaded, and must not have a game session running.
+pub fn start_replay(game: &mut Game, mut replay: Replay) {
+    let playback = std::mem::take(&mut replay.playback);
+    game.sessions.start_replay(replay);
+    let session = game.sessions.get_mut(SessionNames::GAME).unwrap();
+    session.install_plugin(session_plugin);
+    session.runner = Box::new(HeadlessMatchRunner {
+        replay: Some(playback),
+    });
+}
+
+/// Load the game from the `assets` and `packs` directories and start a headless match on the first
+/// stable map, with the given number of AI players, stepped once so that the map has spawned.
+#[cfg(test)]
+pub fn start_test_match(active_players: usize) -> Game {
+    start_test_match_with(&vec![None; active_players])
+}
+
+/// Like [`start_test_match()`], but with a player for each of the given control sources. Players
+/// without a control source are AI players.
+#[cfg(test)]
+pub fn start_test_match_with(control_sources: &[Option<ControlSource>]) -> Game {
+    let mut game = load_game(Path::new("assets"), Path::new("packs")).unwrap();
+    let (map, players) = {
+        let assets = game.shared_resource::<AssetServer>().unwrap();
+        let core = &assets.root::<GameMeta>().core;
+        let map = (*assets.get(core.stable_maps[0])).clone();
+        (map, core.players.clone())
+    };
+    start_match(
+        &mut game,
+        map,
+        std::array::from_fn(|i| PlayerInput {
+            active: i < control_sources.len(),
+            selected_player: players[i % players.len()],
+            control_source: control_sources.get(i).copied().flatten(),
+            ..default()
+        }),
+    );
+    game.step(Instant::now());
+    game
+}
+
+/// Simulate a complete match and return its results.
+///
+/// The game must already have its assets loaded, and must not have a game session running.
+pub fn run_match(game: &mut Game, headless_match: HeadlessMatch) -> HeadlessMatchReport {
+    let HeadlessMatch {
+        map,
+        player_info,
+        max_frames,
+    } = headless_match;
+    let map_name = map.name;
+    let active_players = player_info
+        .iter()
+        .enumerate()
+        .filter(|(_, player)| player.active)
+        .map(|(i, _)| i as u32)
+        .collect::<Vec<_>>();
+
+    start_match(game, map, player_info);
+
+    // The match is played until the first round is over.
+    let (stats, round_state) = loop {
+        let world = &game.sessions.get(SessionNames::GAME).unwrap().world;
+        let round_state = world.resource::<RoundState>().clone();
+        if world.resource::<HeadlessMatchStats>().frame >= max_frames || !round_state.is_playing() {
+            break (world.resource::<HeadlessMatchStats>().clone(), round_state);
+        }
+        game.step(Instant::now());
+    };
+    game.sessions.end_game();
+
+    if let RoundState::RoundOver { winner, .. } = round_state {
+        return HeadlessMatchReport {
+            map_name,
+            frames_played: stats.frame,
+            kills: stats.kills,
+            winner,
+        };
+    }
+
+    // If the round didn't end in time, the player that died the fewest times wins.
+    let deaths = |player: u32| stats.kills.iter().filter(|x| x.player == player).count();
+    let fewest_deaths = active_players.iter().map(|&x| deaths(x)).min();
+    let mut best = active_players
+        .iter()
+        .copied()
+        .filter(|&x| Some(deaths(x)) == fewest_deaths);
+    let winner = match (best.next(), best.next()) {
+        (Some(winner), None) => Some(winner),
+        _ => None,
+    };
+
+    HeadlessMatchReport {
+        map_name,
+        frames_played: stats.frame,
+        kills: stats.kills,
+        winner,
+    }
+}
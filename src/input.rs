@@ -116,7 +116,7 @@ impl GlobalPlayerControls {
 }
 
 /// The source of player control inputs
-#[derive(Debug, Clone, Copy, Default, HasSchema, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Default, HasSchema, Hash, Eq, PartialEq, Serialize, Deserialize)]
 #[repr(C, u8)]
 pub enum ControlSource {
     #[default]
@@ -126,10 +126,15 @@ pub enum ControlSource {
     Keyboard2,
     /// A gamepad control with the given index
     Gamepad(u32),
+    /// A remote player in an online match, with the given player index.
+    ///
+    /// The controls for remote players are received over the network instead of being collected
+    /// from local input devices.
+    Remote(u32),
 }
 
 /// Player control input state
-#[derive(HasSchema, Default, Clone, Copy, Debug)]
+#[derive(HasSchema, Default, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 #[repr(C)]
 pub struct PlayerControl {
     pub left: f32,
@@ -282,15 +287,9 @@ impl PlayerInputCollector {
         };
 
         for (source, control) in self.current_controls.iter_mut() {
-            apply_controls(
-                control,
-                source,
-                match source {
-                    ControlSource::Keyboard1 => &mapping.keyboard1,
-                    ControlSource::Keyboard2 => &mapping.keyboard2,
-                    ControlSource::Gamepad(_) => &mapping.gamepad,
-                },
-            );
+            if let Some(mapping) = mapping.for_source(*source) {
+                apply_controls(control, source, mapping);
+            }
         }
     }
 
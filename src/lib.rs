@@ -0,0 +1,57 @@
+#![doc(html_logo_url = "https://avatars.githubusercontent.com/u/87333478?s=200&v=4")]
+// This cfg_attr is needed because `rustdoc::all` includes lints not supported on stable
+#![cfg_attr(doc, allow(unknown_lints))]
+#![deny(rustdoc::all)]
+#![allow(clippy::too_many_arguments)]
+// TODO: Warn on dead code.
+// This is temporarily disabled while migrating to the new bones.
+#![allow(dead_code)]
+#![allow(ambiguous_glob_reexports)]
+#![doc = include_str!("./README.md")]
+
+use bones_framework::prelude::*;
+
+pub mod core;
+pub mod fullscreen;
+pub mod headless;
+pub mod input;
+pub mod music;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod networking;
+pub mod sessions;
+pub mod settings;
+pub mod ui;
+
+pub mod prelude {
+    pub use crate::{
+        core::prelude::*, impl_system_param, input::*, sessions::*, settings::*, GameMeta,
+    };
+    pub use bones_framework::prelude::*;
+    pub use once_cell::sync::Lazy;
+    pub use serde::{Deserialize, Serialize};
+    pub use std::{sync::Arc, time::Duration};
+    pub use tracing::{debug, error, info, trace, warn};
+}
+use crate::prelude::*;
+
+#[derive(HasSchema, Clone, Debug, Default)]
+#[type_data(metadata_asset("game"))]
+#[repr(C)]
+pub struct GameMeta {
+    pub core: CoreMeta,
+    pub default_settings: settings::Settings,
+    pub localization: Handle<LocalizationAsset>,
+    pub theme: ui::UiTheme,
+    pub main_menu: ui::main_menu::MainMenuMeta,
+    pub music: GameMusic,
+}
+
+#[derive(HasSchema, Clone, Debug, Default)]
+#[repr(C)]
+pub struct GameMusic {
+    pub title_screen: Handle<AudioSource>,
+    pub fight: SVec<Handle<AudioSource>>,
+    pub character_screen: Handle<AudioSource>,
+    pub results_screen: Handle<AudioSource>,
+    pub credits: Handle<AudioSource>,
+}
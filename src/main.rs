@@ -1,36 +1,5 @@
-#![doc(html_logo_url = "https://avatars.githubusercontent.com/u/87333478?s=200&v=4")]
-// This cfg_attr is needed because `rustdoc::all` includes lints not supported on stable
-#![cfg_attr(doc, allow(unknown_lints))]
-#![deny(rustdoc::all)]
-#![allow(clippy::too_many_arguments)]
-// TODO: Warn on dead code.
-// This is temporarily disabled while migrating to the new bones.
-#![allow(dead_code)]
-#![allow(ambiguous_glob_reexports)]
-#![doc = include_str!("./README.md")]
-
 use bones_bevy_renderer::BonesBevyRenderer;
-use bones_framework::prelude::*;
-
-pub mod core;
-pub mod fullscreen;
-pub mod input;
-pub mod music;
-pub mod sessions;
-pub mod settings;
-pub mod ui;
-
-mod prelude {
-    pub use crate::{
-        core::prelude::*, impl_system_param, input::*, sessions::*, settings::*, GameMeta,
-    };
-    pub use bones_framework::prelude::*;
-    pub use once_cell::sync::Lazy;
-    pub use serde::{Deserialize, Serialize};
-    pub use std::{sync::Arc, time::Duration};
-    pub use tracing::{debug, error, info, trace, warn};
-}
-use crate::prelude::*;
+use jumpy::{prelude::*, GameMeta};
 
 // This will cause Bevy to be dynamically linked during development,
 // which can greatly reduce re-compile times in some circumstances.
@@ -39,34 +8,12 @@ use crate::prelude::*;
 #[allow(clippy::single_component_path_imports)]
 use bevy_dylib;
 
-#[derive(HasSchema, Clone, Debug, Default)]
-#[type_data(metadata_asset("game"))]
-#[repr(C)]
-pub struct GameMeta {
-    pub core: CoreMeta,
-    pub default_settings: settings::Settings,
-    pub localization: Handle<LocalizationAsset>,
-    pub theme: ui::UiTheme,
-    pub main_menu: ui::main_menu::MainMenuMeta,
-    pub music: GameMusic,
-}
-
-#[derive(HasSchema, Clone, Debug, Default)]
-#[repr(C)]
-pub struct GameMusic {
-    pub title_screen: Handle<AudioSource>,
-    pub fight: SVec<Handle<AudioSource>>,
-    pub character_screen: Handle<AudioSource>,
-    pub results_screen: Handle<AudioSource>,
-    pub credits: Handle<AudioSource>,
-}
-
 fn main() {
     // Initialize the Bevy task pool manually so that we can use it during startup.
     bevy_tasks::IoTaskPool::init(bevy_tasks::TaskPool::new);
 
     // Register types that we will load from persistent storage.
-    settings::Settings::schema();
+    jumpy::settings::Settings::schema();
 
     // First create bones game.
     let mut game = Game::new();
@@ -77,24 +24,45 @@ fn main() {
     game
         // Install game plugins
         .install_plugin(DefaultGamePlugin)
-        .install_plugin(music::game_plugin)
-        .install_plugin(settings::game_plugin)
-        .install_plugin(fullscreen::game_plugin)
-        .install_plugin(input::game_plugin)
-        .install_plugin(core::game_plugin)
+        .install_plugin(jumpy::music::game_plugin)
+        .install_plugin(jumpy::settings::game_plugin)
+        .install_plugin(jumpy::fullscreen::game_plugin)
+        .install_plugin(jumpy::input::game_plugin)
+        .install_plugin(jumpy::core::game_plugin)
         // We initialize the asset server and register asset types
         .init_shared_resource::<AssetServer>()
         .register_default_assets();
 
-    // Create a new session for the game menu. Each session is it's own bones world with it's own
+    // If a replay was passed on the command line, play it once the assets are loaded. Otherwise
+    // create a new session for the game menu. Each session is it's own bones world with it's own
     // plugins, systems, and entities.
-    game.sessions.start_menu();
+    if let Some(path) = replay_arg() {
+        game.insert_shared_resource(ReplayArg(path));
+        game.systems.add_startup_system(start_replay);
+    } else {
+        game.sessions.start_menu();
+    }
 
     // Create a new session for the pause menu, which sits in the background by default and only
     // does anything while the game is running.
     game.sessions
         .create(SessionNames::PAUSE_MENU)
-        .install_plugin(ui::pause_menu::session_plugin);
+        .install_plugin(jumpy::ui::pause_menu::session_plugin);
+
+    // Create a session for the debug overlay, which draws on top of the running game.
+    game.sessions
+        .create(SessionNames::DEBUG_OVERLAY)
+        .install_plugin(jumpy::ui::debug_overlay::session_plugin);
+
+    // Create a session for the in-match HUD, which draws on top of the running game.
+    game.sessions
+        .create(SessionNames::HUD)
+        .install_plugin(jumpy::ui::hud::session_plugin);
+
+    // Create a session for the round results, which draws on top of the running game.
+    game.sessions
+        .create(SessionNames::ROUND_OVERLAY)
+        .install_plugin(jumpy::ui::round_overlay::session_plugin);
 
     // Create a bevy renderer for the bones game and run it.
     BonesBevyRenderer {
@@ -114,6 +82,38 @@ fn main() {
     .run();
 }
 
+/// Shared resource containing the path of the replay passed with `--replay`.
+#[derive(HasSchema, Clone, Default)]
+struct ReplayArg(String);
+
+/// Get the replay path from the command line arguments.
+fn replay_arg() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--replay" {
+            return args.next();
+        }
+    }
+    None
+}
+
+/// Startup system that plays the replay passed with `--replay`, falling back to the main menu if
+/// it can't be loaded.
+fn start_replay(game: &mut Game) {
+    let path = game.shared_resource::<ReplayArg>().unwrap().0.clone();
+    let replay = {
+        let assets = game.shared_resource::<AssetServer>().unwrap();
+        ReplayFile::load(std::path::Path::new(&path)).and_then(|x| x.resolve(&assets))
+    };
+    match replay {
+        Ok(replay) => game.sessions.start_replay(replay),
+        Err(e) => {
+            error!("Could not play replay `{path}`: {e}");
+            game.sessions.start_menu();
+        }
+    }
+}
+
 fn load_progress(assets: &AssetServer, ctx: &egui::Context) {
     let errored = assets.load_progress.errored();
     egui::CentralPanel::default()
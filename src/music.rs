@@ -29,6 +29,8 @@ pub enum MusicState {
     CharacterSelect(StaticSoundHandle),
     /// Playing the credits music.
     Credits(StaticSoundHandle),
+    /// Playing the results screen music, once a match has been won.
+    Results(StaticSoundHandle),
     /// Playing the fight music.
     Fight {
         /// The handle to the audio instance.
@@ -46,6 +48,7 @@ impl MusicState {
             MusicState::MainMenu(i) => Some(i),
             MusicState::CharacterSelect(i) => Some(i),
             MusicState::Credits(i) => Some(i),
+            MusicState::Results(i) => Some(i),
             MusicState::Fight { instance, .. } => Some(instance),
         }
     }
@@ -86,8 +89,25 @@ fn music_system(
         .volume(MUSIC_VOLUME)
         .fade_in_tween(tween);
 
+    // If we are on the results screen
+    if sessions.get(SessionNames::RESULTS).is_some() {
+        if !matches!(*music_state, MusicState::Results(..)) {
+            if let Some(instance) = music_state.current_instance() {
+                instance.stop(tween).unwrap();
+            }
+            *music_state = MusicState::Results(
+                audio
+                    .play(
+                        assets
+                            .get(meta.music.results_screen)
+                            .with_settings(play_settings.loop_region(Region::default())),
+                    )
+                    .unwrap(),
+            );
+        }
+
     // If we are in a game
-    if sessions.get(SessionNames::GAME).is_some() {
+    } else if sessions.get(SessionNames::GAME).is_some() {
         if let MusicState::Fight { instance, idx } = &mut *music_state {
             if let PlaybackState::Stopped = instance.state() {
                 *idx += 1;
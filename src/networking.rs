@@ -0,0 +1,453 @@
+//! Online match networking.
+//!
+//! Players find each other through a matchmaking server: one player hosts a lobby and the others
+//! join it using the lobby code. Once the host starts the match, the connection to the matchmaker
+//! becomes a [`NetworkMatchSocket`], which relays reliable messages between the players while the
+//! match is being set up.
+//!
+//! The protocol is a stream of length prefixed YAML messages over TCP. The [`matchmaker`] module
+//! contains a minimal server implementation, which can be run locally with the `jumpy-matchmaker`
+//! binary.
+
+use std::{
+    io::{self, Read, Write},
+    net::{IpAddr, Shutdown, TcpStream},
+};
+
+use async_channel::{Receiver, Sender};
+use serde::de::DeserializeOwned;
+
+use crate::prelude::*;
+
+pub mod matchmaker;
+pub mod rollback;
+
+/// The maximum size of a single message, so that we don't allocate huge buffers for garbage data.
+const MAX_MESSAGE_SIZE: u32 = 64 * 1024;
+
+/// The code that players use to join a lobby.
+pub type LobbyId = u32;
+
+/// A message sent from a client to the matchmaking server.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ClientMessage {
+    /// Create a new lobby and join it as the host.
+    HostLobby { max_players: u32 },
+    /// Join an existing lobby.
+    JoinLobby { lobby: LobbyId },
+    /// Start the match with the players currently in the lobby. Only the host may do this.
+    StartMatch,
+    /// Send a message to other players in the match.
+    Relay { target: SocketTarget, data: Vec<u8> },
+}
+
+/// A message sent from the matchmaking server to a client.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ServerMessage {
+    /// The client has joined a lobby, or its player index in the lobby has changed.
+    LobbyJoined { lobby: LobbyId, player_idx: u32 },
+    /// The number of players in the lobby has changed.
+    LobbyUpdated { player_count: u32, max_players: u32 },
+    /// The host has started the match.
+    MatchStarted {
+        player_idx: u32,
+        /// The IP address of every player in the match, as seen by the server.
+        player_ips: Vec<IpAddr>,
+    },
+    /// A message from another player in the match.
+    Relay { from: u32, data: Vec<u8> },
+    /// Another player in the match has disconnected.
+    PlayerDisconnected { player_idx: u32 },
+    /// The last request could not be handled, or the connection failed.
+    Error(String),
+}
+
+/// The players that a message should be sent to.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SocketTarget {
+    /// Send to a single player.
+    Player(u32),
+    /// Send to every other player.
+    All,
+}
+
+/// Network message that may be sent while setting up a match.
+///
+/// Asset handles aren't the same on every machine, so players, hats, and maps are referred to by
+/// their index in [`CoreMeta`] or their name.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum MatchSetupMessage {
+    /// Index into [`CoreMeta::players`].
+    SelectPlayer(usize),
+    /// Index into [`CoreMeta::player_hats`].
+    SelectHat(Option<usize>),
+    ConfirmSelection(bool),
+    /// Sent by the host once the map has been selected, which starts the match.
+    SelectMap {
+        map: String,
+        seed: u64,
+    },
+}
+
+/// Write a length prefixed message to a stream.
+fn write_message<T: Serialize>(stream: &mut impl Write, message: &T) -> io::Result<()> {
+    let data = serde_yaml::to_string(message)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    stream.write_all(&(data.len() as u32).to_le_bytes())?;
+    stream.write_all(data.as_bytes())?;
+    stream.flush()
+}
+
+/// Read a length prefixed message from a stream.
+fn read_message<T: DeserializeOwned>(stream: &mut impl Read) -> io::Result<T> {
+    let mut len = [0; 4];
+    stream.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len);
+    if len > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Message of {len} bytes is too large"),
+        ));
+    }
+    let mut data = vec![0; len as usize];
+    stream.read_exact(&mut data)?;
+    serde_yaml::from_slice(&data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Spawn the threads that write the messages from `outgoing` to the stream, and send the messages
+/// read from the stream to `incoming`.
+///
+/// The connection is shut down once `outgoing` is closed, and `incoming` is closed once the
+/// connection is.
+fn spawn_connection<Out, In>(
+    stream: TcpStream,
+    outgoing: Receiver<Out>,
+    incoming: Sender<In>,
+) -> io::Result<()>
+where
+    Out: Serialize + Send + 'static,
+    In: DeserializeOwned + Send + 'static,
+{
+    stream.set_nodelay(true)?;
+    let mut reader = stream.try_clone()?;
+    let mut writer = stream;
+
+    std::thread::spawn(move || {
+        while let Ok(message) = outgoing.recv_blocking() {
+            if let Err(e) = write_message(&mut writer, &message) {
+                debug!("Error writing network message: {e}");
+                break;
+            }
+        }
+        writer.shutdown(Shutdown::Both).ok();
+    });
+
+    std::thread::spawn(move || loop {
+        match read_message(&mut reader) {
+            Ok(message) => {
+                if incoming.send_blocking(message).is_err() {
+                    break;
+                }
+            }
+            Err(e) => {
+                if e.kind() != io::ErrorKind::UnexpectedEof {
+                    debug!("Error reading network message: {e}");
+                }
+                break;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// A connection to the matchmaking server.
+///
+/// Connecting happens in the background, so messages may be sent right away. If the connection
+/// fails, a [`ServerMessage::Error`] is received.
+#[derive(Clone, Debug)]
+pub struct LobbyClient {
+    sender: Sender<ClientMessage>,
+    receiver: Receiver<ServerMessage>,
+}
+
+impl LobbyClient {
+    /// Connect to the matchmaking server at the given address.
+    pub fn connect(server: &str) -> Self {
+        let (sender, outgoing) = async_channel::unbounded();
+        let (incoming, receiver) = async_channel::unbounded();
+
+        let server = server.to_string();
+        std::thread::spawn(move || {
+            let result = TcpStream::connect(&server)
+                .and_then(|stream| spawn_connection(stream, outgoing, incoming.clone()));
+            if let Err(e) = result {
+                incoming
+                    .send_blocking(ServerMessage::Error(format!(
+                        "Could not connect to `{server}`: {e}"
+                    )))
+                    .ok();
+            }
+        });
+
+        Self { sender, receiver }
+    }
+
+    /// Send a message to the matchmaking server.
+    pub fn send(&self, message: ClientMessage) {
+        self.sender.try_send(message).ok();
+    }
+
+    /// Get the next message from the matchmaking server, if one has arrived.
+    pub fn try_recv(&self) -> Option<ServerMessage> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Whether or not the connection has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed() || (self.receiver.is_closed() && self.receiver.is_empty())
+    }
+
+    /// Close the connection.
+    pub fn close(&self) {
+        self.sender.close();
+    }
+}
+
+/// The connection to the other players in an online match.
+///
+/// This is inserted as a resource into the game session of online matches.
+#[derive(HasSchema, Clone, Debug)]
+#[schema(no_default)]
+pub struct NetworkMatchSocket {
+    client: LobbyClient,
+    player_idx: u32,
+    player_ips: Vec<IpAddr>,
+}
+
+impl NetworkMatchSocket {
+    /// Create a socket from a lobby connection, once the match has started.
+    pub fn new(client: LobbyClient, player_idx: u32, player_ips: Vec<IpAddr>) -> Self {
+        Self {
+            client,
+            player_idx,
+            player_ips,
+        }
+    }
+
+    /// The index of the local player.
+    pub fn player_idx(&self) -> u32 {
+        self.player_idx
+    }
+
+    /// The number of players in the match.
+    pub fn player_count(&self) -> u32 {
+        self.player_ips.len() as u32
+    }
+
+    /// The IP address of a player, used to connect to them directly.
+    pub fn player_ip(&self, player_idx: u32) -> Option<IpAddr> {
+        self.player_ips.get(player_idx as usize).copied()
+    }
+
+    /// Reliably send a message to other players.
+    pub fn send_reliable(&self, target: SocketTarget, data: &[u8]) {
+        self.client.send(ClientMessage::Relay {
+            target,
+            data: data.to_vec(),
+        });
+    }
+
+    /// Get the reliable messages that have arrived from other players.
+    pub fn recv_reliable(&self) -> Vec<(u32, Vec<u8>)> {
+        let mut messages = Vec::new();
+        while let Some(message) = self.client.try_recv() {
+            match message {
+                ServerMessage::Relay { from, data } => messages.push((from, data)),
+                ServerMessage::PlayerDisconnected { player_idx } => {
+                    warn!("Player {} disconnected from the match", player_idx + 1)
+                }
+                ServerMessage::Error(e) => warn!("Network error: {e}"),
+                _ => (),
+            }
+        }
+        messages
+    }
+
+    /// Serialize and reliably send a message to other players.
+    pub fn send_message<T: Serialize>(&self, target: SocketTarget, message: &T) {
+        match serde_yaml::to_string(message) {
+            Ok(data) => self.send_reliable(target, data.as_bytes()),
+            Err(e) => error!("Could not serialize network message: {e}"),
+        }
+    }
+
+    /// Get and deserialize the messages that have arrived from other players.
+    pub fn recv_messages<T: DeserializeOwned>(&self) -> Vec<(u32, T)> {
+        self.recv_reliable()
+            .into_iter()
+            .filter_map(|(from, data)| match serde_yaml::from_slice(&data) {
+                Ok(message) => Some((from, message)),
+                Err(e) => {
+                    warn!("Ignoring network message that was not understood: {e}");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Close the connection.
+    pub fn close(&self) {
+        self.client.close();
+    }
+}
+
+/// Start a match with the given number of players through a matchmaking server on the loopback
+/// interface, returning the socket of every player.
+#[cfg(test)]
+pub fn start_loopback_match(player_count: u32) -> Vec<NetworkMatchSocket> {
+    use std::time::{Duration, Instant};
+
+    /// Wait for a message from the server that the function returns a value for.
+    fn wait_for<T>(client: &LobbyClient, mut f: impl FnMut(ServerMessage) -> Option<T>) -> T {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some(value) = client.try_recv().and_then(&mut f) {
+                return value;
+            }
+            assert!(Instant::now() < deadline, "Timed out waiting for message");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let server = listener.local_addr().unwrap().to_string();
+    std::thread::spawn(move || matchmaker::serve(listener));
+
+    let clients = (0..player_count)
+        .map(|_| LobbyClient::connect(&server))
+        .collect::<Vec<_>>();
+    clients[0].send(ClientMessage::HostLobby {
+        max_players: player_count,
+    });
+    let lobby = wait_for(&clients[0], |message| match message {
+        ServerMessage::LobbyJoined { lobby, .. } => Some(lobby),
+        _ => None,
+    });
+    for client in &clients[1..] {
+        client.send(ClientMessage::JoinLobby { lobby });
+        wait_for(client, |message| {
+            matches!(message, ServerMessage::LobbyJoined { .. }).then_some(())
+        });
+    }
+    wait_for(&clients[0], |message| match message {
+        ServerMessage::LobbyUpdated {
+            player_count: count,
+            ..
+        } => (count == player_count).then_some(()),
+        _ => None,
+    });
+
+    clients[0].send(ClientMessage::StartMatch);
+    clients
+        .into_iter()
+        .map(|client| {
+            let (player_idx, player_ips) = wait_for(&client, |message| match message {
+                ServerMessage::MatchStarted {
+                    player_idx,
+                    player_ips,
+                } => Some((player_idx, player_ips)),
+                _ => None,
+            });
+            NetworkMatchSocket::new(client, player_idx, player_ips)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        net::TcpListener,
+        time::{Duration, Instant},
+    };
+
+    use super::*;
+
+    /// Wait for the next message from the server.
+    fn recv(client: &LobbyClient) -> ServerMessage {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some(message) = client.try_recv() {
+                return message;
+            }
+            assert!(Instant::now() < deadline, "Timed out waiting for message");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn host_join_and_relay_on_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = listener.local_addr().unwrap().to_string();
+        std::thread::spawn(move || matchmaker::serve(listener));
+
+        let host = LobbyClient::connect(&server);
+        host.send(ClientMessage::HostLobby { max_players: 2 });
+        let ServerMessage::LobbyJoined { lobby, player_idx } = recv(&host) else {
+            panic!("Expected to join the lobby");
+        };
+        assert_eq!(player_idx, 0);
+        assert_eq!(
+            recv(&host),
+            ServerMessage::LobbyUpdated {
+                player_count: 1,
+                max_players: 2
+            }
+        );
+
+        let guest = LobbyClient::connect(&server);
+        guest.send(ClientMessage::JoinLobby { lobby });
+        assert_eq!(
+            recv(&guest),
+            ServerMessage::LobbyJoined {
+                lobby,
+                player_idx: 1
+            }
+        );
+        for client in [&host, &guest] {
+            assert_eq!(
+                recv(client),
+                ServerMessage::LobbyUpdated {
+                    player_count: 2,
+                    max_players: 2
+                }
+            );
+        }
+
+        host.send(ClientMessage::StartMatch);
+        let loopback = IpAddr::from([127, 0, 0, 1]);
+        for (i, client) in [&host, &guest].into_iter().enumerate() {
+            assert_eq!(
+                recv(client),
+                ServerMessage::MatchStarted {
+                    player_idx: i as u32,
+                    player_ips: vec![loopback; 2],
+                }
+            );
+        }
+
+        let host = NetworkMatchSocket::new(host, 0, vec![loopback; 2]);
+        host.send_message(
+            SocketTarget::All,
+            &MatchSetupMessage::ConfirmSelection(true),
+        );
+        let ServerMessage::Relay { from, data } = recv(&guest) else {
+            panic!("Expected a relayed message");
+        };
+        assert_eq!(from, 0);
+        assert_eq!(
+            serde_yaml::from_slice::<MatchSetupMessage>(&data).unwrap(),
+            MatchSetupMessage::ConfirmSelection(true)
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! A minimal matchmaking server.
+//!
+//! This keeps track of the lobbies, starts matches, and relays messages between the players of a
+//! match. It is meant for playing on a LAN and for testing online play on loopback.
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+};
+
+use super::*;
+
+/// A player connected to a lobby.
+struct LobbyMember {
+    /// Unique identifier of the member's connection.
+    connection: u64,
+    /// The IP address that the member connected from.
+    ip: IpAddr,
+    /// Channel to the member's connection, or [`None`] if the member disconnected after the match
+    /// started.
+    sender: Option<Sender<ServerMessage>>,
+}
+
+struct Lobby {
+    max_players: u32,
+    started: bool,
+    /// The members of the lobby, in player order. The first member is the host.
+    members: Vec<LobbyMember>,
+}
+
+impl Lobby {
+    fn player_idx(&self, connection: u64) -> Option<u32> {
+        self.members
+            .iter()
+            .position(|x| x.connection == connection)
+            .map(|x| x as u32)
+    }
+
+    fn send(&self, player_idx: u32, message: ServerMessage) {
+        if let Some(sender) = self
+            .members
+            .get(player_idx as usize)
+            .and_then(|x| x.sender.as_ref())
+        {
+            sender.try_send(message).ok();
+        }
+    }
+
+    fn broadcast(&self, message: ServerMessage) {
+        for i in 0..self.members.len() {
+            self.send(i as u32, message.clone());
+        }
+    }
+
+    fn broadcast_update(&self) {
+        self.broadcast(ServerMessage::LobbyUpdated {
+            player_count: self.members.len() as u32,
+            max_players: self.max_players,
+        });
+    }
+}
+
+#[derive(Default)]
+struct Lobbies {
+    lobbies: HashMap<LobbyId, Lobby>,
+    next_connection: u64,
+}
+
+/// Accept and serve clients on the listener forever.
+pub fn serve(listener: TcpListener) {
+    let lobbies = Arc::new(Mutex::new(Lobbies::default()));
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let lobbies = lobbies.clone();
+                std::thread::spawn(move || handle_client(stream, lobbies));
+            }
+            Err(e) => warn!("Error accepting connection: {e}"),
+        }
+    }
+}
+
+fn handle_client(stream: TcpStream, lobbies: Arc<Mutex<Lobbies>>) {
+    let ip = match stream.peer_addr() {
+        Ok(addr) => addr.ip(),
+        Err(e) => {
+            warn!("Error starting connection: {e}");
+            return;
+        }
+    };
+    let connection = {
+        let mut state = lobbies.lock().unwrap();
+        state.next_connection += 1;
+        state.next_connection
+    };
+    let (sender, outgoing) = async_channel::unbounded();
+    let (incoming, receiver) = async_channel::unbounded();
+    if let Err(e) = spawn_connection(stream, outgoing, incoming) {
+        warn!("Error starting connection: {e}");
+        return;
+    }
+    let error = |message: &str| {
+        sender
+            .try_send(ServerMessage::Error(message.to_string()))
+            .ok();
+    };
+
+    // The lobby that the client is a member of.
+    let mut current_lobby = None;
+
+    while let Ok(message) = receiver.recv_blocking() {
+        let mut state = lobbies.lock().unwrap();
+        let lobbies = &mut state.lobbies;
+
+        match message {
+            ClientMessage::HostLobby { max_players } => {
+                if current_lobby.is_some() {
+                    error("Already in a lobby");
+                    continue;
+                }
+                if !(2..=MAX_PLAYERS as u32).contains(&max_players) {
+                    error("Invalid player count");
+                    continue;
+                }
+
+                let id = loop {
+                    let id = THREAD_RNG.with(|rng| rng.u32(1000..10000));
+                    if !lobbies.contains_key(&id) {
+                        break id;
+                    }
+                };
+                let lobby = Lobby {
+                    max_players,
+                    started: false,
+                    members: vec![LobbyMember {
+                        connection,
+                        ip,
+                        sender: Some(sender.clone()),
+                    }],
+                };
+                lobby.send(
+                    0,
+                    ServerMessage::LobbyJoined {
+                        lobby: id,
+                        player_idx: 0,
+                    },
+                );
+                lobby.broadcast_update();
+                lobbies.insert(id, lobby);
+                current_lobby = Some(id);
+            }
+            ClientMessage::JoinLobby { lobby: id } => {
+                if current_lobby.is_some() {
+                    error("Already in a lobby");
+                    continue;
+                }
+                let Some(lobby) = lobbies.get_mut(&id) else {
+                    error("Lobby not found");
+                    continue;
+                };
+                if lobby.started || lobby.members.len() as u32 >= lobby.max_players {
+                    error("Lobby is full");
+                    continue;
+                }
+
+                lobby.members.push(LobbyMember {
+                    connection,
+                    ip,
+                    sender: Some(sender.clone()),
+                });
+                let player_idx = lobby.members.len() as u32 - 1;
+                lobby.send(
+                    player_idx,
+                    ServerMessage::LobbyJoined {
+                        lobby: id,
+                        player_idx,
+                    },
+                );
+                lobby.broadcast_update();
+                current_lobby = Some(id);
+            }
+            ClientMessage::StartMatch => {
+                let Some(lobby) = current_lobby.and_then(|id| lobbies.get_mut(&id)) else {
+                    error("Not in a lobby");
+                    continue;
+                };
+                if lobby.player_idx(connection) != Some(0) {
+                    error("Only the host may start the match");
+                    continue;
+                }
+                if lobby.started || lobby.members.len() < 2 {
+                    error("Not enough players to start the match");
+                    continue;
+                }
+
+                lobby.started = true;
+                let player_ips = lobby.members.iter().map(|x| x.ip).collect::<Vec<_>>();
+                for player_idx in 0..player_ips.len() as u32 {
+                    lobby.send(
+                        player_idx,
+                        ServerMessage::MatchStarted {
+                            player_idx,
+                            player_ips: player_ips.clone(),
+                        },
+                    );
+                }
+            }
+            ClientMessage::Relay { target, data } => {
+                let Some(lobby) = current_lobby.and_then(|id| lobbies.get(&id)) else {
+                    continue;
+                };
+                let Some(from) = lobby.player_idx(connection) else {
+                    continue;
+                };
+                if !lobby.started {
+                    continue;
+                }
+
+                let message = ServerMessage::Relay { from, data };
+                match target {
+                    SocketTarget::Player(player_idx) => lobby.send(player_idx, message),
+                    SocketTarget::All => {
+                        for player_idx in (0..lobby.members.len() as u32).filter(|&x| x != from) {
+                            lobby.send(player_idx, message.clone());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // The client has disconnected, so remove it from its lobby.
+    let Some(id) = current_lobby else {
+        return;
+    };
+    let mut state = lobbies.lock().unwrap();
+    let Some(lobby) = state.lobbies.get_mut(&id) else {
+        return;
+    };
+    let Some(player_idx) = lobby.player_idx(connection) else {
+        return;
+    };
+
+    if lobby.started {
+        // Keep the player indexes of the match stable.
+        lobby.members[player_idx as usize].sender = None;
+        lobby.broadcast(ServerMessage::PlayerDisconnected { player_idx });
+    } else {
+        lobby.members.remove(player_idx as usize);
+        // Let the players after the removed one know about their new index.
+        for i in player_idx..lobby.members.len() as u32 {
+            lobby.send(
+                i,
+                ServerMessage::LobbyJoined {
+                    lobby: id,
+                    player_idx: i,
+                },
+            );
+        }
+        lobby.broadcast_update();
+    }
+
+    if lobby.members.iter().all(|x| x.sender.is_none()) {
+        state.lobbies.remove(&id);
+    }
+}
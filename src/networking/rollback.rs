@@ -0,0 +1,804 @@
+//! Peer-to-peer rollback networking.
+//!
+//! Every player simulates the whole match. Each frame, the players send their local input directly
+//! to each other over UDP. When the input of a remote player hasn't arrived yet, it is predicted by
+//! assuming that they keep holding the same buttons. Once the real input arrives and the
+//! prediction turns out to be wrong, the world is restored from the [`SnapshotHistory`] and the
+//! frames since then are simulated again.
+//!
+//! Once the input of every player is known for a frame, its [`StateChecksum`] is recorded and
+//! compared with the checksums of the other players, so that desyncs are noticed on the frame that
+//! they happen.
+//!
+//! When a player times out, they are dropped from the match and their input is neutral from then
+//! on. Every packet lists the players that the sender has dropped, along with the first frame
+//! without their input, and everyone uses the earliest of those frames, rolling back if they
+//! already simulated it with the input of the dropped player. That way every player switches to
+//! the neutral input on the same frame, no matter when they noticed the timeout.
+//!
+//! The end of a round is only acted on once it is confirmed, so that every player moves on to the
+//! next round after the same frame. No frames are simulated after the round has ended, and frame
+//! numbers keep counting up in the next round, so that inputs that are still on their way from the
+//! previous round can't be mistaken for inputs of the new one.
+//!
+//! The UDP ports are exchanged through the [`NetworkMatchSocket`], and the IP addresses are the
+//! ones that the matchmaking server saw, so the players must be able to reach each other directly,
+//! e.g. on the same LAN.
+
+use std::{
+    collections::BTreeMap,
+    io,
+    net::{SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+use super::{NetworkMatchSocket, SocketTarget};
+use crate::{prelude::*, settings::PlayerControlMapping};
+
+/// The number of frames that local input is delayed by, to give it time to reach the other
+/// players before it is needed.
+const INPUT_DELAY: u32 = 2;
+
+/// The maximum number of frames that we simulate ahead of the last frame for which the input of
+/// every player is known. If we get further ahead than this we wait for the other players.
+const MAX_PREDICTION: u32 = 8;
+
+/// The maximum number of inputs sent in a single packet.
+const MAX_PACKET_INPUTS: u32 = 16;
+
+/// The maximum size of a received packet.
+const MAX_PACKET_SIZE: usize = 64 * 1024;
+
+/// The number of runner steps between re-sending our UDP port while waiting for the other players.
+const HANDSHAKE_INTERVAL: u32 = 30;
+
+/// How long to wait for packets from a player before considering them disconnected.
+const PEER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The number of frames to keep checksums for.
+const CHECKSUM_HISTORY: u32 = 2 * FPS as u32;
+
+/// Message sent over the [`NetworkMatchSocket`] to set up the UDP connections.
+#[derive(Serialize, Deserialize, Debug)]
+enum RollbackSetupMessage {
+    /// The UDP port that the sender receives inputs on.
+    UdpPort(u16),
+}
+
+/// Packet sent to each of the other players every frame, encoded with [`postcard`] to keep it well
+/// below the size of a single UDP datagram.
+#[derive(Serialize, Deserialize, Debug)]
+struct InputPacket {
+    /// The player that sent the packet.
+    player_idx: u32,
+    /// The frame of the first input in `inputs`.
+    start_frame: u32,
+    /// The sender's inputs for consecutive frames, starting at `start_frame`.
+    inputs: Vec<PlayerControl>,
+    /// The sender has received the receiver's inputs for every frame before this one.
+    received: u32,
+    /// The checksum of the latest frame that the sender has simulated with confirmed inputs.
+    checksum: Option<(u32, u64)>,
+    /// The players that the sender has dropped from the match, and the first frame without their
+    /// input.
+    dropped: Vec<(u32, u32)>,
+}
+
+/// A player in the match, including the local one.
+struct Peer {
+    /// The address to send packets to, once known.
+    addr: Option<SocketAddr>,
+    /// The last time that we received a packet from the player.
+    last_heard: Option<Instant>,
+    /// If the player has been dropped from the match, the first frame without their input.
+    dropped_from: Option<u32>,
+    /// The confirmed inputs of the player by frame.
+    inputs: BTreeMap<u32, PlayerControl>,
+    /// The predicted inputs that frames have been simulated with, while the real input was
+    /// missing.
+    predictions: BTreeMap<u32, PlayerControl>,
+    /// We have received the player's inputs for every frame before this one.
+    received: u32,
+    /// The player has received our inputs for every frame before this one.
+    acked: u32,
+    /// The checksums that the player has sent us, by frame.
+    checksums: BTreeMap<u32, u64>,
+}
+
+impl Default for Peer {
+    fn default() -> Self {
+        Self {
+            addr: None,
+            last_heard: None,
+            dropped_from: None,
+            // Nobody has any input for the first frames, because of the input delay.
+            inputs: (0..INPUT_DELAY).map(|frame| (frame, default())).collect(),
+            predictions: default(),
+            received: INPUT_DELAY,
+            acked: 0,
+            checksums: default(),
+        }
+    }
+}
+
+impl Peer {
+    /// Get the input of the player for a frame, predicting it if it hasn't arrived yet.
+    ///
+    /// Once the player has been dropped from the match, their input is neutral.
+    fn input(&mut self, frame: u32) -> PlayerControl {
+        if let Some(input) = self.inputs.get(&frame) {
+            return *input;
+        }
+        if self.dropped_from.is_some_and(|x| frame >= x) {
+            return default();
+        }
+        let prediction = self
+            .inputs
+            .range(..frame)
+            .next_back()
+            .map(|(_, last)| predict(last))
+            .unwrap_or_default();
+        self.predictions.insert(frame, prediction);
+        prediction
+    }
+}
+
+/// Get the earlier of two optional frames.
+fn earliest(a: Option<u32>, b: Option<u32>) -> Option<u32> {
+    a.into_iter().chain(b).min()
+}
+
+/// Predict the next input of a player, assuming they keep holding the same buttons.
+fn predict(last: &PlayerControl) -> PlayerControl {
+    PlayerControl {
+        just_moved: false,
+        menu_back_just_pressed: false,
+        menu_confirm_just_pressed: false,
+        menu_start_just_pressed: false,
+        pause_just_pressed: false,
+        jump_just_pressed: false,
+        shoot_just_pressed: false,
+        grab_just_pressed: false,
+        slide_just_pressed: false,
+        ..*last
+    }
+}
+
+/// Match runner for online matches, which exchanges inputs with the other players and rolls back
+/// when a prediction of their input was wrong.
+pub struct NetworkMatchRunner {
+    /// Collects the local input, like the one of [`crate::core::JumpyDefaultMatchRunner`].
+    input_collector: PlayerInputCollector,
+    accumulator: f64,
+    last_run: Option<Instant>,
+    network_socket: NetworkMatchSocket,
+    udp_socket: UdpSocket,
+    local_player: u32,
+    /// Every player in the match, indexed by player.
+    peers: Vec<Peer>,
+    /// The next frame to simulate.
+    frame: u32,
+    /// Snapshots from before each frame that may still have to be simulated again.
+    history: SnapshotHistory,
+    /// Our checksums of the frames simulated with confirmed inputs.
+    checksums: BTreeMap<u32, u64>,
+    /// The checksums of every frame before this one have been recorded.
+    checksummed: u32,
+    /// The round of the match that is being simulated, to notice when the runner has been carried
+    /// over into the session of the next round.
+    round: Option<u32>,
+    /// The first frame where our checksum differed from another player's.
+    desync_frame: Option<u32>,
+    /// Steps until the UDP port is sent again, while waiting for the other players.
+    handshake_timer: u32,
+}
+
+impl NetworkMatchRunner {
+    /// Create a runner that plays the match with the players connected to the socket.
+    pub fn new(network_socket: NetworkMatchSocket) -> io::Result<Self> {
+        let udp_socket = UdpSocket::bind("0.0.0.0:0")?;
+        udp_socket.set_nonblocking(true)?;
+
+        let local_player = network_socket.player_idx();
+        let mut peers = (0..network_socket.player_count())
+            .map(|_| Peer::default())
+            .collect::<Vec<_>>();
+        peers[local_player as usize].last_heard = Some(Instant::now());
+
+        Ok(Self {
+            input_collector: default(),
+            accumulator: 0.0,
+            last_run: None,
+            network_socket,
+            udp_socket,
+            local_player,
+            peers,
+            frame: 0,
+            history: SnapshotHistory::new(MAX_PREDICTION as usize + 1),
+            checksums: default(),
+            checksummed: 0,
+            round: None,
+            desync_frame: None,
+            handshake_timer: 0,
+        })
+    }
+
+    /// Whether or not we have heard from every other player, so that the match can start.
+    fn is_connected(&self) -> bool {
+        self.peers.iter().all(|x| x.last_heard.is_some())
+    }
+
+    /// Every frame before this one has confirmed input from every connected player.
+    fn confirmed_frames(&self) -> u32 {
+        self.peers
+            .iter()
+            .filter(|x| x.dropped_from.is_none())
+            .map(|x| x.received)
+            .min()
+            .unwrap_or(self.frame)
+    }
+
+    /// Handle the messages from the other players.
+    ///
+    /// Returns the first frame that was simulated with a wrong prediction, if any.
+    fn receive(&mut self) -> Option<u32> {
+        for (from, message) in self.network_socket.recv_messages::<RollbackSetupMessage>() {
+            let RollbackSetupMessage::UdpPort(port) = message;
+            if let (Some(peer), Some(ip)) = (
+                self.peers.get_mut(from as usize),
+                self.network_socket.player_ip(from),
+            ) {
+                peer.addr.get_or_insert(SocketAddr::new(ip, port));
+            }
+        }
+
+        let mut rollback_frame = None;
+        let mut buffer = vec![0; MAX_PACKET_SIZE];
+        loop {
+            let (len, addr) = match self.udp_socket.recv_from(&mut buffer) {
+                Ok(x) => x,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    debug!("Error receiving packet: {e}");
+                    continue;
+                }
+            };
+            let packet = match postcard::from_bytes::<InputPacket>(&buffer[..len]) {
+                Ok(packet) => packet,
+                Err(e) => {
+                    debug!("Ignoring invalid packet from {addr}: {e}");
+                    continue;
+                }
+            };
+            if packet.player_idx == self.local_player {
+                continue;
+            }
+            let Some(peer) = self.peers.get_mut(packet.player_idx as usize) else {
+                continue;
+            };
+            if peer.dropped_from.is_some() {
+                continue;
+            }
+
+            // Reply to wherever the packets come from, in case that's different from the address
+            // that the matchmaker saw.
+            peer.addr = Some(addr);
+            peer.last_heard = Some(Instant::now());
+            peer.acked = peer.acked.max(packet.received);
+
+            for (frame, input) in (packet.start_frame..).zip(packet.inputs) {
+                if frame < peer.received {
+                    continue;
+                } else if frame > peer.received {
+                    break;
+                }
+                peer.inputs.insert(frame, input);
+                peer.received += 1;
+
+                if let Some(prediction) = peer.predictions.remove(&frame) {
+                    if prediction != input {
+                        rollback_frame = earliest(rollback_frame, Some(frame));
+                    }
+                }
+            }
+
+            // The checksum may have been taken without the input of a dropped player, so the drops
+            // are applied before it is compared.
+            for (player, frame) in packet.dropped {
+                rollback_frame = earliest(rollback_frame, self.drop_player(player, frame));
+            }
+
+            if let Some((frame, checksum)) = packet.checksum {
+                self.peers[packet.player_idx as usize]
+                    .checksums
+                    .insert(frame, checksum);
+                self.check_desync(frame);
+            }
+        }
+
+        let now = Instant::now();
+        let timed_out = self
+            .peers
+            .iter()
+            .enumerate()
+            .filter(|(i, peer)| {
+                *i as u32 != self.local_player
+                    && peer.dropped_from.is_none()
+                    && peer.last_heard.is_some_and(|x| now - x > PEER_TIMEOUT)
+            })
+            .map(|(i, peer)| (i as u32, peer.received))
+            .collect::<Vec<_>>();
+        for (player, received) in timed_out {
+            warn!("Player {} timed out", player + 1);
+            rollback_frame = earliest(rollback_frame, self.drop_player(player, received));
+        }
+
+        rollback_frame
+    }
+
+    /// Drop a player from the match, using neutral input for them from the given frame on.
+    ///
+    /// If the player has already been dropped from a later frame, the drop is moved to the
+    /// earlier one, so that every player ends up using the same frame. The frame is never later
+    /// than the input that we have received from the player.
+    ///
+    /// Returns the frame to roll back to, if frames from then on were simulated with the input of
+    /// the player.
+    fn drop_player(&mut self, player: u32, frame: u32) -> Option<u32> {
+        if player == self.local_player {
+            return None;
+        }
+        let peer = self.peers.get_mut(player as usize)?;
+        let frame = frame.min(peer.received);
+        if peer.dropped_from.is_some_and(|x| x <= frame) {
+            return None;
+        }
+        if peer.dropped_from.is_none() {
+            warn!(
+                "Player {} disconnected, continuing without their input from frame {frame}",
+                player + 1
+            );
+        }
+        peer.dropped_from = Some(frame);
+        peer.inputs.retain(|x, _| *x < frame);
+        peer.predictions.retain(|x, _| *x < frame);
+
+        // The checksums from then on may have been taken with the input of the player.
+        self.checksums.retain(|x, _| *x < frame);
+        for peer in &mut self.peers {
+            peer.checksums.retain(|x, _| *x < frame);
+        }
+        self.checksummed = self.checksummed.min(frame);
+
+        (frame < self.frame).then_some(frame)
+    }
+
+    /// Send our inputs, the latest checksum and the dropped players to the other players.
+    fn send(&self) {
+        let local = &self.peers[self.local_player as usize];
+        let checksum = self.checksums.last_key_value().map(|(f, c)| (*f, *c));
+        let dropped = self
+            .peers
+            .iter()
+            .enumerate()
+            .filter_map(|(i, peer)| Some((i as u32, peer.dropped_from?)))
+            .collect::<Vec<_>>();
+
+        for peer in &self.peers {
+            let Some(addr) = peer.addr else {
+                continue;
+            };
+            if peer.dropped_from.is_some() {
+                continue;
+            }
+            let start_frame = peer.acked;
+            let end_frame = local.received.min(start_frame + MAX_PACKET_INPUTS);
+            let packet = InputPacket {
+                player_idx: self.local_player,
+                start_frame,
+                inputs: (start_frame..end_frame)
+                    .filter_map(|frame| local.inputs.get(&frame).copied())
+                    .collect(),
+                received: peer.received,
+                checksum,
+                dropped: dropped.clone(),
+            };
+
+            match postcard::to_allocvec(&packet) {
+                Ok(data) => {
+                    if let Err(e) = self.udp_socket.send_to(&data, addr) {
+                        if e.kind() != io::ErrorKind::WouldBlock {
+                            debug!("Error sending packet to {addr}: {e}");
+                        }
+                    }
+                }
+                Err(e) => error!("Could not serialize input packet: {e}"),
+            }
+        }
+    }
+
+    /// Compare our checksum for a frame with the ones from the other players.
+    fn check_desync(&mut self, frame: u32) {
+        if self.desync_frame.is_some() {
+            return;
+        }
+        let Some(checksum) = self.checksums.get(&frame) else {
+            return;
+        };
+        for (i, peer) in self.peers.iter().enumerate() {
+            if let Some(remote) = peer.checksums.get(&frame) {
+                if remote != checksum {
+                    error!(
+                        "Desync detected on frame {frame}: player {} has checksum {remote:016x}, \
+                        ours is {checksum:016x}",
+                        i + 1
+                    );
+                    self.desync_frame = Some(frame);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Simulate the next frame with the inputs that we have.
+    fn simulate_frame(&mut self, world: &mut World, stages: &mut SystemStages, resimulating: bool) {
+        pub const STEP: f64 = 1.0 / FPS as f64;
+        let frame = self.frame;
+        self.history.capture(frame, world);
+
+        world
+            .resource_mut::<Time>()
+            .advance_exact(Duration::from_secs_f64(STEP));
+
+        {
+            let mut player_inputs = world.resource_mut::<MatchInputs>();
+            for (i, peer) in self.peers.iter_mut().enumerate() {
+                player_inputs.players[i].control = peer.input(frame);
+            }
+            world
+                .resource_mut::<ReplayRecorder>()
+                .record(&player_inputs);
+        }
+
+        world.insert_resource(Resimulating(resimulating));
+        stages.run(world);
+        self.frame += 1;
+    }
+
+    /// Record and compare the checksums of the frames that have been confirmed since the last
+    /// time.
+    ///
+    /// Frames that were simulated with a prediction that turned out to be right aren't simulated
+    /// again, so the state at the end of them is taken from the snapshot of the next frame.
+    fn record_checksums(&mut self, world: &World) {
+        let confirmed = self.confirmed_frames().min(self.frame);
+        while self.checksummed < confirmed {
+            let frame = self.checksummed;
+            self.checksummed += 1;
+
+            let checksum = if frame + 1 == self.frame {
+                Some(world.resource::<StateChecksum>().value)
+            } else {
+                self.history
+                    .get(frame + 1)
+                    .map(|x| x.world().resource::<StateChecksum>().value)
+            };
+            let Some(checksum) = checksum else {
+                debug!("Could not record the checksum of frame {frame}, the snapshot is missing");
+                continue;
+            };
+            self.checksums.insert(frame, checksum);
+            self.check_desync(frame);
+        }
+    }
+
+    /// Drop the inputs and checksums that won't be needed anymore.
+    fn prune(&mut self) {
+        let oldest_needed = self
+            .peers
+            .iter()
+            .enumerate()
+            .filter(|(i, x)| *i as u32 != self.local_player && x.dropped_from.is_none())
+            .map(|(_, x)| x.acked)
+            .chain([self.confirmed_frames()])
+            .chain(self.history.oldest_frame())
+            .min()
+            .unwrap_or(self.frame)
+            // Keep the last input before that around for predictions.
+            .saturating_sub(1);
+        let oldest_checksum = self.frame.saturating_sub(CHECKSUM_HISTORY);
+
+        for peer in &mut self.peers {
+            peer.inputs = peer.inputs.split_off(&oldest_needed);
+            peer.predictions = peer.predictions.split_off(&oldest_needed);
+            peer.checksums = peer.checksums.split_off(&oldest_checksum);
+        }
+        self.checksums = self.checksums.split_off(&oldest_checksum);
+    }
+
+    /// Exchange inputs with the other players, roll back if needed, and simulate the frames that
+    /// are due after `delta` seconds with the given local input.
+    fn advance(
+        &mut self,
+        world: &mut World,
+        stages: &mut SystemStages,
+        delta: f64,
+        local_input: PlayerControl,
+    ) {
+        pub const STEP: f64 = 1.0 / FPS as f64;
+
+        // The snapshots of the previous round must never be restored into the session of the next
+        // round. Every frame of the previous round is confirmed by now, so they aren't needed.
+        let round = world.resource::<MatchScore>().round;
+        if self.round != Some(round) {
+            self.history.clear();
+            self.round = Some(round);
+        }
+
+        if let Some(frame) = self.receive() {
+            let current_frame = self.frame;
+            if self.history.restore(frame, world) {
+                self.frame = frame;
+                // The round may now end sooner than it did with the wrong prediction.
+                while self.frame < current_frame && !world.resource::<RoundState>().has_ended() {
+                    self.simulate_frame(world, stages, true);
+                }
+                // Frames that weren't simulated again aren't predicted anymore.
+                let next_frame = self.frame;
+                for peer in &mut self.peers {
+                    peer.predictions.retain(|frame, _| *frame < next_frame);
+                }
+            } else {
+                error!("Could not roll back to frame {frame}, the snapshot is missing");
+            }
+        }
+
+        if !self.is_connected() {
+            if self.handshake_timer == 0 {
+                self.network_socket.send_message(
+                    SocketTarget::All,
+                    &RollbackSetupMessage::UdpPort(self.udp_socket.local_addr().unwrap().port()),
+                );
+                self.handshake_timer = HANDSHAKE_INTERVAL;
+            }
+            self.handshake_timer -= 1;
+            self.send();
+            return;
+        }
+
+        self.accumulator += delta;
+
+        let loop_start = Instant::now();
+        loop {
+            if self.accumulator >= STEP {
+                let loop_too_long = (Instant::now() - loop_start).as_secs_f64() > STEP;
+
+                if loop_too_long {
+                    warn!("Frame took too long: couldn't keep up with fixed update.");
+                    self.accumulator = 0.0;
+                    break;
+                } else if self.frame >= self.confirmed_frames() + MAX_PREDICTION {
+                    // Wait for the other players to catch up.
+                    self.accumulator = STEP;
+                    break;
+                } else if world.resource::<RoundState>().has_ended() {
+                    // Wait for the end of the round to be confirmed, and the session to move on.
+                    self.accumulator = 0.0;
+                    break;
+                } else {
+                    self.accumulator -= STEP;
+
+                    let local = &mut self.peers[self.local_player as usize];
+                    local.inputs.insert(self.frame + INPUT_DELAY, local_input);
+                    local.received = self.frame + INPUT_DELAY + 1;
+
+                    self.simulate_frame(world, stages, false);
+                }
+            } else {
+                break;
+            }
+        }
+
+        self.record_checksums(world);
+        world.insert_resource(Predicted(self.frame > self.confirmed_frames()));
+        self.send();
+        self.prune();
+    }
+}
+
+impl SessionRunner for NetworkMatchRunner {
+    fn step(&mut self, frame_start: Instant, world: &mut World, stages: &mut SystemStages) {
+        let last_run = self.last_run.unwrap_or(frame_start);
+        let delta = (frame_start - last_run).as_secs_f64();
+        self.last_run = Some(frame_start);
+
+        {
+            let keyboard = world.resource::<KeyboardInputs>();
+            let gamepad = world.resource::<GamepadInputs>();
+            self.input_collector.update(
+                &world.resource::<PlayerControlMapping>(),
+                &keyboard,
+                &gamepad,
+            );
+        }
+
+        let control_source =
+            world.resource::<MatchInputs>().players[self.local_player as usize].control_source;
+        let local_input = control_source
+            .and_then(|source| self.input_collector.get().get(&source).copied())
+            .unwrap_or_default();
+
+        self.advance(world, stages, delta, local_input);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{headless::start_test_match_with, networking::start_loopback_match};
+
+    const STEP: f64 = 1.0 / FPS as f64;
+
+    /// A player in a match on the loopback interface, with their own copy of the game.
+    struct TestPeer {
+        game: Game,
+        runner: NetworkMatchRunner,
+    }
+
+    impl TestPeer {
+        fn new(socket: NetworkMatchSocket) -> Self {
+            let local_player = socket.player_idx();
+            let control_sources = (0..socket.player_count())
+                .map(|i| {
+                    Some(if i == local_player {
+                        ControlSource::Keyboard1
+                    } else {
+                        ControlSource::Remote(i)
+                    })
+                })
+                .collect::<Vec<_>>();
+            Self {
+                game: start_test_match_with(&control_sources),
+                runner: NetworkMatchRunner::new(socket).unwrap(),
+            }
+        }
+
+        fn advance(&mut self, delta: f64, input: PlayerControl) {
+            let session = self.game.sessions.get_mut(SessionNames::GAME).unwrap();
+            self.runner
+                .advance(&mut session.world, &mut session.stages, delta, input);
+        }
+    }
+
+    /// Start a match on the loopback interface and wait for the players to connect.
+    fn connect(player_count: u32) -> Vec<TestPeer> {
+        let mut peers = start_loopback_match(player_count)
+            .into_iter()
+            .map(TestPeer::new)
+            .collect::<Vec<_>>();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !peers.iter().all(|x| x.runner.is_connected()) {
+            assert!(
+                Instant::now() < deadline,
+                "Timed out connecting the players"
+            );
+            play(&mut peers, &[0, 1, 2], 0.0, |_| default());
+        }
+        peers
+    }
+
+    /// Advance the given players once, giving the packets time to arrive afterwards.
+    fn play(
+        peers: &mut [TestPeer],
+        players: &[usize],
+        delta: f64,
+        input: impl Fn(usize) -> PlayerControl,
+    ) {
+        for &i in players {
+            if let Some(peer) = peers.get_mut(i) {
+                peer.advance(delta, input(i));
+            }
+        }
+        std::thread::sleep(Duration::from_millis(2));
+    }
+
+    /// Assert that the players recorded the same checksums for the given frames, and didn't
+    /// notice a desync.
+    fn assert_checksums_match(peers: &[TestPeer], frames: std::ops::Range<u32>) {
+        for peer in peers {
+            assert_eq!(peer.runner.desync_frame, None);
+        }
+        for frame in frames {
+            let checksums = peers
+                .iter()
+                .map(|x| x.runner.checksums.get(&frame).copied())
+                .collect::<Vec<_>>();
+            assert!(checksums[0].is_some(), "Frame {frame} wasn't confirmed");
+            assert!(
+                checksums.iter().all(|x| *x == checksums[0]),
+                "Checksums of frame {frame} differ: {checksums:?}"
+            );
+        }
+    }
+
+    fn running() -> PlayerControl {
+        PlayerControl {
+            right: 1.0,
+            move_direction: vec2(1.0, 0.0),
+            moving: true,
+            ..default()
+        }
+    }
+
+    fn jumping() -> PlayerControl {
+        PlayerControl {
+            left: 1.0,
+            move_direction: vec2(-1.0, 0.0),
+            moving: true,
+            jump_pressed: true,
+            jump_just_pressed: true,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn late_input_is_rolled_back_to_the_same_state() {
+        let mut peers = connect(2);
+        for _ in 0..30 {
+            play(&mut peers, &[0, 1], STEP, |_| running());
+        }
+
+        // The second player falls behind, so the first one has to predict their input.
+        let late_frame = peers[0].runner.peers[1].received;
+        for _ in 0..MAX_PREDICTION {
+            play(&mut peers, &[0], STEP, |_| running());
+        }
+        assert!(peers[0].runner.peers[1]
+            .predictions
+            .contains_key(&late_frame));
+
+        // The input of the second player turns out to be different from the prediction.
+        for _ in 0..MAX_PREDICTION {
+            play(&mut peers, &[1], STEP, |_| jumping());
+        }
+        for _ in 0..30 {
+            play(&mut peers, &[0, 1], STEP, |i| {
+                if i == 1 {
+                    jumping()
+                } else {
+                    running()
+                }
+            });
+        }
+        for _ in 0..20 {
+            play(&mut peers, &[0, 1], STEP, |_| default());
+        }
+
+        assert_checksums_match(&peers, late_frame..late_frame + MAX_PREDICTION);
+    }
+
+    #[test]
+    fn disconnected_player_is_dropped_on_the_same_frame() {
+        let mut peers = connect(3);
+        for _ in 0..30 {
+            play(&mut peers, &[0, 1, 2], STEP, |_| running());
+        }
+
+        // The third player stops sending input while running, and times out.
+        let deadline = Instant::now() + PEER_TIMEOUT * 2;
+        while !peers[..2]
+            .iter()
+            .all(|x| x.runner.peers[2].dropped_from.is_some())
+        {
+            assert!(Instant::now() < deadline, "Timed out dropping the player");
+            play(&mut peers, &[0, 1], STEP, |_| running());
+        }
+        for _ in 0..30 {
+            play(&mut peers, &[0, 1], STEP, |_| running());
+        }
+
+        let dropped_from = peers[0].runner.peers[2].dropped_from.unwrap();
+        assert_eq!(peers[1].runner.peers[2].dropped_from, Some(dropped_from));
+        assert_checksums_match(&peers[..2], dropped_from..dropped_from + 20);
+    }
+}
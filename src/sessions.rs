@@ -6,13 +6,30 @@ impl SessionNames {
     pub const MAIN_MENU: &str = "main_menu";
     pub const PAUSE_MENU: &str = "pause_menu";
     pub const MUSIC_PLAYER: &str = "music_player";
+    pub const DEBUG_OVERLAY: &str = "debug_overlay";
+    pub const ROUND_OVERLAY: &str = "round_overlay";
+    pub const HUD: &str = "hud";
+    pub const RESULTS: &str = "results";
+    pub const EDITOR: &str = "editor";
 }
 
 pub trait SessionExt {
     fn start_menu(&mut self);
     fn end_game(&mut self);
     fn restart_game(&mut self);
+    fn start_next_round(&mut self, map: MapMeta);
+    fn start_results(&mut self);
+
     fn start_game(&mut self, match_plugin: crate::core::MatchPlugin);
+    fn start_playlist(&mut self, match_plugin: crate::core::MatchPlugin, playlist: MapPlaylist);
+    fn start_replay(&mut self, replay: crate::core::replay::Replay);
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start_network_game(
+        &mut self,
+        match_plugin: crate::core::MatchPlugin,
+        seed: u64,
+        socket: crate::networking::NetworkMatchSocket,
+    );
 }
 
 impl SessionExt for Sessions {
@@ -25,23 +42,171 @@ impl SessionExt for Sessions {
         self.delete(SessionNames::GAME);
     }
 
+    /// Re-create the game session to play the match again, on the next map of the [`MapPlaylist`].
     #[track_caller]
     fn restart_game(&mut self) {
-        if let Some((map, player_info)) = self.get(SessionNames::GAME).map(|session| {
-            let map = (*session.world.resource::<LoadedMap>().0).clone();
-            let match_inputs = session.world.resource::<MatchInputs>();
-            (map, match_inputs.players.clone())
-        }) {
+        if let Some((map, player_info, seed, round_map_constructor, playlist)) =
+            self.get(SessionNames::GAME).map(|session| {
+                let mut playlist = session.world.resource::<MapPlaylist>().clone();
+                playlist.advance();
+                let map = match playlist.current() {
+                    Some(handle) => (*session.world.resource::<AssetServer>().get(handle)).clone(),
+                    None => (*session.world.resource::<LoadedMap>().0).clone(),
+                };
+                let match_inputs = session.world.resource::<MatchInputs>();
+                let seed = *session.world.resource::<RngSeed>();
+                let round_map_constructor = session.world.resource::<RoundMapConstructor>();
+                (
+                    map,
+                    match_inputs.players.clone(),
+                    seed,
+                    round_map_constructor.constructor,
+                    playlist,
+                )
+            })
+        {
             self.end_game();
-            self.create(SessionNames::GAME)
-                .install_plugin(crate::core::MatchPlugin { map, player_info });
+            let session = self.create(SessionNames::GAME);
+            session.world.insert_resource(seed);
+            session
+                .world
+                .insert_resource(RoundMapConstructor::new(round_map_constructor));
+            session.world.insert_resource(playlist);
+            session.install_plugin(crate::core::MatchPlugin { map, player_info });
         } else {
             panic!("Cannot restart game when game is not running");
         }
     }
 
+    /// Re-create the game session for the next round of the match on the given map.
+    ///
+    /// The score, the stats, the [`RoundMapConstructor`], the [`MapPlaylist`], the replay recording
+    /// and playback, and the session runner are carried over, so that online matches and replays
+    /// continue where the last round ended. The playlist is moved on to its next map.
+    #[track_caller]
+    fn start_next_round(&mut self, map: MapMeta) {
+        let Some(session) = self.get_mut(SessionNames::GAME) else {
+            panic!("Cannot start the next round when game is not running");
+        };
+        let player_info = session.world.resource::<MatchInputs>().players.clone();
+        let seed = *session.world.resource::<RngSeed>();
+        let mut score = *session.world.resource::<MatchScore>();
+        score.round += 1;
+        let stats = *session.world.resource::<MatchStats>();
+        let round_map_constructor = session.world.resource::<RoundMapConstructor>().constructor;
+        let mut playlist = session.world.resource::<MapPlaylist>().clone();
+        playlist.advance();
+        let mut recorder = session.world.resource::<ReplayRecorder>().clone();
+        recorder.finish_round((*session.world.resource::<LoadedMap>().0).clone());
+        let replay_rounds = session
+            .world
+            .get_resource::<ReplayRounds>()
+            .map(|x| (*x).clone());
+        let runner = std::mem::replace(
+            &mut session.runner,
+            Box::<crate::core::JumpyDefaultMatchRunner>::default(),
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        let socket = session
+            .world
+            .get_resource::<crate::networking::NetworkMatchSocket>()
+            .map(|x| (*x).clone());
+
+        self.end_game();
+        let session = self.create(SessionNames::GAME);
+        session.world.insert_resource(seed);
+        session.world.insert_resource(score);
+        session.world.insert_resource(stats);
+        session
+            .world
+            .insert_resource(RoundMapConstructor::new(round_map_constructor));
+        session.world.insert_resource(playlist);
+        session.world.insert_resource(recorder);
+        if let Some(replay_rounds) = replay_rounds {
+            session.world.insert_resource(replay_rounds);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(socket) = socket {
+            session.world.insert_resource(socket);
+        }
+        session.install_plugin(crate::core::MatchPlugin {
+            map,
+            player_info: std::array::from_fn(|i| PlayerInput {
+                control: default(),
+                editor_input: default(),
+                ..player_info[i]
+            }),
+        });
+        session.runner = runner;
+    }
+
+    /// End the game and show the results of the match.
+    #[track_caller]
+    fn start_results(&mut self) {
+        let Some(session) = self.get(SessionNames::GAME) else {
+            panic!("Cannot show the match results when game is not running");
+        };
+        let results = crate::ui::results::MatchResults::from_world(&session.world);
+
+        self.end_game();
+        self.create(SessionNames::RESULTS)
+            .install_plugin(crate::ui::results::session_plugin)
+            .world
+            .insert_resource(results);
+    }
+
     fn start_game(&mut self, match_plugin: crate::core::MatchPlugin) {
         let session = self.create(SessionNames::GAME);
         session.install_plugin(match_plugin);
     }
+
+    /// Start a game on the maps of a playlist. The match plugin should use the current map of the
+    /// playlist.
+    fn start_playlist(&mut self, match_plugin: crate::core::MatchPlugin, playlist: MapPlaylist) {
+        let session = self.create(SessionNames::GAME);
+        session.world.insert_resource(playlist);
+        session.install_plugin(match_plugin);
+    }
+
+    fn start_replay(&mut self, replay: crate::core::replay::Replay) {
+        let crate::core::replay::Replay {
+            seed,
+            score,
+            round_map_constructor,
+            player_info,
+            rounds,
+            playback,
+        } = replay;
+        let map = rounds.rounds[0].map.clone();
+        let session = self.create(SessionNames::GAME);
+        session.world.insert_resource(RngSeed(seed));
+        session.world.insert_resource(score);
+        session
+            .world
+            .insert_resource(RoundMapConstructor::new(round_map_constructor));
+        session.world.insert_resource(rounds);
+        session.install_plugin(crate::core::MatchPlugin { map, player_info });
+        session.runner = Box::new(crate::core::JumpyDefaultMatchRunner {
+            replay: Some(playback),
+            ..default()
+        });
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start_network_game(
+        &mut self,
+        match_plugin: crate::core::MatchPlugin,
+        seed: u64,
+        socket: crate::networking::NetworkMatchSocket,
+    ) {
+        let session = self.create(SessionNames::GAME);
+        // Every player must use the same seed to stay in sync.
+        session.world.insert_resource(RngSeed(seed));
+        session.world.insert_resource(socket.clone());
+        session.install_plugin(match_plugin);
+        match crate::networking::rollback::NetworkMatchRunner::new(socket) {
+            Ok(runner) => session.runner = Box::new(runner),
+            Err(e) => error!("Could not start the network match runner: {e}"),
+        }
+    }
 }
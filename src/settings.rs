@@ -51,6 +51,19 @@ pub struct PlayerControlMapping {
     pub keyboard2: PlayerControlSetting,
 }
 
+impl PlayerControlMapping {
+    /// Get the control settings used by the given control source, or [`None`] if it is a remote
+    /// player that has no local controls.
+    pub fn for_source(&self, source: ControlSource) -> Option<&PlayerControlSetting> {
+        match source {
+            ControlSource::Keyboard1 => Some(&self.keyboard1),
+            ControlSource::Keyboard2 => Some(&self.keyboard2),
+            ControlSource::Gamepad(_) => Some(&self.gamepad),
+            ControlSource::Remote(_) => None,
+        }
+    }
+}
+
 /// Binds inputs to player actions
 #[derive(HasSchema, Clone, Debug, Default)]
 #[repr(C)]
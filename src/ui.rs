@@ -1,8 +1,13 @@
 use crate::prelude::*;
 
+pub mod debug_overlay;
+pub mod editor;
+pub mod hud;
 pub mod main_menu;
 pub mod map_select;
 pub mod pause_menu;
+pub mod results;
+pub mod round_overlay;
 
 #[derive(HasSchema, Clone, Debug)]
 #[repr(C)]
@@ -0,0 +1,66 @@
+//! Debug information drawn on top of the running match.
+
+use crate::prelude::*;
+
+pub fn session_plugin(session: &mut Session) {
+    session.world.init_param::<Localization<GameMeta>>();
+    session.add_system_to_stage(Update, debug_overlay_system);
+}
+
+fn debug_overlay_system(
+    meta: Root<GameMeta>,
+    localization: Localization<GameMeta>,
+    mut sessions: ResMut<Sessions>,
+    ctx: Res<EguiCtx>,
+    keyboard: Res<KeyboardInputs>,
+) {
+    let Some(session) = sessions.get_mut(SessionNames::GAME) else {
+        return;
+    };
+
+    // Toggle the state checksum with F3
+    let f3_pressed = keyboard
+        .key_events
+        .iter()
+        .any(|x| x.key_code == Set(KeyCode::F3) && x.button_state.pressed());
+    let show_state_checksum = {
+        let mut settings = session.world.init_resource::<DebugSettings>();
+        if f3_pressed {
+            settings.show_state_checksum = !settings.show_state_checksum;
+        }
+        settings.show_state_checksum
+    };
+    if !show_state_checksum {
+        return;
+    }
+
+    let Some(checksum) = session.world.get_resource::<StateChecksum>().map(|x| *x) else {
+        return;
+    };
+    let mismatch = session
+        .world
+        .get_resource::<ReferenceChecksums>()
+        .and_then(|x| x.mismatch);
+
+    egui::Area::new("debug_overlay")
+        .anchor(egui::Align2::LEFT_TOP, egui::vec2(8.0, 8.0))
+        .interactable(false)
+        .show(&ctx, |ui| {
+            let font = meta.theme.font_styles.smaller.clone();
+            ui.label(font.rich(localization.get_with(
+                "state-checksum",
+                &fluent_args! {
+                    "frame" => checksum.frame.unwrap_or(0),
+                    "checksum" => format!("{:016x}", checksum.value)
+                },
+            )));
+            if let Some(frame) = mismatch {
+                ui.label(font.with_color(Color::RED).rich(localization.get_with(
+                    "state-checksum-mismatch",
+                    &fluent_args! {
+                        "frame" => frame
+                    },
+                )));
+            }
+        });
+}
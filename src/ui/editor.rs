@@ -0,0 +1,874 @@
+//! The in-game map editor.
+//!
+//! The editor doesn't modify the map directly. It queues [`EditorInput`]s in the game session's
+//! [`LocalEditorInputs`] instead, which are handled by the [`MapManager`] in the simulation like any
+//! other player input, so that the edits are recorded in replays and can be undone.
+
+use std::collections::BTreeMap;
+
+use crate::prelude::*;
+
+/// The size of the tile buttons in the tile palette.
+const TILE_BUTTON_SIZE: f32 = 32.0;
+
+/// The factor that the camera height is multiplied by for every point scrolled.
+const ZOOM_SPEED: f32 = 0.998;
+
+/// The smallest and largest camera heights that the editor can zoom to.
+const CAMERA_HEIGHT_RANGE: (f32, f32) = (100.0, 4000.0);
+
+pub fn session_plugin(session: &mut Session) {
+    session.world.init_param::<Localization<GameMeta>>();
+    session.world.init_resource::<EditorState>();
+    session.add_system_to_stage(Update, editor_system);
+}
+
+/// The tool that is used when clicking on the map.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EditorTool {
+    /// Paint the selected tile, with the selected collision.
+    #[default]
+    Tile,
+    /// Erase tiles.
+    Erase,
+    /// Paint the selected collision onto existing tiles.
+    Collision,
+    /// Fill a rectangle with the selected tile.
+    Rect,
+    /// Flood fill the tiles connected to the clicked tile with the selected tile.
+    Fill,
+    /// Select a rectangle of the map and copy it.
+    Copy,
+    /// Paste the copied rectangle.
+    Paste,
+    /// Place the selected element.
+    Element,
+    /// Drag elements around, or delete them with the secondary button.
+    Move,
+}
+
+impl EditorTool {
+    const ALL: [EditorTool; 9] = [
+        EditorTool::Tile,
+        EditorTool::Erase,
+        EditorTool::Collision,
+        EditorTool::Rect,
+        EditorTool::Fill,
+        EditorTool::Copy,
+        EditorTool::Paste,
+        EditorTool::Element,
+        EditorTool::Move,
+    ];
+
+    fn localization_key(self) -> &'static str {
+        match self {
+            EditorTool::Tile => "paint-tiles",
+            EditorTool::Erase => "erase-tiles",
+            EditorTool::Collision => "paint-collision",
+            EditorTool::Rect => "fill-rect",
+            EditorTool::Fill => "flood-fill",
+            EditorTool::Copy => "copy-region",
+            EditorTool::Paste => "paste-region",
+            EditorTool::Element => "place-element",
+            EditorTool::Move => "move-elements",
+        }
+    }
+}
+
+/// Resource containing the state of the editor UI.
+#[derive(HasSchema, Clone, Debug, Default)]
+pub struct EditorState {
+    /// The selected tool.
+    pub tool: EditorTool,
+    /// The index of the selected layer.
+    pub layer: u8,
+    /// The selected tile index in the tilemap of the selected layer.
+    pub tile_idx: u32,
+    /// The selected collision kind for painting tiles.
+    pub collision: TileCollisionKind,
+    /// How the tiles to flood fill are found.
+    pub fill_mode: FloodFillMode,
+    /// The selected map constructor.
+    pub map_constructor: Option<Ustr>,
+    /// Whether or not to run the map constructor again at the start of every round.
+    pub construct_every_round: bool,
+    /// The selected element to place.
+    pub element: Option<Handle<ElementMeta>>,
+    /// The contents of the map name field, if it has been edited.
+    pub map_name: Option<String>,
+    /// The contents of the layer name field.
+    pub layer_name: String,
+    /// The last tile painted in the current brush stroke, so that it isn't painted every frame.
+    pub last_painted: Option<UVec2>,
+    /// The tile that the rectangle being dragged out starts at.
+    pub rect_start: Option<UVec2>,
+    /// The element being dragged, along with its offset from the cursor.
+    pub dragging: Option<(Entity, Vec2)>,
+}
+
+/// The parts of the game world that are shown in the editor.
+struct MapView {
+    map_meta: SpawnedMapMeta,
+    can_undo: bool,
+    can_redo: bool,
+    /// The tilemap of each layer, by layer index.
+    tilemaps: Vec<Option<Handle<Atlas>>>,
+    /// The map elements, with their entity, position and layer index.
+    elements: Vec<(Entity, Handle<ElementMeta>, Vec2, u32)>,
+    /// The size of the region in the [`MapClipboard`].
+    clipboard_size: UVec2,
+    /// The names of the [`MapConstructors`].
+    map_constructors: Vec<Ustr>,
+    /// The center and the height of the camera.
+    camera: Option<(Vec2, f32)>,
+}
+
+fn collect_map_view(
+    entities: Res<Entities>,
+    map_meta: Res<SpawnedMapMeta>,
+    history: Res<MapEditHistory>,
+    clipboard: Res<MapClipboard>,
+    map_constructors: Res<MapConstructors>,
+    tile_layers: Comp<TileLayer>,
+    spawned_map_layer_metas: Comp<SpawnedMapLayerMeta>,
+    element_handles: Comp<ElementHandle>,
+    transforms: Comp<Transform>,
+    cameras: Comp<Camera>,
+    camera_shakes: Comp<CameraShake>,
+) -> MapView {
+    let mut tilemaps = vec![None; map_meta.layer_names.len()];
+    for (_, (tile_layer, layer_meta)) in
+        entities.iter_with((&tile_layers, &spawned_map_layer_metas))
+    {
+        if let Some(tilemap) = tilemaps.get_mut(layer_meta.layer_idx as usize) {
+            *tilemap = (tile_layer.atlas != default()).then_some(tile_layer.atlas);
+        }
+    }
+
+    MapView {
+        map_meta: (*map_meta).clone(),
+        can_undo: history.can_undo(),
+        can_redo: history.can_redo(),
+        tilemaps,
+        elements: entities
+            .iter_with((&element_handles, &transforms, &spawned_map_layer_metas))
+            .map(|(ent, (element_handle, transform, layer_meta))| {
+                (
+                    ent,
+                    element_handle.0,
+                    transform.translation.truncate(),
+                    layer_meta.layer_idx,
+                )
+            })
+            .collect(),
+        clipboard_size: clipboard.size,
+        map_constructors: map_constructors.names().collect(),
+        camera: entities.iter_with((&cameras, &camera_shakes)).next().map(
+            |(_, (camera, camera_shake))| {
+                let height = match camera.size {
+                    CameraSize::FixedHeight(height) => height,
+                    _ => CameraMeta::default().default_height,
+                };
+                (camera_shake.center.truncate(), height)
+            },
+        ),
+    }
+}
+
+/// Get the tile index of the tile at the given position on a layer.
+fn tile_at(
+    In((layer_idx, pos)): In<(u32, UVec2)>,
+    entities: Res<Entities>,
+    tile_layers: Comp<TileLayer>,
+    spawned_map_layer_metas: Comp<SpawnedMapLayerMeta>,
+    tiles: Comp<Tile>,
+) -> Option<u32> {
+    let (_, (tile_layer, _)) = entities
+        .iter_with((&tile_layers, &spawned_map_layer_metas))
+        .find(|x| x.1 .1.layer_idx == layer_idx)?;
+    tile_layer
+        .get(pos)
+        .and_then(|ent| tiles.get(ent))
+        .map(|tile| tile.idx)
+}
+
+/// Take over the camera from the camera controller, panning it by the given amount and
+/// multiplying its height by the given zoom factor.
+fn control_camera(
+    In((pan, zoom)): In<(Vec2, f32)>,
+    entities: Res<Entities>,
+    mut cameras: CompMut<Camera>,
+    mut camera_shakes: CompMut<CameraShake>,
+    mut camera_states: CompMut<CameraState>,
+) {
+    for (_, (camera, camera_shake, camera_state)) in
+        entities.iter_with((&mut cameras, &mut camera_shakes, &mut camera_states))
+    {
+        camera_state.disable_controller = true;
+        camera_shake.center += pan.extend(0.0);
+        if let CameraSize::FixedHeight(height) = &mut camera.size {
+            *height = (*height * zoom).clamp(CAMERA_HEIGHT_RANGE.0, CAMERA_HEIGHT_RANGE.1);
+        }
+    }
+}
+
+/// Give the camera back to the camera controller.
+fn release_camera(entities: Res<Entities>, mut camera_states: CompMut<CameraState>) {
+    for (_, camera_state) in entities.iter_with(&mut camera_states) {
+        camera_state.disable_controller = false;
+    }
+}
+
+/// Get a human-readable name for an asset from the name of the file it was loaded from.
+fn asset_name(assets: &AssetServer, handle: UntypedHandle) -> String {
+    assets
+        .get_asset_untyped(handle)
+        .and_then(|asset| {
+            let file_name = asset.loc.path.file_name()?.to_string_lossy();
+            file_name.split('.').next().map(|x| x.to_string())
+        })
+        .unwrap_or_default()
+}
+
+fn editor_system(
+    meta: Root<GameMeta>,
+    localization: Localization<GameMeta>,
+    mut sessions: ResMut<Sessions>,
+    mut session_options: ResMut<SessionOptions>,
+    mut state: ResMut<EditorState>,
+    ctx: Res<EguiCtx>,
+    assets: Res<AssetServer>,
+    egui_textures: Res<EguiTextures>,
+) {
+    let Some(session) = sessions.get_mut(SessionNames::GAME) else {
+        // There is nothing left to edit once the game has ended.
+        session_options.delete = true;
+        return;
+    };
+    // Let the pause menu draw over the game instead.
+    if !session.active {
+        return;
+    }
+    let world = &session.world;
+    if world.get_resource::<SpawnedMapMeta>().is_none() {
+        return;
+    }
+    let view = world.run_system(collect_map_view, ());
+    let layer_count = view.map_meta.layer_names.len();
+    let state = &mut *state;
+    state.layer = state.layer.min(layer_count.saturating_sub(1) as u8);
+    let layer = state.layer;
+
+    let bigger_font = meta
+        .theme
+        .font_styles
+        .bigger
+        .with_color(meta.theme.panel.font_color);
+    let normal_font = meta
+        .theme
+        .font_styles
+        .normal
+        .with_color(meta.theme.panel.font_color);
+    let small_button = |ui: &mut egui::Ui, text: String| {
+        BorderedButton::themed(&meta.theme.buttons.small, text)
+            .show(ui)
+            .clicked()
+    };
+
+    let mut inputs = Vec::new();
+    let mut close = false;
+
+    egui::SidePanel::left("map_editor")
+        .frame(egui::Frame::none())
+        .resizable(false)
+        .show(&ctx, |ui| {
+            BorderedFrame::new(&meta.theme.panel.border)
+                .padding(meta.theme.panel.padding)
+                .show(ui, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        ui.label(bigger_font.rich(localization.get("map-editor")));
+
+                        ui.horizontal(|ui| {
+                            ui.add_enabled_ui(view.can_undo, |ui| {
+                                if small_button(ui, localization.get("undo")) {
+                                    inputs.push(EditorInput::Undo);
+                                }
+                            });
+                            ui.add_enabled_ui(view.can_redo, |ui| {
+                                if small_button(ui, localization.get("redo")) {
+                                    inputs.push(EditorInput::Redo);
+                                }
+                            });
+                            if small_button(ui, localization.get("close")) {
+                                close = true;
+                            }
+                        });
+                        ui.separator();
+
+                        // Map name
+                        ui.label(normal_font.rich(localization.get("name")));
+                        let map_name = state
+                            .map_name
+                            .get_or_insert_with(|| view.map_meta.name.to_string());
+                        ui.horizontal(|ui| {
+                            ui.text_edit_singleline(map_name);
+                            if small_button(ui, localization.get("rename"))
+                                && map_name.as_str() != view.map_meta.name.as_str()
+                            {
+                                inputs.push(EditorInput::RenameMap {
+                                    name: map_name.clone(),
+                                });
+                            }
+                        });
+                        ui.separator();
+
+                        // Map size
+                        let grid_size = view.map_meta.grid_size;
+                        ui.label(normal_font.rich(format!(
+                            "{}: {} × {}",
+                            localization.get("grid-size"),
+                            grid_size.x,
+                            grid_size.y
+                        )));
+                        egui::Grid::new("editor_map_size").show(ui, |ui| {
+                            // Growing or cropping the left and bottom edges moves the contents of
+                            // the map, so that they stay in place relative to the other edges.
+                            for (edge, axis, offset) in [
+                                ("left-edge", UVec2::X, ivec2(1, 0)),
+                                ("right-edge", UVec2::X, IVec2::ZERO),
+                                ("bottom-edge", UVec2::Y, ivec2(0, 1)),
+                                ("top-edge", UVec2::Y, IVec2::ZERO),
+                            ] {
+                                ui.label(normal_font.rich(localization.get(edge)));
+                                if small_button(ui, "+".into()) {
+                                    inputs.push(EditorInput::ResizeMap {
+                                        size: grid_size + axis,
+                                        offset,
+                                    });
+                                }
+                                if small_button(ui, "-".into()) && grid_size.dot(axis) > 1 {
+                                    inputs.push(EditorInput::ResizeMap {
+                                        size: grid_size - axis,
+                                        offset: -offset,
+                                    });
+                                }
+                                ui.end_row();
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label(normal_font.rich(localization.get("shift-map")));
+                            for (text, offset) in [
+                                ("←", ivec2(-1, 0)),
+                                ("→", ivec2(1, 0)),
+                                ("↓", ivec2(0, -1)),
+                                ("↑", ivec2(0, 1)),
+                            ] {
+                                if small_button(ui, text.into()) {
+                                    inputs.push(EditorInput::ShiftMap { offset });
+                                }
+                            }
+                        });
+                        ui.separator();
+
+                        // Map constructors
+                        let map_constructor = state
+                            .map_constructor
+                            .filter(|x| view.map_constructors.contains(x))
+                            .or_else(|| view.map_constructors.first().copied());
+                        ui.horizontal_wrapped(|ui| {
+                            egui::ComboBox::from_id_source("editor_map_constructor")
+                                .selected_text(map_constructor.map_or("", |x| x.as_str()))
+                                .show_ui(ui, |ui| {
+                                    for &name in &view.map_constructors {
+                                        let selected = map_constructor == Some(name);
+                                        if ui.selectable_label(selected, name.as_str()).clicked() {
+                                            state.map_constructor = Some(name);
+                                        }
+                                    }
+                                });
+                            if let Some(name) = map_constructor {
+                                if small_button(ui, localization.get("randomize")) {
+                                    inputs.push(EditorInput::ConstructMap {
+                                        name: name.to_string(),
+                                        every_round: state.construct_every_round,
+                                    });
+                                }
+                            }
+                        });
+                        ui.checkbox(
+                            &mut state.construct_every_round,
+                            normal_font.rich(localization.get("randomize-every-round")),
+                        );
+                        ui.separator();
+
+                        // Tools
+                        ui.horizontal_wrapped(|ui| {
+                            for tool in EditorTool::ALL {
+                                let text =
+                                    normal_font.rich(localization.get(tool.localization_key()));
+                                if ui.selectable_label(state.tool == tool, text).clicked() {
+                                    state.tool = tool;
+                                }
+                            }
+                        });
+                        if state.tool == EditorTool::Fill {
+                            ui.horizontal(|ui| {
+                                for (mode, key) in [
+                                    (FloodFillMode::TileIndex, "fill-by-tile"),
+                                    (FloodFillMode::Collision, "fill-by-collision"),
+                                ] {
+                                    let text = normal_font.rich(localization.get(key));
+                                    if ui.selectable_label(state.fill_mode == mode, text).clicked()
+                                    {
+                                        state.fill_mode = mode;
+                                    }
+                                }
+                            });
+                        }
+                        ui.separator();
+
+                        // Layers
+                        ui.label(bigger_font.rich(localization.get("layers")));
+                        for (i, name) in view.map_meta.layer_names.iter().enumerate() {
+                            let text = normal_font.rich(name.as_str());
+                            if ui.selectable_label(i == layer as usize, text).clicked() {
+                                state.layer = i as u8;
+                            }
+                        }
+                        ui.text_edit_singleline(&mut state.layer_name);
+                        ui.horizontal_wrapped(|ui| {
+                            let has_name = !state.layer_name.is_empty();
+                            ui.add_enabled_ui(has_name, |ui| {
+                                if small_button(ui, localization.get("create")) {
+                                    inputs.push(EditorInput::CreateLayer {
+                                        id: state.layer_name.clone(),
+                                    });
+                                    state.layer = layer_count as u8;
+                                }
+                            });
+                            ui.add_enabled_ui(has_name && layer_count > 0, |ui| {
+                                if small_button(ui, localization.get("rename")) {
+                                    inputs.push(EditorInput::RenameLayer {
+                                        layer,
+                                        name: state.layer_name.clone(),
+                                    });
+                                }
+                            });
+                        });
+                        ui.horizontal_wrapped(|ui| {
+                            ui.add_enabled_ui(layer > 0, |ui| {
+                                if small_button(ui, localization.get("move-up")) {
+                                    inputs.push(EditorInput::MoveLayer { layer, down: false });
+                                    state.layer -= 1;
+                                }
+                            });
+                            ui.add_enabled_ui((layer as usize + 1) < layer_count, |ui| {
+                                if small_button(ui, localization.get("move-down")) {
+                                    inputs.push(EditorInput::MoveLayer { layer, down: true });
+                                    state.layer += 1;
+                                }
+                            });
+                            ui.add_enabled_ui(layer_count > 1, |ui| {
+                                if small_button(ui, localization.get("delete-layer")) {
+                                    inputs.push(EditorInput::DeleteLayer { layer });
+                                }
+                            });
+                        });
+                        ui.separator();
+
+                        // Collision brush
+                        ui.label(bigger_font.rich(localization.get("collision")));
+                        ui.horizontal_wrapped(|ui| {
+                            for (collision, key) in [
+                                (TileCollisionKind::Empty, "empty"),
+                                (TileCollisionKind::Solid, "solid"),
+                                (TileCollisionKind::JumpThrough, "jump-through"),
+                            ] {
+                                let text = normal_font.rich(localization.get(key));
+                                if ui
+                                    .selectable_label(state.collision == collision, text)
+                                    .clicked()
+                                {
+                                    state.collision = collision;
+                                }
+                            }
+                        });
+                        ui.separator();
+
+                        // Tile palette
+                        ui.label(bigger_font.rich(localization.get("tilemap")));
+                        let tilemap = view.tilemaps.get(layer as usize).copied().flatten();
+                        let tilemap_name =
+                            |handle: Handle<Atlas>| asset_name(&assets, handle.untyped());
+                        egui::ComboBox::from_id_source("editor_tilemap")
+                            .selected_text(
+                                tilemap.map_or_else(|| localization.get("none"), tilemap_name),
+                            )
+                            .show_ui(ui, |ui| {
+                                if ui
+                                    .selectable_label(tilemap.is_none(), localization.get("none"))
+                                    .clicked()
+                                    && tilemap.is_some()
+                                {
+                                    inputs.push(EditorInput::SetTilemap {
+                                        layer,
+                                        handle: None,
+                                    });
+                                }
+                                for &handle in meta.core.map_tilesets.iter() {
+                                    let selected = tilemap == Some(handle);
+                                    if ui
+                                        .selectable_label(selected, tilemap_name(handle))
+                                        .clicked()
+                                        && !selected
+                                    {
+                                        inputs.push(EditorInput::SetTilemap {
+                                            layer,
+                                            handle: Some(handle),
+                                        });
+                                    }
+                                }
+                            });
+                        if let Some(tilemap) = tilemap {
+                            let atlas = assets.get(tilemap);
+                            if let Some(&texture_id) = egui_textures.0.get(&atlas.image) {
+                                ui.horizontal_wrapped(|ui| {
+                                    ui.spacing_mut().item_spacing = egui::vec2(2.0, 2.0);
+                                    for idx in 0..atlas.columns * atlas.rows {
+                                        let (rect, response) = ui.allocate_exact_size(
+                                            egui::vec2(TILE_BUTTON_SIZE, TILE_BUTTON_SIZE),
+                                            egui::Sense::click(),
+                                        );
+                                        let tile_pos = atlas.tile_pos(idx);
+                                        let uv_min = tile_pos / atlas.size();
+                                        let uv_max = (tile_pos + atlas.tile_size) / atlas.size();
+                                        let mut mesh = egui::Mesh::with_texture(texture_id);
+                                        mesh.add_rect_with_uv(
+                                            rect,
+                                            egui::Rect {
+                                                min: egui::pos2(uv_min.x, uv_min.y),
+                                                max: egui::pos2(uv_max.x, uv_max.y),
+                                            },
+                                            egui::Color32::WHITE,
+                                        );
+                                        ui.painter().add(mesh);
+                                        if idx == state.tile_idx {
+                                            ui.painter().rect_stroke(
+                                                rect,
+                                                0.0,
+                                                egui::Stroke::new(2.0, egui::Color32::YELLOW),
+                                            );
+                                        }
+                                        if response.clicked() {
+                                            state.tile_idx = idx;
+                                            state.tool = EditorTool::Tile;
+                                        }
+                                    }
+                                });
+                            }
+                        }
+                        ui.separator();
+
+                        // Element palette, grouped by category
+                        ui.label(bigger_font.rich(localization.get("elements")));
+                        let mut categories = BTreeMap::<String, Vec<Handle<ElementMeta>>>::new();
+                        for &handle in meta.core.map_elements.iter() {
+                            let category = assets.get(handle).category.to_string();
+                            categories.entry(category).or_default().push(handle);
+                        }
+                        for (category, handles) in categories {
+                            egui::CollapsingHeader::new(normal_font.rich(category.as_str()))
+                                .id_source(("editor_element_category", category.as_str()))
+                                .show(ui, |ui| {
+                                    for handle in handles {
+                                        let name = assets.get(handle).name.to_string();
+                                        let selected = state.element == Some(handle);
+                                        if ui.selectable_label(selected, name).clicked() {
+                                            state.element = Some(handle);
+                                            state.tool = EditorTool::Element;
+                                        }
+                                    }
+                                });
+                        }
+                    });
+                });
+        });
+
+    // Keyboard shortcuts
+    let (undo, redo) = ctx.input(|i| {
+        let undo = i.modifiers.command && i.key_pressed(egui::Key::Z);
+        (
+            undo && !i.modifiers.shift,
+            (undo && i.modifiers.shift) || (i.modifiers.command && i.key_pressed(egui::Key::Y)),
+        )
+    });
+    if undo {
+        inputs.push(EditorInput::Undo);
+    } else if redo {
+        inputs.push(EditorInput::Redo);
+    }
+
+    // Interact with the map
+    let screen_rect = ctx.screen_rect();
+    let (camera_center, camera_height) = view.camera.unwrap_or_default();
+    let scale = camera_height / screen_rect.height();
+    let to_world = |pos: egui::Pos2| {
+        camera_center
+            + vec2(
+                pos.x - screen_rect.center().x,
+                screen_rect.center().y - pos.y,
+            ) * scale
+    };
+    let to_screen = |pos: Vec2| {
+        let offset = (pos - camera_center) / scale;
+        screen_rect.center() + egui::vec2(offset.x, -offset.y)
+    };
+    let world_rect =
+        |min: Vec2, max: Vec2| egui::Rect::from_two_pos(to_screen(min), to_screen(max));
+
+    let pointer_over_ui = ctx.is_pointer_over_area();
+    let (hover_pos, primary_pressed, primary_down, primary_released, secondary_pressed) = ctx
+        .input(|i| {
+            (
+                i.pointer.hover_pos(),
+                i.pointer.primary_pressed(),
+                i.pointer.primary_down(),
+                i.pointer.primary_released(),
+                i.pointer.secondary_pressed(),
+            )
+        });
+    let (pan, zoom) = ctx.input(|i| {
+        let pan = if i.pointer.middle_down() && !pointer_over_ui {
+            let delta = i.pointer.delta();
+            vec2(-delta.x, delta.y) * scale
+        } else {
+            Vec2::ZERO
+        };
+        let zoom = if pointer_over_ui {
+            1.0
+        } else {
+            ZOOM_SPEED.powf(i.scroll_delta.y)
+        };
+        (pan, zoom)
+    });
+    world.run_system(control_camera, (pan, zoom));
+
+    let painter = ctx.layer_painter(egui::LayerId::background());
+    let map_meta = &view.map_meta;
+    let map_size = map_meta.grid_size.as_vec2() * map_meta.tile_size;
+    painter.rect_stroke(
+        world_rect(Vec2::ZERO, map_size),
+        0.0,
+        egui::Stroke::new(1.0, egui::Color32::WHITE),
+    );
+
+    // Get the element under the cursor
+    let element_rect = |handle: Handle<ElementMeta>, pos: Vec2| {
+        let editor = &assets.get(handle).editor;
+        let center = pos + editor.grab_offset;
+        world_rect(
+            center - editor.grab_size / 2.0,
+            center + editor.grab_size / 2.0,
+        )
+    };
+    let hovered_element = hover_pos
+        .filter(|_| !pointer_over_ui)
+        .and_then(|hover_pos| {
+            view.elements
+                .iter()
+                .filter(|x| x.3 == layer as u32)
+                .find(|(_, handle, pos, _)| element_rect(*handle, *pos).contains(hover_pos))
+        });
+
+    // Draw the elements of the selected layer
+    if matches!(state.tool, EditorTool::Element | EditorTool::Move) {
+        for &(ent, handle, pos, layer_idx) in &view.elements {
+            if layer_idx != layer as u32 {
+                continue;
+            }
+            let pos = match state.dragging {
+                Some((dragged, offset)) if dragged == ent => {
+                    hover_pos.map_or(pos, |x| to_world(x) + offset)
+                }
+                _ => pos,
+            };
+            let rect = element_rect(handle, pos);
+            let hovered = hovered_element.map_or(false, |x| x.0 == ent);
+            let color = if hovered {
+                egui::Color32::YELLOW
+            } else {
+                egui::Color32::WHITE
+            };
+            painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, color));
+            let element_meta = assets.get(handle);
+            if element_meta.editor.show_name {
+                painter.text(
+                    rect.center_top(),
+                    egui::Align2::CENTER_BOTTOM,
+                    element_meta.name.as_str(),
+                    egui::FontId::proportional(12.0),
+                    color,
+                );
+            }
+        }
+    }
+
+    if !primary_down {
+        state.last_painted = None;
+    }
+    if let Some(hover_pos) = hover_pos.filter(|_| !pointer_over_ui) {
+        let cursor = to_world(hover_pos);
+        let tile_pos = (cursor / map_meta.tile_size).floor();
+        let tile_pos = (tile_pos.cmpge(Vec2::ZERO).all()
+            && tile_pos.cmplt(map_meta.grid_size.as_vec2()).all())
+        .then(|| tile_pos.as_uvec2());
+
+        match state.tool {
+            EditorTool::Tile | EditorTool::Erase | EditorTool::Collision => {
+                if let Some(tile_pos) = tile_pos {
+                    let min = tile_pos.as_vec2() * map_meta.tile_size;
+                    painter.rect_stroke(
+                        world_rect(min, min + map_meta.tile_size),
+                        0.0,
+                        egui::Stroke::new(1.0, egui::Color32::YELLOW),
+                    );
+
+                    if primary_down && state.last_painted != Some(tile_pos) && layer_count > 0 {
+                        state.last_painted = Some(tile_pos);
+                        let tilemap_tile_idx = match state.tool {
+                            EditorTool::Tile => Some(state.tile_idx),
+                            EditorTool::Collision => {
+                                world.run_system(tile_at, (layer as u32, tile_pos))
+                            }
+                            _ => None,
+                        };
+                        // Painting collision only changes existing tiles.
+                        if state.tool != EditorTool::Collision || tilemap_tile_idx.is_some() {
+                            inputs.push(EditorInput::SetTile {
+                                layer,
+                                pos: tile_pos,
+                                tilemap_tile_idx,
+                                collision: state.collision,
+                            });
+                        }
+                    }
+                }
+            }
+            EditorTool::Rect | EditorTool::Copy => {
+                if let Some(tile_pos) = tile_pos {
+                    if primary_pressed {
+                        state.rect_start = Some(tile_pos);
+                    }
+                    let start = state.rect_start.unwrap_or(tile_pos);
+                    let (min, max) = (start.min(tile_pos), start.max(tile_pos) + UVec2::ONE);
+                    painter.rect_stroke(
+                        world_rect(
+                            min.as_vec2() * map_meta.tile_size,
+                            max.as_vec2() * map_meta.tile_size,
+                        ),
+                        0.0,
+                        egui::Stroke::new(1.0, egui::Color32::YELLOW),
+                    );
+
+                    if primary_released && state.rect_start.is_some() {
+                        if state.tool == EditorTool::Copy {
+                            inputs.push(EditorInput::CopyRegion {
+                                start: min,
+                                size: max - min,
+                            });
+                        } else if layer_count > 0 {
+                            inputs.push(EditorInput::FillRect {
+                                layer,
+                                start: min,
+                                size: max - min,
+                                tilemap_tile_idx: Some(state.tile_idx),
+                                collision: state.collision,
+                            });
+                        }
+                    }
+                }
+            }
+            EditorTool::Fill => {
+                if let Some(tile_pos) = tile_pos {
+                    let min = tile_pos.as_vec2() * map_meta.tile_size;
+                    painter.rect_stroke(
+                        world_rect(min, min + map_meta.tile_size),
+                        0.0,
+                        egui::Stroke::new(1.0, egui::Color32::YELLOW),
+                    );
+
+                    if primary_pressed && layer_count > 0 {
+                        inputs.push(EditorInput::FloodFill {
+                            layer,
+                            pos: tile_pos,
+                            tilemap_tile_idx: Some(state.tile_idx),
+                            collision: state.collision,
+                            mode: state.fill_mode,
+                        });
+                    }
+                }
+            }
+            EditorTool::Paste => {
+                if let Some(tile_pos) = tile_pos.filter(|_| view.clipboard_size != UVec2::ZERO) {
+                    let min = tile_pos.as_vec2() * map_meta.tile_size;
+                    painter.rect_stroke(
+                        world_rect(
+                            min,
+                            min + view.clipboard_size.as_vec2() * map_meta.tile_size,
+                        ),
+                        0.0,
+                        egui::Stroke::new(1.0, egui::Color32::YELLOW),
+                    );
+
+                    if primary_pressed {
+                        inputs.push(EditorInput::PasteRegion { pos: tile_pos });
+                    }
+                }
+            }
+            EditorTool::Element => {
+                if let Some(handle) = state.element.filter(|_| primary_pressed && layer_count > 0) {
+                    inputs.push(EditorInput::SpawnElement {
+                        handle,
+                        translation: cursor,
+                        layer,
+                    });
+                }
+            }
+            EditorTool::Move => {
+                if primary_pressed {
+                    state.dragging = hovered_element.map(|&(ent, _, pos, _)| (ent, pos - cursor));
+                } else if secondary_pressed {
+                    if let Some(&(entity, ..)) = hovered_element {
+                        inputs.push(EditorInput::DeleteEntity { entity });
+                    }
+                }
+            }
+        }
+
+        if primary_released {
+            if let Some((entity, offset)) = state.dragging.take() {
+                inputs.push(EditorInput::MoveEntity {
+                    entity,
+                    pos: cursor + offset,
+                });
+            }
+        }
+    } else if primary_released {
+        state.dragging = None;
+    }
+    if !primary_down {
+        state.rect_start = None;
+    }
+
+    if !inputs.is_empty() {
+        world
+            .resource_mut::<LocalEditorInputs>()
+            .inputs
+            .extend(inputs);
+    }
+
+    if close {
+        world.run_system(release_camera, ());
+        session_options.delete = true;
+    }
+}
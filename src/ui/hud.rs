@@ -0,0 +1,140 @@
+//! The in-match HUD, with the kill feed, item pickups, and round announcements.
+
+use crate::prelude::*;
+
+/// The amount of time that an entry stays in the kill feed.
+const FEED_ENTRY_DURATION: Duration = Duration::from_secs(5);
+
+/// The maximum number of entries shown in the kill feed at once.
+const MAX_FEED_ENTRIES: usize = 6;
+
+/// The amount of time that the round number is announced at the start of a round.
+const ROUND_ANNOUNCEMENT_DURATION: Duration = Duration::from_secs(2);
+
+pub fn session_plugin(session: &mut Session) {
+    session.world.init_param::<Localization<GameMeta>>();
+    session.add_system_to_stage(Update, hud_system);
+}
+
+/// Get the localization key for the name of a cause of death.
+fn death_cause_key(cause: DeathCause) -> &'static str {
+    match cause {
+        DeathCause::Unknown => "death-cause-unknown",
+        DeathCause::Bullet => "death-cause-bullet",
+        DeathCause::Sword => "death-cause-sword",
+        DeathCause::Explosion => "death-cause-explosion",
+        DeathCause::Stomp => "death-cause-stomp",
+        DeathCause::Spikes => "death-cause-spikes",
+        DeathCause::Crate => "death-cause-crate",
+        DeathCause::OutOfBounds => "death-cause-out-of-bounds",
+    }
+}
+
+fn hud_system(
+    meta: Root<GameMeta>,
+    localization: Localization<GameMeta>,
+    sessions: Res<Sessions>,
+    ctx: Res<EguiCtx>,
+    assets: Res<AssetServer>,
+) {
+    let Some(session) = sessions.get(SessionNames::GAME) else {
+        return;
+    };
+    // Let the pause menu draw over the game instead.
+    if !session.active {
+        return;
+    }
+    let world = &session.world;
+    let Some(now) = world.get_resource::<Time>().map(|x| x.elapsed()) else {
+        return;
+    };
+    let is_recent = |time: Duration| now.saturating_sub(time) < FEED_ENTRY_DURATION;
+
+    // Collect the recent kills and item pickups
+    let mut entries = Vec::new();
+    if let Some(kill_events) = world.get_resource::<KillEvents>() {
+        for event in kill_events.events.iter().filter(|x| is_recent(x.time)) {
+            let cause = localization.get(death_cause_key(event.cause));
+            let text = match event.killer {
+                Some(killer) if killer != event.victim => localization.get_with(
+                    "kill-feed",
+                    &fluent_args! {
+                        "killer" => killer + 1,
+                        "cause" => cause,
+                        "victim" => event.victim + 1
+                    },
+                ),
+                _ => localization.get_with(
+                    "kill-feed-suicide",
+                    &fluent_args! {
+                        "cause" => cause,
+                        "victim" => event.victim + 1
+                    },
+                ),
+            };
+            entries.push((event.time, text));
+        }
+    }
+    if let Some(pickup_events) = world.get_resource::<ItemPickupEvents>() {
+        for event in pickup_events.events.iter().filter(|x| is_recent(x.time)) {
+            let Some(element) = event.element else {
+                continue;
+            };
+            let text = localization.get_with(
+                "item-pickup",
+                &fluent_args! {
+                    "player" => event.player + 1,
+                    "item" => assets.get(element).name.to_string()
+                },
+            );
+            entries.push((event.time, text));
+        }
+    }
+    entries.sort_by_key(|(time, _)| *time);
+    let skip = entries.len().saturating_sub(MAX_FEED_ENTRIES);
+
+    if !entries.is_empty() {
+        egui::Area::new("kill_feed")
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-8.0, 8.0))
+            .interactable(false)
+            .show(&ctx, |ui| {
+                BorderedFrame::new(&meta.theme.panel.border)
+                    .padding(meta.theme.panel.padding)
+                    .show(ui, |ui| {
+                        let font = meta
+                            .theme
+                            .font_styles
+                            .smaller
+                            .with_color(meta.theme.panel.font_color);
+                        for (_, text) in entries.into_iter().skip(skip) {
+                            ui.label(font.rich(text));
+                        }
+                    });
+            });
+    }
+
+    // Announce the round when it starts
+    let has_rounds = world
+        .get_resource::<MatchInputs>()
+        .map_or(false, |x| has_rounds(&x));
+    if has_rounds && now < ROUND_ANNOUNCEMENT_DURATION {
+        let round = world.get_resource::<MatchScore>().map_or(0, |x| x.round);
+        egui::Area::new("round_announcement")
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 32.0))
+            .interactable(false)
+            .show(&ctx, |ui| {
+                ui.label(
+                    meta.theme
+                        .font_styles
+                        .heading
+                        .rich(localization.get_with(
+                            "round-start",
+                            &fluent_args! {
+                                "round" => round + 1
+                            },
+                        ))
+                        .color(meta.theme.panel.font_color),
+                );
+            });
+    }
+}
@@ -4,6 +4,8 @@ use super::ImageMeta;
 
 mod credits;
 mod map_select;
+#[cfg(not(target_arch = "wasm32"))]
+mod network_game;
 mod player_select;
 mod settings;
 
@@ -88,7 +90,10 @@ fn main_menu_system(world: &World) {
             MenuPage::PlayerSelect => world.run_initialized_system(player_select::widget, ui),
             MenuPage::MapSelect { .. } => world.run_initialized_system(map_select::widget, ui),
             MenuPage::Credits => world.run_initialized_system(credits::widget, ui),
-            MenuPage::NetworkGame => todo!(),
+            #[cfg(not(target_arch = "wasm32"))]
+            MenuPage::NetworkGame => world.run_initialized_system(network_game::widget, ui),
+            #[cfg(target_arch = "wasm32")]
+            MenuPage::NetworkGame => (),
         });
 }
 
@@ -128,16 +133,18 @@ fn home_menu(
                     ui.ctx().set_state(MenuPage::PlayerSelect);
                 }
 
-                // // Online game
-                // #[cfg(not(target_arch = "wasm32"))]
-                // if BorderedButton::themed(
-                //     &meta.theme.buttons.normal,
-                //     localization.get("online-game"),
-                // )
-                // .min_size(vec2(ui.available_width(), 0.0))
-                // .show(ui)
-                // .clicked()
-                // {}
+                // Online game
+                #[cfg(not(target_arch = "wasm32"))]
+                if BorderedButton::themed(
+                    &meta.theme.buttons.normal,
+                    localization.get("online-game"),
+                )
+                .min_size(vec2(ui.available_width(), 0.0))
+                .show(ui)
+                .clicked()
+                {
+                    ui.ctx().set_state(MenuPage::NetworkGame);
+                }
 
                 // Settings
                 if BorderedButton::themed(&meta.theme.buttons.normal, localization.get("settings"))
@@ -7,12 +7,19 @@ use super::player_select::PlayerSelectState;
 use super::MenuPage;
 
 pub fn widget(
-    ui: In<&mut egui::Ui>,
+    mut ui: In<&mut egui::Ui>,
     world: &World,
     mut sessions: ResMut<Sessions>,
     mut session_options: ResMut<SessionOptions>,
 ) {
-    let select_action = world.run_initialized_system(map_select_menu, ());
+    let player_select_state = ui.ctx().get_state::<PlayerSelectState>();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if player_select_state.network_socket.is_some() {
+        return world.run_initialized_system(network_widget, &mut **ui);
+    }
+
+    let select_action = world.run_initialized_system(map_select_menu, true);
 
     match select_action {
         MapSelectAction::None => (),
@@ -20,24 +27,129 @@ pub fn widget(
             session_options.delete = true;
             ui.ctx().set_state(MenuPage::Home);
 
-            let player_select_state = ui.ctx().get_state::<PlayerSelectState>();
             sessions.start_game(MatchPlugin {
                 map: map_meta,
-                player_info: std::array::from_fn(|i| {
-                    let slot = player_select_state.slots[i];
-
-                    PlayerInput {
-                        active: slot.active,
-                        selected_player: slot.selected_player,
-                        selected_hat: slot.selected_hat,
-                        control_source: slot.control_source,
-                        editor_input: default(),
-                        control: default(),
-                    }
-                }),
+                player_info: player_info(&player_select_state),
             });
             ui.ctx().set_state(PlayerSelectState::default());
         }
+        MapSelectAction::SelectPlaylist { playlist, map } => {
+            session_options.delete = true;
+            ui.ctx().set_state(MenuPage::Home);
+
+            sessions.start_playlist(
+                MatchPlugin {
+                    map,
+                    player_info: player_info(&player_select_state),
+                },
+                playlist,
+            );
+            ui.ctx().set_state(PlayerSelectState::default());
+        }
         MapSelectAction::GoBack => ui.ctx().set_state(MenuPage::PlayerSelect),
     }
 }
+
+/// Get the match players from the player selection.
+fn player_info(player_select_state: &PlayerSelectState) -> [PlayerInput; MAX_PLAYERS] {
+    std::array::from_fn(|i| {
+        let slot = player_select_state.slots[i];
+
+        PlayerInput {
+            active: slot.active,
+            selected_player: slot.selected_player,
+            selected_hat: slot.selected_hat,
+            control_source: slot.control_source,
+            ai_difficulty: slot.ai_difficulty,
+            editor_input: default(),
+            control: default(),
+        }
+    })
+}
+
+/// Map selection in online matches, where the host picks the map and everybody else waits for it.
+#[cfg(not(target_arch = "wasm32"))]
+fn network_widget(
+    mut ui: In<&mut egui::Ui>,
+    world: &World,
+    meta: Root<GameMeta>,
+    assets: Res<AssetServer>,
+    localization: Localization<GameMeta>,
+    mut sessions: ResMut<Sessions>,
+    mut session_options: ResMut<SessionOptions>,
+) {
+    use crate::networking::{MatchSetupMessage, SocketTarget};
+
+    let ui = &mut *ui;
+    let mut state = ui.ctx().get_state::<PlayerSelectState>();
+    let Some(socket) = state.network_socket.clone() else {
+        return;
+    };
+
+    let selected_map = if socket.player_idx() == 0 {
+        // Playlists aren't offered, because only the first map is sent to the other players.
+        match world.run_initialized_system(map_select_menu, false) {
+            MapSelectAction::None => None,
+            MapSelectAction::SelectMap(map_meta) => {
+                let seed = THREAD_RNG.with(|rng| rng.u64(..));
+                socket.send_message(
+                    SocketTarget::All,
+                    &MatchSetupMessage::SelectMap {
+                        map: map_meta.name.to_string(),
+                        seed,
+                    },
+                );
+                Some((map_meta, seed))
+            }
+            // Everybody has already confirmed their selection, so we can't go back.
+            MapSelectAction::SelectPlaylist { .. } | MapSelectAction::GoBack => None,
+        }
+    } else {
+        for (_, message) in socket.recv_messages::<MatchSetupMessage>() {
+            if let MatchSetupMessage::SelectMap { map, seed } = message {
+                state.selected_map = Some((map, seed));
+            }
+        }
+
+        ui.vertical_centered(|ui| {
+            ui.add_space(meta.theme.font_styles.heading.size);
+            ui.label(
+                meta.theme
+                    .font_styles
+                    .bigger
+                    .rich(localization.get("waiting-for-map")),
+            );
+        });
+
+        state.selected_map.take().and_then(|(name, seed)| {
+            let map_meta = meta
+                .core
+                .stable_maps
+                .iter()
+                .chain(meta.core.experimental_maps.iter())
+                .map(|handle| assets.get(*handle))
+                .find(|map| map.name.as_str() == name)
+                .map(|map| (*map).clone());
+            if map_meta.is_none() {
+                error!("The host selected the map `{name}`, which we don't have");
+            }
+            map_meta.map(|map_meta| (map_meta, seed))
+        })
+    };
+    ui.ctx().set_state(state.clone());
+
+    if let Some((map_meta, seed)) = selected_map {
+        session_options.delete = true;
+        ui.ctx().set_state(MenuPage::Home);
+
+        sessions.start_network_game(
+            MatchPlugin {
+                map: map_meta,
+                player_info: player_info(&state),
+            },
+            seed,
+            socket,
+        );
+        ui.ctx().set_state(PlayerSelectState::default());
+    }
+}
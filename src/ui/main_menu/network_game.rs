@@ -0,0 +1,306 @@
+use crate::{
+    networking::{ClientMessage, LobbyClient, LobbyId, NetworkMatchSocket, ServerMessage},
+    settings::Settings,
+};
+
+use super::{player_select::PlayerSelectState, *};
+
+#[derive(Clone, Default, Debug)]
+struct NetworkGameState {
+    /// The connection to the matchmaking server, if we are connecting or in a lobby.
+    client: Option<LobbyClient>,
+    lobby: Option<LobbyInfo>,
+    /// The lobby code typed in by the player.
+    lobby_code: String,
+    /// The number of players to host a lobby for.
+    max_players: u32,
+    error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct LobbyInfo {
+    id: LobbyId,
+    player_idx: u32,
+    player_count: u32,
+    max_players: u32,
+}
+
+impl NetworkGameState {
+    fn leave(&mut self) {
+        if let Some(client) = self.client.take() {
+            client.close();
+        }
+        self.lobby = None;
+    }
+}
+
+pub fn widget(
+    mut ui: In<&mut egui::Ui>,
+    meta: Root<GameMeta>,
+    localization: Localization<GameMeta>,
+    controls: Res<GlobalPlayerControls>,
+    storage: Res<Storage>,
+) {
+    let ui = &mut *ui;
+    let mut state = ui.ctx().get_state::<NetworkGameState>();
+    if state.max_players == 0 {
+        state.max_players = 2;
+    }
+
+    let heading_font = meta
+        .theme
+        .font_styles
+        .heading
+        .with_color(meta.theme.panel.font_color);
+    let bigger_font = meta
+        .theme
+        .font_styles
+        .bigger
+        .with_color(meta.theme.panel.font_color);
+    let normal_font = meta
+        .theme
+        .font_styles
+        .normal
+        .with_color(meta.theme.panel.font_color);
+
+    // Handle messages from the matchmaking server
+    if let Some(client) = state.client.clone() {
+        while let Some(message) = client.try_recv() {
+            match message {
+                ServerMessage::LobbyJoined { lobby, player_idx } => {
+                    let info = state.lobby.get_or_insert(LobbyInfo {
+                        id: lobby,
+                        player_idx,
+                        player_count: 1,
+                        max_players: 1,
+                    });
+                    info.id = lobby;
+                    info.player_idx = player_idx;
+                    state.error = None;
+                }
+                ServerMessage::LobbyUpdated {
+                    player_count,
+                    max_players,
+                } => {
+                    if let Some(info) = &mut state.lobby {
+                        info.player_count = player_count;
+                        info.max_players = max_players;
+                    }
+                }
+                ServerMessage::MatchStarted {
+                    player_idx,
+                    player_ips,
+                } => {
+                    // Hand the connection over to the player select menu.
+                    ui.ctx().set_state(PlayerSelectState {
+                        network_socket: Some(NetworkMatchSocket::new(
+                            client.clone(),
+                            player_idx,
+                            player_ips,
+                        )),
+                        ..default()
+                    });
+                    ui.ctx().set_state(NetworkGameState::default());
+                    ui.ctx().set_state(MenuPage::PlayerSelect);
+                    return;
+                }
+                ServerMessage::Error(e) => {
+                    warn!("Matchmaking error: {e}");
+                    state.error = Some(e);
+                    // Errors before joining a lobby mean that we failed to host or join one.
+                    if state.lobby.is_none() {
+                        state.leave();
+                    }
+                }
+                ServerMessage::Relay { .. } | ServerMessage::PlayerDisconnected { .. } => (),
+            }
+        }
+
+        if client.is_closed() && state.client.is_some() {
+            state.leave();
+        }
+    }
+
+    let go_back = controls.values().any(|x| x.menu_back_just_pressed);
+
+    BorderedFrame::new(&meta.theme.panel.border)
+        .margin(egui::style::Margin::symmetric(
+            ui.available_width() * 0.2,
+            meta.theme.font_styles.bigger.size,
+        ))
+        .padding(meta.theme.panel.padding)
+        .show(ui, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.label(heading_font.rich(localization.get("online-game")));
+                ui.add_space(normal_font.size);
+
+                if let Some(error) = &state.error {
+                    ui.label(normal_font.rich(format!("{}: {error}", localization.get("error"))));
+                    ui.add_space(normal_font.size);
+                }
+
+                if let Some(info) = state.lobby {
+                    ui.label(bigger_font.rich(format!(
+                        "{}: {}",
+                        localization.get("lobby-code"),
+                        info.id
+                    )));
+                    ui.label(normal_font.rich(localization.get_with(
+                        "waiting-for-players",
+                        &fluent_args! {
+                            "current" => info.player_count,
+                            "total" => info.max_players
+                        },
+                    )));
+                    ui.add_space(normal_font.size);
+
+                    for i in 0..info.player_count {
+                        let mut label = localization.get_with(
+                            "player-number",
+                            &fluent_args! {
+                                "number" => i + 1
+                            },
+                        );
+                        if i == info.player_idx {
+                            label = format!("{label} {}", localization.get("you-marker"));
+                        }
+                        ui.label(normal_font.rich(label));
+                    }
+                    ui.add_space(normal_font.size);
+
+                    // Only the host may start the match
+                    if info.player_idx == 0
+                        && ui
+                            .add_enabled_ui(info.player_count > 1, |ui| {
+                                BorderedButton::themed(
+                                    &meta.theme.buttons.normal,
+                                    localization.get("start-match"),
+                                )
+                                .min_size(vec2(ui.available_width() / 2.0, 0.0))
+                                .show(ui)
+                                .focus_by_default(ui)
+                            })
+                            .inner
+                            .clicked()
+                    {
+                        if let Some(client) = &state.client {
+                            client.send(ClientMessage::StartMatch);
+                        }
+                    }
+
+                    if BorderedButton::themed(
+                        &meta.theme.buttons.normal,
+                        localization.get("leave-lobby"),
+                    )
+                    .min_size(vec2(ui.available_width() / 2.0, 0.0))
+                    .show(ui)
+                    .clicked()
+                        || go_back
+                    {
+                        state.leave();
+                    }
+                } else if state.client.is_some() {
+                    ui.label(bigger_font.rich(localization.get("connecting")));
+                    ui.add_space(normal_font.size);
+
+                    if BorderedButton::themed(
+                        &meta.theme.buttons.normal,
+                        localization.get("cancel"),
+                    )
+                    .min_size(vec2(ui.available_width() / 2.0, 0.0))
+                    .show(ui)
+                    .focus_by_default(ui)
+                    .clicked()
+                        || go_back
+                    {
+                        state.leave();
+                    }
+                } else {
+                    let server = &storage.get::<Settings>().unwrap().matchmaking_server;
+
+                    // Host a lobby
+                    ui.horizontal(|ui| {
+                        ui.label(normal_font.rich(localization.get("player-count")));
+                        if ui
+                            .add_enabled_ui(state.max_players > 2, |ui| {
+                                BorderedButton::themed(&meta.theme.buttons.small, "-").show(ui)
+                            })
+                            .inner
+                            .clicked()
+                        {
+                            state.max_players -= 1;
+                        }
+                        ui.label(normal_font.rich(state.max_players.to_string()));
+                        if ui
+                            .add_enabled_ui(state.max_players < MAX_PLAYERS as u32, |ui| {
+                                BorderedButton::themed(&meta.theme.buttons.small, "+").show(ui)
+                            })
+                            .inner
+                            .clicked()
+                        {
+                            state.max_players += 1;
+                        }
+                    });
+                    if BorderedButton::themed(&meta.theme.buttons.normal, localization.get("host"))
+                        .min_size(vec2(ui.available_width() / 2.0, 0.0))
+                        .show(ui)
+                        .focus_by_default(ui)
+                        .clicked()
+                    {
+                        let client = LobbyClient::connect(server);
+                        client.send(ClientMessage::HostLobby {
+                            max_players: state.max_players,
+                        });
+                        state.client = Some(client);
+                        state.error = None;
+                    }
+
+                    ui.add_space(normal_font.size);
+
+                    // Join a lobby
+                    ui.horizontal(|ui| {
+                        ui.label(normal_font.rich(localization.get("lobby-code")));
+                        ui.add(
+                            egui::TextEdit::singleline(&mut state.lobby_code)
+                                .font(normal_font.clone())
+                                .desired_width(normal_font.size * 4.0),
+                        );
+                    });
+                    let lobby = state.lobby_code.trim().parse::<LobbyId>().ok();
+                    if ui
+                        .add_enabled_ui(lobby.is_some(), |ui| {
+                            BorderedButton::themed(
+                                &meta.theme.buttons.normal,
+                                localization.get("join"),
+                            )
+                            .min_size(vec2(ui.available_width() / 2.0, 0.0))
+                            .show(ui)
+                        })
+                        .inner
+                        .clicked()
+                    {
+                        if let Some(lobby) = lobby {
+                            let client = LobbyClient::connect(server);
+                            client.send(ClientMessage::JoinLobby { lobby });
+                            state.client = Some(client);
+                            state.error = None;
+                        }
+                    }
+
+                    ui.add_space(normal_font.size);
+
+                    if BorderedButton::themed(&meta.theme.buttons.normal, localization.get("back"))
+                        .min_size(vec2(ui.available_width() / 2.0, 0.0))
+                        .show(ui)
+                        .clicked()
+                        || go_back
+                    {
+                        state.error = None;
+                        ui.ctx().set_state(MenuPage::Home);
+                    }
+                }
+            });
+        });
+
+    ui.ctx().set_state(state);
+}
@@ -1,5 +1,5 @@
-// #[cfg(not(target_arch = "wasm32"))]
-// use crate::networking::{NetworkMatchSocket, SocketTarget};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::networking::{MatchSetupMessage, NetworkMatchSocket, SocketTarget};
 
 use super::*;
 
@@ -9,6 +9,13 @@ use super::*;
 #[derive(Default, Clone, Debug)]
 pub struct PlayerSelectState {
     pub slots: [PlayerSlot; MAX_PLAYERS],
+    /// The connection to the other players, if this is an online match.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub network_socket: Option<NetworkMatchSocket>,
+    /// The map name and random seed picked by the host of an online match, if they have already
+    /// arrived.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub selected_map: Option<(String, u64)>,
 }
 
 #[derive(Default, Clone, Copy, Debug)]
@@ -18,6 +25,8 @@ pub struct PlayerSlot {
     pub selected_player: Handle<PlayerMeta>,
     pub selected_hat: Option<Handle<HatMeta>>,
     pub control_source: Option<ControlSource>,
+    /// The difficulty of the slot if it is an AI player.
+    pub ai_difficulty: AiDifficulty,
 }
 
 impl PlayerSlot {
@@ -26,14 +35,6 @@ impl PlayerSlot {
     }
 }
 
-// /// Network message that may be sent during player selection.
-// #[derive(Serialize, Deserialize)]
-// pub enum PlayerSelectMessage {
-//     SelectPlayer(Handle<PlayerMeta>),
-//     SelectHat(Option<Handle<HatMeta>>),
-//     ConfirmSelection(bool),
-// }
-
 pub fn widget(
     mut ui: In<&mut egui::Ui>,
     meta: Root<GameMeta>,
@@ -41,15 +42,18 @@ pub fn widget(
     controls: Res<GlobalPlayerControls>,
     world: &World,
 ) {
-    let is_online = false;
     let mut state = ui.ctx().get_state::<PlayerSelectState>();
+    #[cfg(not(target_arch = "wasm32"))]
+    let is_online = state.network_socket.is_some();
+    #[cfg(target_arch = "wasm32")]
+    let is_online = false;
     ui.ctx().set_state(EguiInputSettings {
         disable_keyboard_input: true,
         disable_gamepad_input: true,
     });
 
-    // #[cfg(not(target_arch = "wasm32"))]
-    // handle_match_setup_messages(&mut params);
+    #[cfg(not(target_arch = "wasm32"))]
+    handle_match_setup_messages(&mut state, &meta);
 
     // Whether or not the continue button should be enabled
     let mut ready_players = 0;
@@ -64,15 +68,16 @@ pub fn widget(
     }
     let may_continue = ready_players >= 1 && unconfirmed_players == 0;
 
-    // #[cfg(not(target_arch = "wasm32"))]
-    // if let Some(socket) = &params.network_socket {
-    //     if may_continue {
-    //         // The first player picks the map
-    //         let is_waiting = socket.player_idx() != 0;
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(socket) = &state.network_socket {
+        if may_continue {
+            // The first player picks the map
+            let is_waiting = socket.player_idx() != 0;
 
-    //         *params.menu_page = MenuPage::MapSelect { is_waiting };
-    //     }
-    // }
+            ui.ctx().set_state(MenuPage::MapSelect { is_waiting });
+            ui.ctx().set_state(EguiInputSettings::default());
+        }
+    }
 
     let bigger_text_style = &meta
         .theme
@@ -126,10 +131,10 @@ pub fn widget(
                     ui.ctx().set_state(EguiInputSettings::default());
                     ui.ctx().set_state(PlayerSelectState::default());
 
-                    // #[cfg(not(target_arch = "wasm32"))]
-                    // if let Some(socket) = params.network_socket {
-                    //     socket.close();
-                    // }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(socket) = state.network_socket.take() {
+                        socket.close();
+                    }
                 }
 
                 ui.add_space(button_spacing);
@@ -173,29 +178,38 @@ pub fn widget(
     ui.ctx().set_state(state);
 }
 
-// #[cfg(not(target_arch = "wasm32"))]
-// fn handle_match_setup_messages(params: &mut PlayerSelectMenu) {
-//     if let Some(socket) = &params.network_socket {
-//         let datas: Vec<(usize, Vec<u8>)> = socket.recv_reliable();
-
-//         for (player, data) in datas {
-//             match postcard::from_bytes::<PlayerSelectMessage>(&data) {
-//                 Ok(message) => match message {
-//                     PlayerSelectMessage::SelectPlayer(player_handle) => {
-//                         params.player_select_state.slots[player].selected_player = player_handle;
-//                     }
-//                     PlayerSelectMessage::ConfirmSelection(confirmed) => {
-//                         params.player_select_state.slots[player].confirmed = confirmed;
-//                     }
-//                     PlayerSelectMessage::SelectHat(hat) => {
-//                         params.player_select_state.slots[player].selected_hat = hat;
-//                     }
-//                 },
-//                 Err(e) => warn!("Ignoring network message that was not understood: {e}"),
-//             }
-//         }
-//     }
-// }
+/// Apply the selections of the other players in an online match.
+#[cfg(not(target_arch = "wasm32"))]
+fn handle_match_setup_messages(state: &mut PlayerSelectState, meta: &GameMeta) {
+    let Some(socket) = state.network_socket.clone() else {
+        return;
+    };
+
+    // Every other player in the match has a slot that is controlled remotely.
+    for i in (0..socket.player_count()).filter(|&i| i != socket.player_idx()) {
+        let slot = &mut state.slots[i as usize];
+        slot.active = true;
+        slot.control_source = Some(ControlSource::Remote(i));
+    }
+
+    for (player, message) in socket.recv_messages::<MatchSetupMessage>() {
+        let Some(slot) = state.slots.get_mut(player as usize) else {
+            continue;
+        };
+        match message {
+            MatchSetupMessage::SelectPlayer(idx) => {
+                if let Some(handle) = meta.core.players.get(idx) {
+                    slot.selected_player = *handle;
+                }
+            }
+            MatchSetupMessage::SelectHat(idx) => {
+                slot.selected_hat = idx.and_then(|idx| meta.core.player_hats.get(idx).copied());
+            }
+            MatchSetupMessage::ConfirmSelection(confirmed) => slot.confirmed = confirmed,
+            MatchSetupMessage::SelectMap { map, seed } => state.selected_map = Some((map, seed)),
+        }
+    }
+}
 
 fn player_select_panel(
     mut params: In<(&mut egui::Ui, usize, &mut PlayerSelectState)>,
@@ -208,61 +222,28 @@ fn player_select_panel(
 ) {
     let (ui, slot_id, state) = &mut *params;
 
+    #[cfg(not(target_arch = "wasm32"))]
+    let network_socket = state.network_socket.clone();
+    #[cfg(not(target_arch = "wasm32"))]
+    let is_network = network_socket.is_some();
+    #[cfg(target_arch = "wasm32")]
     let is_network = false;
-    // #[cfg(target_arch = "wasm32")]
-    // let is_network = false;
-    // #[cfg(not(target_arch = "wasm32"))]
-    // let is_network = params.network_socket.is_some();
-
-    // let player_map = params
-    //     .players
-    //     .iter()
-    //     .find(|(player_idx, _, _)| player_idx.0 == player_id)
-    //     .unwrap()
-    //     .2;
-
-    // #[cfg(not(target_arch = "wasm32"))]
-    // let dummy_actions = default();
-    // let (player_actions, player_action_map) = if is_network {
-    //     // #[cfg(not(target_arch = "wasm32"))]
-    //     // if let Some(socket) = &params.network_socket {
-    //     //     let actions = if player_id == socket.player_idx() {
-    //     //         params
-    //     //             .players
-    //     //             .iter()
-    //     //             .find(|(player_idx, _, _)| player_idx.0 == 0)
-    //     //             .unwrap()
-    //     //             .1
-    //     //     } else {
-    //     //         &dummy_actions
-    //     //     };
-    //     //     let map = None;
-    //     //     (actions, map)
-    //     // } else {
-    //     //     unreachable!();
-    //     // }
-
-    //     // #[cfg(target_arch = "wasm32")]
-    //     // unreachable!()
-    // } else {
-    //     let actions = players
-    //         .iter()
-    //         .find(|(player_idx, _, _)| player_idx.0 == player_id)
-    //         .unwrap()
-    //         .1;
-    //     let map = Some(get_player_actions(player_id, player_map));
-    //     (actions, map)
-    // };
-
-    // #[cfg(not(target_arch = "wasm32"))]
-    // if let Some(socket) = &params.network_socket {
-    //     // Don't show panels for non-connected players.
-    //     if player_id + 1 > socket.player_count() {
-    //         return;
-    //     } else {
-    //         slot.active = true;
-    //     }
-    // }
+
+    // Send a message to the other players in an online match.
+    #[cfg(not(target_arch = "wasm32"))]
+    let send = |message: MatchSetupMessage| {
+        if let Some(socket) = &network_socket {
+            socket.send_message(SocketTarget::All, &message);
+        }
+    };
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(socket) = &network_socket {
+        // Don't show panels for non-connected players.
+        if *slot_id as u32 >= socket.player_count() {
+            return;
+        }
+    }
 
     // Get the ID of the first un-occupied slot
     let next_open_slot = state
@@ -270,6 +251,12 @@ fn player_select_panel(
         .iter()
         .enumerate()
         .find_map(|(i, slot)| (!slot.active).then_some(i));
+    // In online matches the local player can only join their own slot.
+    #[cfg(not(target_arch = "wasm32"))]
+    let next_open_slot = match &network_socket {
+        Some(socket) => Some(socket.player_idx() as usize).filter(|&i| !state.slots[i].active),
+        None => next_open_slot,
+    };
 
     // Check if a new player is trying to join
     let new_player_join = controls.iter().find_map(|(source, control)| {
@@ -326,20 +313,14 @@ fn player_select_panel(
     let player_control = slot
         .control_source
         .as_ref()
-        .map(|s| *controls.get(s).unwrap())
+        .and_then(|s| controls.get(s).copied())
         .unwrap_or_default();
 
     if player_control.menu_confirm_just_pressed && new_player_join.is_none() {
         slot.confirmed = true;
 
-        // #[cfg(not(target_arch = "wasm32"))]
-        // if let Some(socket) = &params.network_socket {
-        //     socket.send_reliable(
-        //         SocketTarget::All,
-        //         &postcard::to_allocvec(&PlayerSelectMessage::ConfirmSelection(slot.confirmed))
-        //             .unwrap(),
-        //     );
-        // }
+        #[cfg(not(target_arch = "wasm32"))]
+        send(MatchSetupMessage::ConfirmSelection(slot.confirmed));
     } else if player_control.menu_back_just_pressed {
         if !is_network {
             if slot.confirmed {
@@ -352,14 +333,8 @@ fn player_select_panel(
             slot.confirmed = false;
         }
 
-        // #[cfg(not(target_arch = "wasm32"))]
-        // if let Some(socket) = &params.network_socket {
-        //     socket.send_reliable(
-        //         SocketTarget::All,
-        //         &postcard::to_allocvec(&PlayerSelectMessage::ConfirmSelection(slot.confirmed))
-        //             .unwrap(),
-        //     );
-        // }
+        #[cfg(not(target_arch = "wasm32"))]
+        send(MatchSetupMessage::ConfirmSelection(slot.confirmed));
     } else if player_control.just_moved {
         let direction = player_control.move_direction;
 
@@ -392,14 +367,8 @@ fn player_select_panel(
             };
             slot.selected_hat = next_idx.map(|idx| *meta.core.player_hats.get(idx).unwrap());
 
-            // #[cfg(not(target_arch = "wasm32"))]
-            // if let Some(socket) = &params.network_socket {
-            //     socket.send_reliable(
-            //         SocketTarget::All,
-            //         &postcard::to_allocvec(&PlayerSelectMessage::SelectHat(player_hat.clone()))
-            //             .unwrap(),
-            //     );
-            // }
+            #[cfg(not(target_arch = "wasm32"))]
+            send(MatchSetupMessage::SelectHat(next_idx));
 
             // Select player skin if the player has not be confirmed
         } else {
@@ -432,16 +401,10 @@ fn player_select_panel(
                 }
             }
 
-            // #[cfg(not(target_arch = "wasm32"))]
-            // if let Some(socket) = &params.network_socket {
-            //     socket.send_reliable(
-            //         SocketTarget::All,
-            //         &postcard::to_allocvec(&PlayerSelectMessage::SelectPlayer(
-            //             player_handle.clone(),
-            //         ))
-            //         .unwrap(),
-            //     );
-            // }
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(idx) = meta.core.players.iter().position(|x| x == player_handle) {
+                send(MatchSetupMessage::SelectPlayer(idx));
+            }
         }
     }
 
@@ -457,38 +420,25 @@ fn player_select_panel(
             let heading_font = &meta.theme.font_styles.heading.with_color(panel.font_color);
 
             // Marker for current player in online matches
-            // #[cfg(not(target_arch = "wasm32"))]
-            // if let Some(socket) = &params.network_socket {
-            //     if socket.player_idx() == player_id {
-            //         ui.vertical_centered(|ui| {
-            //             ui.themed_label(normal_font, &params.localization.get("you-marker"));
-            //         });
-            //     } else {
-            //         ui.add_space(normal_font.size);
-            //     }
-            // } else {
-            //     ui.add_space(normal_font.size);
-            // }
-
-            ui.add_space(normal_font.size);
+            #[cfg(not(target_arch = "wasm32"))]
+            let is_local_player = network_socket
+                .as_ref()
+                .is_some_and(|socket| socket.player_idx() as usize == *slot_id);
+            #[cfg(target_arch = "wasm32")]
+            let is_local_player = false;
+            if is_local_player {
+                ui.vertical_centered(|ui| {
+                    ui.label(normal_font.rich(localization.get("you-marker")));
+                });
+            } else {
+                ui.add_space(normal_font.size);
+            }
 
             if slot.active {
-                let confirm_binding = slot.control_source.map(|s| {
-                    match s {
-                        ControlSource::Keyboard1 => &mapping.keyboard1.menu_confirm,
-                        ControlSource::Keyboard2 => &mapping.keyboard2.menu_confirm,
-                        ControlSource::Gamepad(_) => &mapping.gamepad.menu_confirm,
-                    }
-                    .to_string()
-                });
-                let back_binding = slot.control_source.map(|s| {
-                    match s {
-                        ControlSource::Keyboard1 => &mapping.keyboard1.menu_back,
-                        ControlSource::Keyboard2 => &mapping.keyboard2.menu_back,
-                        ControlSource::Gamepad(_) => &mapping.gamepad.menu_back,
-                    }
-                    .to_string()
-                });
+                // Remote and AI players don't have any bindings
+                let setting = slot.control_source.and_then(|s| mapping.for_source(s));
+                let confirm_binding = setting.map(|x| x.menu_confirm.to_string());
+                let back_binding = setting.map(|x| x.menu_back.to_string());
                 ui.vertical_centered(|ui| {
                     let player_meta = asset_server.get(slot.selected_player);
                     let hat_meta = slot
@@ -499,19 +449,23 @@ fn player_select_panel(
                     ui.label(normal_font.rich(localization.get("pick-a-fish")));
 
                     if !slot.confirmed {
-                        ui.label(normal_font.rich(localization.get_with(
-                            "press-button-to-lock-in",
-                            &fluent_args! {
-                                "button" => confirm_binding.as_ref().unwrap().as_str()
-                            },
-                        )));
-
-                        ui.label(normal_font.rich(localization.get_with(
-                            "press-button-to-remove",
-                            &fluent_args! {
-                                "button" => back_binding.as_ref().unwrap().as_str()
-                            },
-                        )));
+                        if let (Some(confirm_binding), Some(back_binding)) =
+                            (&confirm_binding, &back_binding)
+                        {
+                            ui.label(normal_font.rich(localization.get_with(
+                                "press-button-to-lock-in",
+                                &fluent_args! {
+                                    "button" => confirm_binding.as_str()
+                                },
+                            )));
+
+                            ui.label(normal_font.rich(localization.get_with(
+                                "press-button-to-remove",
+                                &fluent_args! {
+                                    "button" => back_binding.as_str()
+                                },
+                            )));
+                        }
                     } else {
                         ui.label(normal_font.rich(localization.get("waiting")));
                     }
@@ -525,13 +479,15 @@ fn player_select_panel(
                                     .with_color(meta.theme.colors.positive)
                                     .rich(localization.get("player-select-ready")),
                             );
-                            ui.add_space(normal_font.size / 2.0);
-                            ui.label(normal_font.rich(localization.get_with(
-                                "player-select-unready",
-                                &fluent_args! {
-                                    "button" => back_binding.as_ref().unwrap().as_str()
-                                },
-                            )));
+                            if let Some(back_binding) = &back_binding {
+                                ui.add_space(normal_font.size / 2.0);
+                                ui.label(normal_font.rich(localization.get_with(
+                                    "player-select-unready",
+                                    &fluent_args! {
+                                        "button" => back_binding.as_str()
+                                    },
+                                )));
+                            }
                         }
                         if slot.is_ai() {
                             ui.label(
@@ -540,6 +496,20 @@ fn player_select_panel(
                                     .rich(localization.get("ai-player")),
                             );
                             ui.add_space(normal_font.size / 2.0);
+                            if BorderedButton::themed(
+                                &meta.theme.buttons.normal,
+                                format!(
+                                    "<  {}  >",
+                                    localization.get(slot.ai_difficulty.localization_key())
+                                ),
+                            )
+                            .show(ui)
+                            .on_hover_text(localization.get("ai-difficulty"))
+                            .clicked()
+                            {
+                                slot.ai_difficulty = slot.ai_difficulty.next();
+                            }
+                            ui.add_space(normal_font.size / 2.0);
                             if BorderedButton::themed(
                                 &meta.theme.buttons.normal,
                                 localization.get("remove-ai-player"),
@@ -550,6 +520,7 @@ fn player_select_panel(
                                 slot.confirmed = false;
                                 slot.active = false;
                                 slot.control_source = None;
+                                slot.ai_difficulty = default();
                             }
                         }
                     });
@@ -579,11 +550,8 @@ fn player_select_panel(
             } else {
                 let bindings = available_input_sources
                     .into_iter()
-                    .map(|x| match x {
-                        ControlSource::Keyboard1 => mapping.keyboard1.menu_confirm.to_string(),
-                        ControlSource::Keyboard2 => mapping.keyboard2.menu_confirm.to_string(),
-                        ControlSource::Gamepad(_) => mapping.gamepad.menu_confirm.to_string(),
-                    })
+                    .filter_map(|x| mapping.for_source(x))
+                    .map(|x| x.menu_confirm.to_string())
                     .collect::<SmallVec<[_; 3]>>();
 
                 ui.vertical_centered(|ui| {
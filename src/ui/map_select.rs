@@ -5,10 +5,26 @@ pub enum MapSelectAction {
     #[default]
     None,
     SelectMap(MapMeta),
+    /// Play the maps of a playlist, starting with `map`, which is its current map.
+    SelectPlaylist {
+        playlist: MapPlaylist,
+        map: MapMeta,
+    },
     GoBack,
 }
 
+/// The state of the map select menu, which is kept while the menu is closed.
+#[derive(Clone, Debug, Default)]
+struct MapSelectState {
+    include_experimental: bool,
+    /// The maps of the playlist created in the menu.
+    custom_playlist: Vec<Handle<MapMeta>>,
+    shuffle_custom_playlist: bool,
+}
+
+/// The map select menu. Playlists are only offered if `allow_playlists` is set.
 pub fn map_select_menu(
+    allow_playlists: In<bool>,
     asset_server: Res<AssetServer>,
     meta: Root<GameMeta>,
     ctx: Res<EguiCtx>,
@@ -18,8 +34,18 @@ pub fn map_select_menu(
     if player_controls.values().any(|x| x.menu_back_just_pressed) {
         return MapSelectAction::GoBack;
     }
+    let allow_playlists = *allow_playlists;
+    let mut state = ctx.get_state::<MapSelectState>();
+    let select_playlist = |playlist: MapPlaylist| {
+        let map = playlist
+            .current()
+            .map(|handle| (*asset_server.get(handle)).clone());
+        map.map_or(MapSelectAction::None, |map| {
+            MapSelectAction::SelectPlaylist { playlist, map }
+        })
+    };
 
-    egui::CentralPanel::default()
+    let action = egui::CentralPanel::default()
         .frame(egui::Frame::none())
         .show(&ctx, |ui| {
             let screen_rect = ui.max_rect();
@@ -45,16 +71,120 @@ pub fn map_select_menu(
 
                     ui.add_space(meta.theme.font_styles.normal.size);
 
-                    egui::ScrollArea::vertical()
-                        .show(ui, |ui| {
-                            ui.vertical_centered_justified(|ui| {
-                                for (i, handle) in meta.core.stable_maps.iter().enumerate() {
-                                    let map_meta = asset_server.get(*handle);
+                    let mut action = MapSelectAction::None;
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        ui.vertical_centered_justified(|ui| {
+                            if allow_playlists {
+                                ui.label(
+                                    meta.theme
+                                        .font_styles
+                                        .normal
+                                        .rich(localization.get("playlists")),
+                                );
+
+                                for playlist in meta.core.map_playlists.iter() {
+                                    if BorderedButton::themed(
+                                        &meta.theme.buttons.small,
+                                        playlist.name.to_string(),
+                                    )
+                                    .show(ui)
+                                    .clicked()
+                                    {
+                                        let seed = THREAD_RNG.with(|rng| rng.u64(..));
+                                        action =
+                                            select_playlist(MapPlaylist::from_meta(playlist, seed));
+                                    }
+                                }
+
+                                if BorderedButton::themed(
+                                    &meta.theme.buttons.small,
+                                    localization.get("random-maps"),
+                                )
+                                .show(ui)
+                                .clicked()
+                                {
+                                    let seed = THREAD_RNG.with(|rng| rng.u64(..));
+                                    action = select_playlist(MapPlaylist::random_maps(
+                                        &meta.core,
+                                        state.include_experimental,
+                                        seed,
+                                    ));
+                                }
+
+                                if !state.custom_playlist.is_empty() {
+                                    if BorderedButton::themed(
+                                        &meta.theme.buttons.small,
+                                        localization.get_with(
+                                            "custom-playlist",
+                                            &fluent_args! {
+                                                "count" => state.custom_playlist.len()
+                                            },
+                                        ),
+                                    )
+                                    .show(ui)
+                                    .clicked()
+                                    {
+                                        let seed = THREAD_RNG.with(|rng| rng.u64(..));
+                                        action = select_playlist(MapPlaylist::new(
+                                            state.custom_playlist.clone(),
+                                            state.shuffle_custom_playlist,
+                                            seed,
+                                        ));
+                                    }
+                                    ui.horizontal(|ui| {
+                                        ui.checkbox(
+                                            &mut state.shuffle_custom_playlist,
+                                            meta.theme
+                                                .font_styles
+                                                .normal
+                                                .rich(localization.get("shuffle-playlist")),
+                                        );
+                                        if BorderedButton::themed(
+                                            &meta.theme.buttons.small,
+                                            localization.get("clear-playlist"),
+                                        )
+                                        .show(ui)
+                                        .clicked()
+                                        {
+                                            state.custom_playlist.clear();
+                                        }
+                                    });
+                                }
+
+                                ui.checkbox(
+                                    &mut state.include_experimental,
+                                    meta.theme
+                                        .font_styles
+                                        .normal
+                                        .rich(localization.get("include-experimental-maps")),
+                                );
+                                ui.add_space(meta.theme.font_styles.normal.size);
+                            }
+
+                            let experimental_maps = if state.include_experimental {
+                                &meta.core.experimental_maps[..]
+                            } else {
+                                &[]
+                            };
+                            let maps = meta.core.stable_maps.iter().chain(experimental_maps);
+                            for (i, handle) in maps.enumerate() {
+                                let map_meta = asset_server.get(*handle);
+
+                                ui.horizontal(|ui| {
+                                    if allow_playlists
+                                        && BorderedButton::themed(&meta.theme.buttons.small, "+")
+                                            .show(ui)
+                                            .on_hover_text(localization.get("add-to-playlist"))
+                                            .clicked()
+                                    {
+                                        state.custom_playlist.push(*handle);
+                                    }
 
                                     let mut button = BorderedButton::themed(
                                         &meta.theme.buttons.small,
                                         map_meta.name.to_string(),
                                     )
+                                    .min_size(vec2(ui.available_width(), 0.0))
                                     .show(ui);
 
                                     if i == 0 {
@@ -62,17 +192,18 @@ pub fn map_select_menu(
                                     }
 
                                     if button.clicked() {
-                                        return MapSelectAction::SelectMap(map_meta.clone());
+                                        action = MapSelectAction::SelectMap(map_meta.clone());
                                     }
-                                }
-
-                                MapSelectAction::None
-                            })
-                            .inner
-                        })
-                        .inner
+                                });
+                            }
+                        });
+                    });
+                    action
                 })
                 .inner
         })
-        .inner
+        .inner;
+
+    ctx.set_state(state);
+    action
 }
@@ -23,6 +23,7 @@ fn pause_menu_system(
 ) {
     let mut back_to_menu = false;
     let mut restart_game = false;
+    let mut edit_map = false;
     let mut select_map = None;
     if let Some(session) = sessions.get_mut(SessionNames::GAME) {
         let pause_pressed = controls.values().any(|x| x.pause_just_pressed);
@@ -52,19 +53,29 @@ fn pause_menu_system(
 
                                     world.run_initialized_system(
                                         main_pause_menu,
-                                        (ui, session, &mut restart_game, &mut back_to_menu),
+                                        (
+                                            ui,
+                                            session,
+                                            &mut restart_game,
+                                            &mut edit_map,
+                                            &mut back_to_menu,
+                                        ),
                                     );
                                 });
                         });
                 }
                 PauseMenuPage::MapSelect => {
                     let action =
-                        world.run_initialized_system(crate::ui::map_select::map_select_menu, ());
+                        world.run_initialized_system(crate::ui::map_select::map_select_menu, true);
 
                     match action {
                         super::map_select::MapSelectAction::None => (),
                         super::map_select::MapSelectAction::SelectMap(map) => {
-                            select_map = Some(map);
+                            select_map = Some((map, None));
+                            ctx.set_state(PauseMenuPage::Pause);
+                        }
+                        super::map_select::MapSelectAction::SelectPlaylist { playlist, map } => {
+                            select_map = Some((map, Some(playlist)));
                             ctx.set_state(PauseMenuPage::Pause);
                         }
                         super::map_select::MapSelectAction::GoBack => {
@@ -83,7 +94,11 @@ fn pause_menu_system(
         sessions.start_menu();
     } else if restart_game {
         sessions.restart_game();
-    } else if let Some(map) = select_map {
+    } else if edit_map && sessions.get(SessionNames::EDITOR).is_none() {
+        sessions
+            .create(SessionNames::EDITOR)
+            .install_plugin(crate::ui::editor::session_plugin);
+    } else if let Some((map, playlist)) = select_map {
         let match_info = sessions
             .get(SessionNames::GAME)
             .unwrap()
@@ -92,24 +107,29 @@ fn pause_menu_system(
             .deref()
             .clone();
         sessions.end_game();
-        sessions.start_game(crate::core::MatchPlugin {
-            map,
-            player_info: std::array::from_fn(|i| PlayerInput {
-                control: default(),
-                editor_input: default(),
-                ..match_info.players[i]
-            }),
-        })
+        // Without a playlist, the rounds are played on the stable maps.
+        sessions.start_playlist(
+            crate::core::MatchPlugin {
+                map,
+                player_info: std::array::from_fn(|i| PlayerInput {
+                    control: default(),
+                    editor_input: default(),
+                    ..match_info.players[i]
+                }),
+            },
+            playlist.unwrap_or_default(),
+        )
     }
 }
 
 fn main_pause_menu(
-    mut param: In<(&mut egui::Ui, &mut Session, &mut bool, &mut bool)>,
+    mut param: In<(&mut egui::Ui, &mut Session, &mut bool, &mut bool, &mut bool)>,
     meta: Root<GameMeta>,
     localization: Localization<GameMeta>,
     controls: Res<GlobalPlayerControls>,
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))] assets: Res<AssetServer>,
 ) {
-    let (ui, session, restart_game, back_to_menu) = &mut *param;
+    let (ui, session, restart_game, edit_map, back_to_menu) = &mut *param;
 
     // Unpause the game
     if controls.values().any(|x| x.pause_just_pressed) {
@@ -150,9 +170,17 @@ fn main_pause_menu(
             session.active = true;
         }
 
+        // Online matches can't be restarted, edited, or have their map changed by a single player.
+        #[cfg(not(target_arch = "wasm32"))]
+        let is_online = session
+            .world
+            .get_resource::<crate::networking::NetworkMatchSocket>()
+            .is_some();
+        #[cfg(target_arch = "wasm32")]
+        let is_online = false;
+
         // Local game buttons
         ui.scope(|ui| {
-            let is_online = false;
             ui.set_enabled(!is_online);
 
             // Map select button
@@ -177,14 +205,41 @@ fn main_pause_menu(
             }
         });
 
+        // Save replay button
+        #[cfg(not(target_arch = "wasm32"))]
+        if BorderedButton::themed(&meta.theme.buttons.normal, localization.get("save-replay"))
+            .min_size(vec2(width, 0.0))
+            .show(ui)
+            .clicked()
+        {
+            save_replay(&session.world, &assets);
+        }
+
+        // Export map button
+        #[cfg(not(target_arch = "wasm32"))]
+        if BorderedButton::themed(&meta.theme.buttons.normal, localization.get("export-map"))
+            .min_size(vec2(width, 0.0))
+            .show(ui)
+            .clicked()
+        {
+            export_map(&session.world, &assets);
+        }
+
+        // Replays are played back as they were recorded, so they can't be edited.
+        let is_replay = session
+            .world
+            .get_resource::<ReplayRounds>()
+            .is_some_and(|x| x.is_replay());
+
         // Edit button
         ui.scope(|ui| {
+            ui.set_enabled(!is_online && !is_replay);
             if BorderedButton::themed(&meta.theme.buttons.normal, localization.get("edit"))
                 .min_size(vec2(width, 0.0))
                 .show(ui)
                 .clicked()
             {
-                // TODO: show editor.
+                **edit_map = true;
                 session.active = true;
             }
         });
@@ -199,3 +254,35 @@ fn main_pause_menu(
         }
     });
 }
+
+/// Save the replay of the running match to the replay directory.
+#[cfg(not(target_arch = "wasm32"))]
+fn save_replay(world: &World, assets: &AssetServer) {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = replay_dir().join(format!("{timestamp}.{REPLAY_FILE_EXTENSION}"));
+    match ReplayFile::from_world(world, assets).and_then(|replay| replay.save(&path)) {
+        Ok(()) => info!("Saved replay to {}", path.display()),
+        Err(e) => error!("Could not save replay: {e}"),
+    }
+}
+
+/// Export the map of the running match, including any changes made in the editor, to the map
+/// export directory.
+#[cfg(not(target_arch = "wasm32"))]
+fn export_map(world: &World, assets: &AssetServer) {
+    let Some(map_name) = world.get_resource::<SpawnedMapMeta>().map(|x| x.name) else {
+        return;
+    };
+    let file_name: String = map_name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    let path = map_export_dir().join(format!("{file_name}.{MAP_FILE_EXTENSION}"));
+    match MapFile::from_world(world, assets).and_then(|map| map.save(&path)) {
+        Ok(()) => info!("Exported map to {}", path.display()),
+        Err(e) => error!("Could not export map: {e}"),
+    }
+}
@@ -0,0 +1,165 @@
+//! The results screen that is shown once a player has won the match.
+
+use crate::prelude::*;
+
+pub fn session_plugin(session: &mut Session) {
+    session.world.init_param::<Localization<GameMeta>>();
+    session.add_system_to_stage(Update, results_system);
+}
+
+/// Resource containing the results of the match that has just ended.
+#[derive(HasSchema, Clone, Debug, Default)]
+pub struct MatchResults {
+    /// The player that won the match.
+    pub winner: Option<u32>,
+    /// The results of every player that took part in the match.
+    pub players: Vec<PlayerResults>,
+}
+
+/// The results of a single player.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlayerResults {
+    /// The index of the player.
+    pub player_idx: u32,
+    /// The number of rounds that the player has won.
+    pub rounds_won: u32,
+    /// The statistics of the player.
+    pub stats: PlayerStats,
+}
+
+impl MatchResults {
+    /// Collect the results from the world of a game session.
+    pub fn from_world(world: &World) -> Self {
+        let score = *world.resource::<MatchScore>();
+        let stats = *world.resource::<MatchStats>();
+        let winner = match *world.resource::<RoundState>() {
+            RoundState::MatchOver { winner } => Some(winner),
+            _ => None,
+        };
+        let players = world
+            .resource::<MatchInputs>()
+            .players
+            .iter()
+            .enumerate()
+            .filter(|(_, player)| player.active)
+            .map(|(i, _)| PlayerResults {
+                player_idx: i as u32,
+                rounds_won: score.scores[i],
+                stats: stats.players[i],
+            })
+            .collect();
+
+        Self { winner, players }
+    }
+}
+
+fn results_system(
+    meta: Root<GameMeta>,
+    localization: Localization<GameMeta>,
+    results: Res<MatchResults>,
+    mut sessions: ResMut<Sessions>,
+    mut session_options: ResMut<SessionOptions>,
+    ctx: Res<EguiCtx>,
+) {
+    let heading_font = meta
+        .theme
+        .font_styles
+        .heading
+        .with_color(meta.theme.panel.font_color);
+    let bigger_font = meta
+        .theme
+        .font_styles
+        .bigger
+        .with_color(meta.theme.panel.font_color);
+    let normal_font = meta
+        .theme
+        .font_styles
+        .normal
+        .with_color(meta.theme.panel.font_color);
+
+    let mut back_to_menu = false;
+    egui::CentralPanel::default()
+        .frame(egui::Frame::none())
+        .show(&ctx, |ui| {
+            let screen_rect = ui.max_rect();
+            let outer_margin = egui::style::Margin::symmetric(
+                screen_rect.width() * 0.15,
+                screen_rect.height() * 0.15,
+            );
+
+            BorderedFrame::new(&meta.theme.panel.border)
+                .margin(outer_margin)
+                .padding(meta.theme.panel.padding)
+                .show(ui, |ui| {
+                    ui.set_min_width(ui.available_width());
+
+                    ui.vertical_centered(|ui| {
+                        let heading = match results.winner {
+                            Some(winner) => localization.get_with(
+                                "match-winner",
+                                &fluent_args! {
+                                    "player" => winner + 1
+                                },
+                            ),
+                            None => localization.get("results"),
+                        };
+                        ui.label(heading_font.rich(heading));
+                        ui.add_space(normal_font.size);
+
+                        egui::Grid::new("match_results")
+                            .spacing(egui::vec2(normal_font.size * 2.0, normal_font.size * 0.5))
+                            .show(ui, |ui| {
+                                for column in [
+                                    "player",
+                                    "rounds-won",
+                                    "kills",
+                                    "deaths",
+                                    "suicides",
+                                    "items-picked-up",
+                                ] {
+                                    ui.label(bigger_font.rich(localization.get(column)));
+                                }
+                                ui.end_row();
+
+                                for player in &results.players {
+                                    ui.label(normal_font.rich(localization.get_with(
+                                        "player-number",
+                                        &fluent_args! {
+                                            "number" => player.player_idx + 1
+                                        },
+                                    )));
+                                    for value in [
+                                        player.rounds_won,
+                                        player.stats.kills,
+                                        player.stats.deaths,
+                                        player.stats.suicides,
+                                        player.stats.items_picked_up,
+                                    ] {
+                                        ui.label(normal_font.rich(value.to_string()));
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+
+                        ui.add_space(normal_font.size);
+
+                        if BorderedButton::themed(
+                            &meta.theme.buttons.normal,
+                            localization.get("main-menu"),
+                        )
+                        .min_size(vec2(meta.main_menu.menu_width, 0.0))
+                        .show(ui)
+                        .focus_by_default(ui)
+                        .clicked()
+                        {
+                            back_to_menu = true;
+                        }
+                    });
+                });
+        });
+
+    if back_to_menu {
+        session_options.delete = true;
+        sessions.start_menu();
+    }
+}
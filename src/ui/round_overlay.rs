@@ -0,0 +1,114 @@
+//! The round results drawn on top of the running match, which also starts the next round, or
+//! shows the match results once the match is over.
+
+use crate::prelude::*;
+
+pub fn session_plugin(session: &mut Session) {
+    session.world.init_param::<Localization<GameMeta>>();
+    session.add_system_to_stage(Update, round_overlay_system);
+}
+
+fn round_overlay_system(
+    meta: Root<GameMeta>,
+    localization: Localization<GameMeta>,
+    mut sessions: ResMut<Sessions>,
+    ctx: Res<EguiCtx>,
+) {
+    let Some(session) = sessions.get(SessionNames::GAME) else {
+        return;
+    };
+    let Some(round_state) = session
+        .world
+        .get_resource::<RoundState>()
+        .map(|x| (*x).clone())
+    else {
+        return;
+    };
+    // Online, the round may only seem to have ended because of a wrong prediction.
+    let predicted = session
+        .world
+        .get_resource::<Predicted>()
+        .is_some_and(|x| **x);
+    if round_state.has_ended() && predicted {
+        return;
+    }
+    let score = *session.world.resource::<MatchScore>();
+    let active_players = session
+        .world
+        .resource::<MatchInputs>()
+        .players
+        .iter()
+        .enumerate()
+        .filter(|(_, player)| player.active)
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    let heading = match round_state {
+        RoundState::Playing => return,
+        RoundState::NextRound { map } => {
+            sessions.start_next_round((*map).clone());
+            return;
+        }
+        RoundState::RoundOver { winner, .. } => match winner {
+            Some(winner) => localization.get_with(
+                "round-winner",
+                &fluent_args! {
+                    "player" => winner + 1
+                },
+            ),
+            None => localization.get("round-draw"),
+        },
+        RoundState::MatchOver { .. } => {
+            sessions.start_results();
+            return;
+        }
+    };
+
+    // Let the pause menu draw over the game instead.
+    if !session.active {
+        return;
+    }
+
+    egui::CentralPanel::default()
+        .frame(egui::Frame::none())
+        .show(&ctx, |ui| {
+            let screen_rect = ui.max_rect();
+            let x_margin = (screen_rect.width() - meta.main_menu.menu_width) / 2.0;
+            let outer_margin =
+                egui::style::Margin::symmetric(x_margin, screen_rect.height() * 0.25);
+
+            BorderedFrame::new(&meta.theme.panel.border)
+                .margin(outer_margin)
+                .padding(meta.theme.panel.padding)
+                .show(ui, |ui| {
+                    ui.set_min_width(ui.available_width());
+
+                    ui.vertical_centered(|ui| {
+                        ui.label(
+                            meta.theme
+                                .font_styles
+                                .heading
+                                .rich(heading)
+                                .color(meta.theme.panel.font_color),
+                        );
+                        ui.add_space(10.0);
+
+                        for &i in &active_players {
+                            ui.label(
+                                meta.theme
+                                    .font_styles
+                                    .bigger
+                                    .rich(localization.get_with(
+                                        "player-score",
+                                        &fluent_args! {
+                                            "player" => i + 1,
+                                            "score" => score.scores[i]
+                                        },
+                                    ))
+                                    .color(meta.theme.panel.font_color),
+                            );
+                        }
+                    });
+                });
+        });
+}